@@ -0,0 +1,17 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use serde::{Deserialize, Serialize};
+
+/// AccessX style keyboard filters, applied to compositor shortcuts and client input alike
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyboardAccessibility {
+    /// Tapping a modifier applies it to the next key press, tapping it twice locks it
+    #[serde(default)]
+    pub sticky_keys: bool,
+    /// Only accept key presses held down for this many milliseconds
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub slow_keys: Option<u32>,
+    /// Ignore presses of a key within this many milliseconds after it was released
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub bounce_keys: Option<u32>,
+}
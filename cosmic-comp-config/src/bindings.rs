@@ -0,0 +1,102 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use cosmic_settings_config::shortcuts;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::workspace::LayoutStrategy;
+
+/// Actions specific to cosmic-comp, that are not covered by the shortcuts of cosmic-settings.
+///
+/// They are bound via the `compositor_bindings` key, mapping key combinations
+/// like `"Super+Shift+m"` or pointer buttons and scroll directions like `"Super+MouseMiddle"`
+/// or `"Super+ScrollUp"` to an action.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CompositorAction {
+    /// Trigger a shortcut action of cosmic-settings, e.g. to bind it to a pointer button
+    Shortcut(shortcuts::Action),
+    /// Set the layout strategy of the active workspace
+    SetLayoutStrategy(LayoutStrategy),
+    /// Switch the active workspace to the next layout strategy
+    CycleLayoutStrategy,
+    /// Switch the tiled windows of the active workspace between the tiling tree
+    /// and a horizontally scrolling strip of columns
+    ToggleScrolling,
+    /// Keep the active workspace around, even if it has no windows
+    TogglePinWorkspace,
+    /// Hide the focused window in the scratchpad
+    MoveToScratchpad,
+    /// Show the most recently hidden scratchpad window on the active output, or hide it again
+    ToggleScratchpad,
+    /// Like `ToggleScratchpad`, but only considers windows with the given app_id
+    ToggleScratchpadApp(String),
+    /// Move the active tab of the focused stack, without leaving the stack
+    MoveTab(Direction),
+    /// Move the active tab of the focused tiled stack out into a new split
+    PullOutTab(Direction),
+    /// Merge the focused tiled window into the window next to it, creating a stack if necessary
+    MergeIntoStack(Direction),
+    /// Resize the focused tiled window to a fraction of the split it is in
+    ResizeToRatio(SplitRatio),
+    /// Evenly distribute the space of all splits around the focused tiled window or group
+    BalanceSplits,
+    /// Resize and/or move the focused floating window.
+    /// The position is relative to the top-left corner of the output.
+    SetFloatingGeometry {
+        #[serde(default)]
+        size: Option<(i32, i32)>,
+        #[serde(default)]
+        position: Option<(i32, i32)>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SplitRatio {
+    OneThird,
+    Half,
+    TwoThirds,
+}
+
+impl SplitRatio {
+    pub fn as_f64(&self) -> f64 {
+        match self {
+            SplitRatio::OneThird => 1. / 3.,
+            SplitRatio::Half => 0.5,
+            SplitRatio::TwoThirds => 2. / 3.,
+        }
+    }
+}
+
+/// A named set of key bindings, that replaces all other bindings while the mode is active,
+/// like the resize mode of i3. Escape always returns to the regular bindings.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BindingMode {
+    /// Binding entering the mode, like `"Super+r"`
+    pub enter: String,
+    /// Bindings of the mode, like `"h"` or `"Shift+Left"`
+    #[serde(default)]
+    pub bindings: HashMap<String, ModeAction>,
+    /// Leave the mode again after the next key press. This turns the mode into a key sequence,
+    /// like `Super+w` followed by `h`.
+    #[serde(default)]
+    pub oneshot: bool,
+}
+
+/// Action of a binding inside a [`BindingMode`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ModeAction {
+    Shortcut(shortcuts::Action),
+    Compositor(CompositorAction),
+    /// Switch to another binding mode
+    EnterMode(String),
+    /// Return to the regular bindings
+    ExitMode,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
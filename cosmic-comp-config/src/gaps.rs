@@ -0,0 +1,63 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Gap and border settings. Unset values fall back to the less specific settings
+/// and ultimately to the values of the cosmic theme.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GapSettings {
+    /// Gap between tiled windows and the edges of the output
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub outer: Option<u32>,
+    /// Gap between tiled windows
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub inner: Option<u32>,
+    /// Thickness of the active window hint
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub active_hint: Option<u32>,
+    /// Remove all gaps, if only a single window is tiled
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub smart_gaps: Option<bool>,
+}
+
+impl GapSettings {
+    /// Returns `self` with all unset values taken from `fallback`
+    pub fn or(self, fallback: GapSettings) -> GapSettings {
+        GapSettings {
+            outer: self.outer.or(fallback.outer),
+            inner: self.inner.or(fallback.inner),
+            active_hint: self.active_hint.or(fallback.active_hint),
+            smart_gaps: self.smart_gaps.or(fallback.smart_gaps),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GapsConfig {
+    /// Settings for all outputs and workspaces
+    #[serde(default)]
+    pub default: GapSettings,
+    /// Overrides per output, keyed by connector name
+    #[serde(skip_serializing_if = "HashMap::is_empty", default)]
+    pub outputs: HashMap<String, GapSettings>,
+    /// Overrides per workspace, keyed by workspace number (starting at 1)
+    #[serde(skip_serializing_if = "HashMap::is_empty", default)]
+    pub workspaces: HashMap<u32, GapSettings>,
+}
+
+impl GapsConfig {
+    /// Resolves the settings of a given output and optionally a workspace on that output.
+    ///
+    /// Workspace overrides take precedence over output overrides.
+    pub fn settings(&self, output: &str, workspace: Option<u32>) -> GapSettings {
+        let mut settings = self.default;
+        if let Some(output) = self.outputs.get(output) {
+            settings = output.or(settings);
+        }
+        if let Some(workspace) = workspace.and_then(|idx| self.workspaces.get(&idx)) {
+            settings = workspace.or(settings);
+        }
+        settings
+    }
+}
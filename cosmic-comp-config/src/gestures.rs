@@ -0,0 +1,28 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use serde::{Deserialize, Serialize};
+
+use crate::bindings::Direction;
+
+/// A touchpad gesture, that can be bound to a shortcut action via the `gestures` key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Gesture {
+    /// Number of fingers, gestures with less than 3 fingers are only recognized for pinches and holds
+    pub fingers: u32,
+    pub kind: GestureKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GestureKind {
+    /// Swipe in the given direction, as if natural scrolling was disabled
+    Swipe(Direction),
+    PinchIn,
+    PinchOut,
+    Hold,
+}
+
+impl Gesture {
+    pub fn new(fingers: u32, kind: GestureKind) -> Gesture {
+        Gesture { fingers, kind }
+    }
+}
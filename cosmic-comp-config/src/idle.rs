@@ -0,0 +1,14 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use serde::{Deserialize, Serialize};
+
+/// Power actions of the compositor after a period without input
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdleConfig {
+    /// Dim all outputs after this many seconds
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub dim_timeout: Option<u32>,
+    /// Turn all outputs off after this many seconds
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub screen_off_timeout: Option<u32>,
+}
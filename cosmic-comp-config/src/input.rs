@@ -5,6 +5,8 @@
 pub use input::{AccelProfile, ClickMethod, ScrollMethod, TapButtonMap};
 use serde::{Deserialize, Serialize};
 
+use crate::XkbConfig;
+
 #[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
 pub struct InputConfig {
     pub state: DeviceState,
@@ -29,6 +31,31 @@ pub struct InputConfig {
     pub tap_config: Option<TapConfig>,
     #[serde(skip_serializing_if = "Option::is_none", default)]
     pub map_to_output: Option<String>,
+    /// Maps absolute devices to an area of the desktop, instead of `map_to_output`
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub map_to_area: Option<MapToArea>,
+    /// Letterbox the mapped area to the aspect ratio of the device
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub keep_aspect_ratio: Option<bool>,
+    /// Keyboard layout used while typing on this device, instead of the layout of the seat
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub xkb_config: Option<XkbConfig>,
+}
+
+/// Area of the desktop an absolute device (touchscreen or tablet) is mapped to
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+pub enum MapToArea {
+    /// Rectangle of the global space in logical coordinates
+    Region {
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+    },
+    /// Geometry of the focused window
+    FocusedWindow,
+    /// Bounding box of all outputs
+    AllOutputs,
 }
 
 #[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
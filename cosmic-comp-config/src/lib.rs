@@ -1,10 +1,21 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
 use cosmic_config::{cosmic_config_derive::CosmicConfigEntry, CosmicConfigEntry};
+use cosmic_settings_config::shortcuts;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+pub mod accessibility;
+pub mod bindings;
+pub mod gaps;
+pub mod gestures;
+pub mod idle;
 pub mod input;
+pub mod night_light;
+pub mod seats;
+pub mod shortcuts_inhibit;
+pub mod switches;
+pub mod window_rules;
 pub mod workspace;
 
 #[derive(Clone, Debug, PartialEq, CosmicConfigEntry)]
@@ -15,16 +26,48 @@ pub struct CosmicCompConfig {
     pub input_touchpad: input::InputConfig,
     pub input_devices: HashMap<String, input::InputConfig>,
     pub xkb_config: XkbConfig,
+    /// Additional seats by name, devices not assigned to any of them stay on `seat-0`
+    pub seats: HashMap<String, seats::SeatConfig>,
+    /// Remember the active keyboard layout per window and restore it on focus change
+    pub keyboard_layout_per_window: bool,
+    /// Sticky, slow and bounce keys
+    pub keyboard_accessibility: accessibility::KeyboardAccessibility,
     /// Autotiling enabled
     pub autotile: bool,
     /// Determines the behavior of the autotile variable
     /// If set to Global, autotile applies to all windows in all workspaces
     /// If set to PerWorkspace, autotile only applies to new windows, and new workspaces
     pub autotile_behavior: TileBehavior,
+    /// Layout strategy of tiled windows on new workspaces
+    pub layout_strategy: workspace::LayoutStrategy,
+    /// Settings of the master/stack layout strategy
+    pub master_stack: workspace::MasterStackConfig,
     /// Active hint enabled
     pub active_hint: bool,
+    /// How much unfocused windows are faded out, between 0.0 (disabled) and 1.0
+    pub dim_inactive: f32,
+    /// Gaps and active hint thickness, overriding the theme
+    pub gaps: gaps::GapsConfig,
     /// Let X11 applications scale themselves
     pub descale_xwayland: bool,
+    /// Rules applied to newly mapped windows
+    pub window_rules: Vec<window_rules::WindowRule>,
+    /// Key and pointer bindings for compositor specific actions
+    pub compositor_bindings: HashMap<String, bindings::CompositorAction>,
+    /// Named binding modes and key sequences
+    pub binding_modes: HashMap<String, bindings::BindingMode>,
+    /// Touchpad gesture bindings, overriding the default workspace swipes
+    pub gestures: HashMap<gestures::Gesture, shortcuts::Action>,
+    /// Actions bound to tablet pad buttons, by button number
+    pub tablet_pad_bindings: HashMap<u32, shortcuts::Action>,
+    /// Lid and tablet mode switch policy
+    pub switches: switches::SwitchConfig,
+    /// Which clients may inhibit shortcuts and how to escape them
+    pub shortcuts_inhibit: shortcuts_inhibit::ShortcutsInhibitConfig,
+    /// Dimming and turning off outputs while idle
+    pub idle: idle::IdleConfig,
+    /// Warm color temperature schedule
+    pub night_light: night_light::NightLightConfig,
 }
 
 impl Default for CosmicCompConfig {
@@ -47,10 +90,26 @@ impl Default for CosmicCompConfig {
             },
             input_devices: Default::default(),
             xkb_config: Default::default(),
+            seats: HashMap::new(),
+            keyboard_layout_per_window: false,
+            keyboard_accessibility: Default::default(),
             autotile: Default::default(),
             autotile_behavior: Default::default(),
+            layout_strategy: Default::default(),
+            master_stack: Default::default(),
             active_hint: true,
+            dim_inactive: 0.0,
+            gaps: Default::default(),
             descale_xwayland: false,
+            window_rules: window_rules::default_rules(),
+            compositor_bindings: HashMap::new(),
+            binding_modes: HashMap::new(),
+            gestures: HashMap::new(),
+            tablet_pad_bindings: HashMap::new(),
+            switches: switches::SwitchConfig::default(),
+            shortcuts_inhibit: shortcuts_inhibit::ShortcutsInhibitConfig::default(),
+            idle: idle::IdleConfig::default(),
+            night_light: night_light::NightLightConfig::default(),
         }
     }
 }
@@ -0,0 +1,48 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use serde::{Deserialize, Serialize};
+
+/// When the night light is active
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum NightLightSchedule {
+    /// From sunset to sunrise at the given location, in degrees
+    SunsetToSunrise { latitude: f64, longitude: f64 },
+    /// Between two local times of the day, given as `(hour, minute)`
+    Fixed { start: (u8, u8), end: (u8, u8) },
+}
+
+impl Default for NightLightSchedule {
+    fn default() -> Self {
+        NightLightSchedule::Fixed {
+            start: (21, 0),
+            end: (7, 0),
+        }
+    }
+}
+
+/// Built-in warm color temperature for the night.
+/// Gamma ramps set by clients take precedence.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct NightLightConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Color temperature in Kelvin, while the night light is active
+    #[serde(default = "default_temperature")]
+    pub temperature: u32,
+    #[serde(default)]
+    pub schedule: NightLightSchedule,
+}
+
+impl Default for NightLightConfig {
+    fn default() -> Self {
+        NightLightConfig {
+            enabled: false,
+            temperature: default_temperature(),
+            schedule: NightLightSchedule::default(),
+        }
+    }
+}
+
+fn default_temperature() -> u32 {
+    4000
+}
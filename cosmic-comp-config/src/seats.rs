@@ -0,0 +1,17 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use serde::{Deserialize, Serialize};
+
+use crate::XkbConfig;
+
+/// An additional seat with its own focus, cursor and keyboard layout
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SeatConfig {
+    /// Devices assigned to the seat, matched by device name, device node,
+    /// sysfs path or udev `ID_PATH`
+    #[serde(default)]
+    pub devices: Vec<String>,
+    /// Keyboard layout of the seat, falls back to `xkb_config`
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub xkb_config: Option<XkbConfig>,
+}
@@ -0,0 +1,42 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use serde::{Deserialize, Serialize};
+
+/// Which clients may inhibit compositor shortcuts, like virtual machine viewers
+/// or remote desktop clients usually do
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ShortcutsInhibitPolicy {
+    /// Every client may inhibit shortcuts
+    #[default]
+    AllowAll,
+    /// Only clients with one of the given app_ids may inhibit shortcuts
+    AllowList(Vec<String>),
+    /// Ask the first time a client tries to inhibit shortcuts,
+    /// the answer is remembered until the session ends
+    AskOnce,
+}
+
+/// Policy of the keyboard shortcuts inhibit protocol
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShortcutsInhibitConfig {
+    #[serde(default)]
+    pub policy: ShortcutsInhibitPolicy,
+    /// Binding like `"Super+Escape"`, that always reaches the compositor, while the focused
+    /// window has a shortcuts inhibitor. It toggles the inhibitor of the focused window.
+    /// Without an inhibitor, the binding triggers the regular shortcuts.
+    #[serde(default = "default_escape_binding")]
+    pub escape_binding: String,
+}
+
+impl Default for ShortcutsInhibitConfig {
+    fn default() -> ShortcutsInhibitConfig {
+        ShortcutsInhibitConfig {
+            policy: ShortcutsInhibitPolicy::default(),
+            escape_binding: default_escape_binding(),
+        }
+    }
+}
+
+fn default_escape_binding() -> String {
+    String::from("Super+Escape")
+}
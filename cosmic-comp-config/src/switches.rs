@@ -0,0 +1,53 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use serde::{Deserialize, Serialize};
+
+/// Action taken when the laptop lid is closed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LidAction {
+    Nothing,
+    /// Disable the builtin output, moving its workspaces to the remaining outputs
+    DisableOutput,
+    Lock,
+    Suspend,
+}
+
+/// Handling of the lid and tablet mode switches
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SwitchConfig {
+    /// Action when closing the lid while an external output is connected
+    #[serde(default = "default_lid_close_docked")]
+    pub lid_close_docked: LidAction,
+    /// Action when closing the lid without any external outputs
+    #[serde(default = "default_lid_close")]
+    pub lid_close: LidAction,
+    /// Disable touchpads while in tablet mode
+    #[serde(default = "default_true")]
+    pub tablet_mode_disable_touchpad: bool,
+    /// Enlarge touch targets like stack tabs while in tablet mode
+    #[serde(default = "default_true")]
+    pub tablet_mode_touch_friendly: bool,
+}
+
+impl Default for SwitchConfig {
+    fn default() -> SwitchConfig {
+        SwitchConfig {
+            lid_close_docked: default_lid_close_docked(),
+            lid_close: default_lid_close(),
+            tablet_mode_disable_touchpad: true,
+            tablet_mode_touch_friendly: true,
+        }
+    }
+}
+
+fn default_lid_close_docked() -> LidAction {
+    LidAction::DisableOutput
+}
+
+fn default_lid_close() -> LidAction {
+    LidAction::Suspend
+}
+
+fn default_true() -> bool {
+    true
+}
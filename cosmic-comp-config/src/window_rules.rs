@@ -0,0 +1,120 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use serde::{Deserialize, Serialize};
+
+/// A single window rule.
+///
+/// Every condition set in `matches` has to be fulfilled for the rule to apply.
+/// If multiple rules match a window, they are applied in order, with later
+/// rules overriding properties set by earlier ones.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WindowRule {
+    #[serde(rename = "match", default)]
+    pub matches: WindowMatch,
+    /// Map the window floating (`true`) or tiled (`false`)
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub floating: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub sticky: Option<bool>,
+    /// Workspace number (starting at 1) the window should be opened on
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub workspace: Option<u32>,
+    /// Connector name of the output the window should be opened on
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub output: Option<String>,
+    /// Initial size of floating windows
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub size: Option<(i32, i32)>,
+    /// Initial position of floating windows relative to the output
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub position: Option<(i32, i32)>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub maximized: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub fullscreen: Option<bool>,
+    /// Opacity between 0.0 and 1.0
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub opacity: Option<f32>,
+}
+
+/// Conditions of a [`WindowRule`].
+///
+/// String conditions are regular expressions, that need to match (parts of) the respective property.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WindowMatch {
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub app_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub x11_class: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub x11_window_type: Option<X11WindowType>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub is_dialog: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum X11WindowType {
+    DropdownMenu,
+    Dialog,
+    Menu,
+    Notification,
+    Normal,
+    PopupMenu,
+    Splash,
+    Toolbar,
+    Tooltip,
+    Utility,
+}
+
+fn floating(app_id: Option<&str>, title: Option<&str>) -> WindowRule {
+    WindowRule {
+        matches: WindowMatch {
+            app_id: app_id.map(String::from),
+            title: title.map(String::from),
+            ..Default::default()
+        },
+        floating: Some(true),
+        ..Default::default()
+    }
+}
+
+/// Rules for applications known to not work well when tiled
+pub fn default_rules() -> Vec<WindowRule> {
+    vec![
+        floating(Some("Authy Desktop"), None),
+        floating(Some("Com.github.amezin.ddterm"), None),
+        floating(Some("Com.github.donadigo.eddy"), None),
+        floating(None, Some("Discord Updater")),
+        floating(Some("Enpass"), Some("Enpass Assistant")),
+        floating(Some("Gjs"), Some("Settings")),
+        floating(Some("Gnome-initial-setup"), None),
+        floating(Some("Gnome-terminal"), Some("Preferences – General")),
+        floating(Some("Guake"), None),
+        floating(Some("Io.elementary.sideload"), None),
+        floating(Some("KotatogramDesktop"), Some("Media viewer")),
+        floating(Some("Mozilla VPN"), None),
+        floating(Some("update-manager"), Some("Software Updater")),
+        floating(Some("Solaar"), None),
+        floating(Some("Steam"), Some("^.*?(Guard|Login).*")),
+        floating(None, Some("Steam")),
+        floating(Some("TelegramDesktop"), Some("Media viewer")),
+        floating(Some("Zotero"), Some("Quick Format Citation")),
+        floating(Some("gjs"), None),
+        floating(Some("gnome-screenshot"), None),
+        floating(Some("ibus-.*"), None),
+        floating(Some("jetbrains-toolbox"), None),
+        floating(Some("jetbrains-webstorm"), Some("Customize WebStorm")),
+        floating(Some("jetbrains-webstorm"), Some("License Activation")),
+        floating(Some("jetbrains-webstorm"), Some("Welcome to WebStorm")),
+        floating(Some("krunner"), None),
+        floating(Some("pritunl"), None),
+        floating(Some("re.sonny.Junction"), None),
+        floating(Some("system76-driver"), None),
+        floating(Some("tilda"), None),
+        floating(Some("zoom"), None),
+        floating(Some("^.*?action=join.*$"), None),
+        floating(None, Some("wl-clipboard")),
+    ]
+}
@@ -11,6 +11,9 @@ pub struct WorkspaceConfig {
     pub workspace_mode: WorkspaceMode,
     #[serde(default = "default_workspace_layout")]
     pub workspace_layout: WorkspaceLayout,
+    /// Named or pinned workspaces, recreated on startup
+    #[serde(default)]
+    pub persistent: Vec<PersistentWorkspace>,
 }
 
 impl Default for WorkspaceConfig {
@@ -18,10 +21,26 @@ impl Default for WorkspaceConfig {
         Self {
             workspace_mode: WorkspaceMode::OutputBound,
             workspace_layout: WorkspaceLayout::Vertical,
+            persistent: Vec::new(),
         }
     }
 }
 
+/// Workspace with properties, that outlive the compositor
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PersistentWorkspace {
+    /// Connector name of the output the workspace belongs to
+    pub output: String,
+    /// Position of the workspace on its output, starting at 0
+    pub index: usize,
+    /// Name used instead of the number of the workspace
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Keep the workspace, even if it has no windows
+    #[serde(default)]
+    pub pinned: bool,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum WorkspaceMode {
     OutputBound,
@@ -33,3 +52,58 @@ pub enum WorkspaceLayout {
     Vertical,
     Horizontal,
 }
+
+/// Strategy used to place tiled windows
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LayoutStrategy {
+    /// New windows split the focused window, the layout is otherwise left to the user
+    #[default]
+    Manual,
+    /// Master windows on the left, all other windows stacked on the right
+    MasterStack,
+    /// Every window splits the space of the previous one, alternating the orientation
+    Dwindle,
+    /// All windows side by side in columns of equal width
+    Columns,
+    /// All windows share the whole output as tabs of a single stack
+    Monocle,
+}
+
+impl LayoutStrategy {
+    pub fn next(&self) -> LayoutStrategy {
+        match self {
+            LayoutStrategy::Manual => LayoutStrategy::MasterStack,
+            LayoutStrategy::MasterStack => LayoutStrategy::Dwindle,
+            LayoutStrategy::Dwindle => LayoutStrategy::Columns,
+            LayoutStrategy::Columns => LayoutStrategy::Monocle,
+            LayoutStrategy::Monocle => LayoutStrategy::Manual,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MasterStackConfig {
+    /// Number of windows in the master area
+    #[serde(default = "default_masters")]
+    pub masters: u32,
+    /// Share of the output width used by the master area, between 0.0 and 1.0
+    #[serde(default = "default_master_ratio")]
+    pub ratio: f32,
+}
+
+fn default_masters() -> u32 {
+    1
+}
+
+fn default_master_ratio() -> f32 {
+    0.5
+}
+
+impl Default for MasterStackConfig {
+    fn default() -> Self {
+        Self {
+            masters: default_masters(),
+            ratio: default_master_ratio(),
+        }
+    }
+}
@@ -15,9 +15,16 @@ window-menu-stack = Create window stack
 window-menu-unstack-all = Unstack windows
 window-menu-unstack = Unstack window
 window-menu-sticky = Sticky window
+window-menu-opacity = Opacity
 window-menu-close = Close
 window-menu-close-all = Close all windows
 window-menu-resize-edge-top = Top
 window-menu-resize-edge-left = Left
 window-menu-resize-edge-right = Right
-window-menu-resize-edge-bottom = Bottom
\ No newline at end of file
+window-menu-resize-edge-bottom = Bottom
+
+shortcuts-inhibit-allow = Allow { $app } to use all shortcuts
+shortcuts-inhibit-deny = Keep system shortcuts for { $app }
+
+session-locked = Session locked
+session-locked-fallback = The lock screen stopped unexpectedly. The session stays locked until it is restarted.
@@ -1,17 +1,23 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
+use crate::shell::gamma::GammaRamp;
+
 use anyhow::{anyhow, Result};
-use smithay::reexports::drm::control::{
-    atomic::AtomicModeReq,
-    connector::{self, State as ConnectorState},
-    crtc,
-    dumbbuffer::DumbBuffer,
-    property, AtomicCommitFlags, Device as ControlDevice, Mode, ModeFlags, PlaneType,
-    ResourceHandle,
+use smithay::{
+    backend::drm::DrmSurface,
+    reexports::drm::control::{
+        atomic::AtomicModeReq,
+        connector::{self, State as ConnectorState},
+        crtc,
+        dumbbuffer::DumbBuffer,
+        property, AtomicCommitFlags, Device as ControlDevice, Mode, ModeFlags, PlaneType,
+        ResourceHandle,
+    },
 };
 use std::{
     collections::HashMap,
     ops::Range,
+    os::fd::AsFd,
     panic::{catch_unwind, AssertUnwindSafe},
 };
 
@@ -380,6 +386,58 @@ pub fn set_vrr(
     }
 }
 
+pub fn get_gamma_size(dev: &impl ControlDevice, crtc: crtc::Handle) -> Result<Option<u32>> {
+    let Ok((val_type, val)) = get_property_val(dev, crtc, "GAMMA_LUT_SIZE") else {
+        return Ok(None);
+    };
+
+    match val_type.convert_value(val) {
+        property::Value::UnsignedRange(size) => Ok(Some(size as u32)),
+        _ => Err(anyhow!("GAMMA_LUT_SIZE has wrong value type")),
+    }
+}
+
+/// Sets the gamma lookup table of the crtc of `surface`.
+///
+/// Has to be called from the thread driving the surface, so it doesn't race its page flips.
+pub fn set_gamma_lut(surface: &DrmSurface, ramp: Option<&GammaRamp>) -> Result<()> {
+    let crtc = surface.crtc();
+    let prop = get_prop(surface, crtc, "GAMMA_LUT")?;
+    let blob = match ramp {
+        Some(ramp) => {
+            // array of `struct drm_color_lut`
+            let mut lut = ramp
+                .red()
+                .iter()
+                .zip(ramp.green())
+                .zip(ramp.blue())
+                .flat_map(|((red, green), blue)| [*red, *green, *blue, 0])
+                .collect::<Vec<u16>>();
+            drm_ffi::mode::create_property_blob(
+                surface.as_fd(),
+                bytemuck::cast_slice_mut(&mut lut),
+            )?
+            .blob_id as u64
+        }
+        None => 0,
+    };
+
+    let value = property::Value::Blob(blob);
+    let res = if surface.is_legacy() {
+        surface.set_property(crtc, prop, value.into())
+    } else {
+        let mut req = AtomicModeReq::new();
+        req.add_property(crtc, prop, value);
+        // blocking, so the next page flip can't collide with it
+        surface.atomic_commit(AtomicCommitFlags::empty(), req)
+    };
+    if blob != 0 {
+        // the crtc state keeps its own reference
+        let _ = surface.destroy_property_blob(blob);
+    }
+    res.map_err(Into::into)
+}
+
 pub fn get_max_bpc(
     dev: &impl ControlDevice,
     conn: connector::Handle,
@@ -1,6 +1,11 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
-use crate::{config::OutputState, shell::Shell, state::BackendData, utils::prelude::*};
+use crate::{
+    config::OutputState,
+    shell::{gamma::GammaRamp, Shell},
+    state::BackendData,
+    utils::prelude::*,
+};
 
 use anyhow::{Context, Result};
 use calloop::LoopSignal;
@@ -60,6 +65,8 @@ pub struct KmsState {
 
     session: LibSeatSession,
     libinput: Libinput,
+    /// Keeps logind from acting on the lid switch itself, while the session is active
+    lid_switch_inhibitor: Option<zbus::zvariant::OwnedFd>,
 }
 
 pub fn init_backend(
@@ -126,6 +133,7 @@ pub fn init_backend(
 
         session,
         libinput: libinput_context,
+        lid_switch_inhibitor: inhibit_lid_switch(),
     });
 
     // start x11
@@ -155,14 +163,26 @@ fn init_libinput(
 
     evlh.insert_source(libinput_backend, move |mut event, _, state| {
         if let InputEvent::DeviceAdded { ref mut device } = &mut event {
-            state.common.config.read_device(device);
+            state
+                .common
+                .config
+                .read_device(device, state.common.tablet_mode);
             state
                 .backend
                 .kms()
                 .input_devices
                 .insert(device.name().into(), device.clone());
+            // switches only report changes, the lid might already be closed
+            if device.has_capability(input::DeviceCapability::Switch) {
+                match crate::dbus::logind::lid_closed() {
+                    Ok(closed) => state.sync_lid_state(closed),
+                    Err(err) => warn!(?err, "Failed to read the lid state."),
+                }
+            }
         } else if let InputEvent::DeviceRemoved { device } = &event {
             state.backend.kms().input_devices.remove(device.name());
+        } else if let InputEvent::Special(input::event::Event::TabletPad(pad_event)) = &event {
+            state.process_tablet_pad_event(pad_event);
         }
 
         state.process_input_event(event, true);
@@ -224,6 +244,16 @@ fn software_renderer() -> anyhow::Result<GlowRenderer> {
     unsafe { Ok(GlowRenderer::new(context)?) }
 }
 
+fn inhibit_lid_switch() -> Option<zbus::zvariant::OwnedFd> {
+    match crate::dbus::logind::inhibit_lid_switch() {
+        Ok(fd) => Some(fd),
+        Err(err) => {
+            warn!(?err, "Failed to inhibit logind lid switch handling.");
+            None
+        }
+    }
+}
+
 fn init_udev(
     seat: String,
     evlh: &LoopHandle<'static, State>,
@@ -244,7 +274,9 @@ fn init_udev(
                 .with_context(|| format!("Failed to remove drm device: {}", device_id)),
         } {
             Ok(()) => {
-                trace!("Successfully handled udev event.")
+                trace!("Successfully handled udev event.");
+                // outputs might have been connected while the lid is closed
+                state.apply_lid_state();
             }
             Err(err) => {
                 error!(?err, "Error while handling udev event.")
@@ -266,6 +298,7 @@ impl State {
         loop_signal: LoopSignal,
     ) {
         let backend = self.backend.kms();
+        backend.lid_switch_inhibitor = inhibit_lid_switch();
 
         // resume input
         if let Err(err) = backend.libinput.resume() {
@@ -323,6 +356,8 @@ impl State {
 
     fn pause_session(&mut self) {
         let backend = self.backend.kms();
+        // let logind handle the lid, while nobody else does
+        backend.lid_switch_inhibitor = None;
         backend.libinput.suspend();
         for device in backend.drm_devices.values_mut() {
             device.drm.pause();
@@ -409,6 +444,37 @@ impl KmsState {
         }
     }
 
+    pub fn set_output_power(&mut self, output: &Output, on: bool) {
+        for surface in self
+            .drm_devices
+            .values_mut()
+            .flat_map(|d| d.surfaces.values_mut())
+            .filter(|s| s.output == *output)
+        {
+            surface.set_dpms(on);
+        }
+    }
+
+    pub fn gamma_size(&self, output: &Output) -> Option<u32> {
+        self.drm_devices.values().find_map(|device| {
+            let surface = device.surfaces.values().find(|s| s.output == *output)?;
+            drm_helpers::get_gamma_size(&device.drm, surface.crtc)
+                .ok()
+                .flatten()
+        })
+    }
+
+    pub fn set_gamma(&mut self, output: &Output, ramp: Option<&GammaRamp>) {
+        for surface in self
+            .drm_devices
+            .values_mut()
+            .flat_map(|d| d.surfaces.values_mut())
+            .filter(|s| s.output == *output)
+        {
+            surface.set_gamma(ramp.cloned());
+        }
+    }
+
     pub fn target_node_for_output(&self, output: &Output) -> Option<DrmNode> {
         self.drm_devices
             .values()
@@ -5,7 +5,10 @@ use crate::{
         element::{CosmicElement, DamageElement},
         init_shaders, workspace_elements, CursorMode, ElementFilter, GlMultiRenderer, CLEAR_COLOR,
     },
-    shell::Shell,
+    shell::{
+        gamma::{GammaRamp, OutputGamma},
+        Shell,
+    },
     state::SurfaceDmabufFeedback,
     utils::{prelude::*, quirks::workspace_overview_is_open},
     wayland::{
@@ -123,6 +126,12 @@ pub struct SurfaceThreadState {
     target_node: DrmNode,
     active: Arc<AtomicBool>,
     compositor: Option<GbmDrmCompositor>,
+    /// Whether the output is turned on, rendering stops while it is off
+    powered: bool,
+    /// Gamma ramp of the crtc, `None` for the default one
+    gamma: Option<GammaRamp>,
+    /// Whether `gamma` has to be committed before the next frame
+    gamma_pending: bool,
 
     state: QueueState,
     timings: Timings,
@@ -237,6 +246,8 @@ pub enum ThreadCommand {
     VBlank(Option<DrmEventMetadata>),
     ScheduleRender,
     SetMode(Mode, SyncSender<Result<()>>),
+    SetDpms(bool),
+    SetGamma(Option<GammaRamp>),
     End,
 }
 
@@ -395,6 +406,14 @@ impl Surface {
         rx.recv().context("Surface thread died")?
     }
 
+    pub fn set_dpms(&mut self, on: bool) {
+        let _ = self.thread_command.send(ThreadCommand::SetDpms(on));
+    }
+
+    pub fn set_gamma(&mut self, ramp: Option<GammaRamp>) {
+        let _ = self.thread_command.send(ThreadCommand::SetGamma(ramp));
+    }
+
     pub fn suspend(&mut self) {
         let _ = self.thread_command.send(ThreadCommand::Suspend);
     }
@@ -481,6 +500,11 @@ fn surface_thread(
         target_node,
         active,
         compositor: None,
+        powered: true,
+        gamma: OutputGamma::get(&output)
+            .ramp()
+            .map(|ramp| GammaRamp::clone(&ramp)),
+        gamma_pending: false,
 
         state: QueueState::Idle,
         timings: Timings::new(None, false),
@@ -540,6 +564,8 @@ fn surface_thread(
                     let _ = result.send(Err(anyhow::anyhow!("Set mode with inactive surface")));
                 }
             }
+            Event::Msg(ThreadCommand::SetDpms(on)) => state.set_dpms(on),
+            Event::Msg(ThreadCommand::SetGamma(ramp)) => state.set_gamma(ramp),
             Event::Closed | Event::Msg(ThreadCommand::End) => {
                 signal.stop();
                 signal.wakeup();
@@ -555,7 +581,10 @@ impl SurfaceThreadState {
     fn suspend(&mut self) {
         self.active.store(false, Ordering::SeqCst);
         let _ = self.compositor.take();
+        self.cancel_redraw();
+    }
 
+    fn cancel_redraw(&mut self) {
         match std::mem::replace(&mut self.state, QueueState::Idle) {
             QueueState::Idle => {}
             QueueState::Queued(token) | QueueState::WaitingForEstimatedVBlank(token) => {
@@ -572,6 +601,32 @@ impl SurfaceThreadState {
         };
     }
 
+    fn set_dpms(&mut self, on: bool) {
+        if self.powered == on {
+            return;
+        }
+        self.powered = on;
+
+        if on {
+            // the next frame enables the crtc again
+            self.queue_redraw(true);
+        } else {
+            self.cancel_redraw();
+            if let Some(compositor) = self.compositor.as_mut() {
+                if let Err(err) = compositor.clear() {
+                    warn!(?err, "Failed to turn off output.");
+                }
+            }
+        }
+    }
+
+    fn set_gamma(&mut self, ramp: Option<GammaRamp>) {
+        self.gamma = ramp;
+        // committed with the next frame, to not collide with a pending page flip
+        self.gamma_pending = true;
+        self.queue_redraw(false);
+    }
+
     fn resume(
         &mut self,
         surface: DrmSurface,
@@ -604,6 +659,8 @@ impl SurfaceThreadState {
                 1_000.0 / drm_helpers::calculate_refresh_rate(surface.pending_mode()) as f64,
             )));
         self.timings.set_vrr(vrr);
+        // the crtc might have been used by someone else in the meantime
+        self.gamma_pending = self.gamma.is_some();
 
         match DrmCompositor::new(
             &self.output,
@@ -768,6 +825,9 @@ impl SurfaceThreadState {
         let Some(_compositor) = self.compositor.as_mut() else {
             return;
         };
+        if !self.powered {
+            return;
+        }
 
         if let QueueState::WaitingForVBlank { .. } = &self.state {
             // We're waiting for VBlank, request a redraw afterwards.
@@ -845,6 +905,13 @@ impl SurfaceThreadState {
             return Ok(());
         };
 
+        if mem::take(&mut self.gamma_pending) {
+            if let Err(err) = drm_helpers::set_gamma_lut(compositor.surface(), self.gamma.as_ref())
+            {
+                warn!(?err, "Failed to set gamma ramp of {}.", self.output.name());
+            }
+        }
+
         let render_node = render_node_for_output(
             self.mirroring.as_ref().unwrap_or(&self.output),
             &self.primary_node,
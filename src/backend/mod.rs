@@ -65,6 +65,7 @@ pub fn init_backend_auto(
             .unwrap()
             .seats
             .add_seat(initial_seat);
+        state.update_seats();
 
         {
             {
@@ -4,6 +4,7 @@ use crate::utils::prelude::*;
 use smithay::{
     backend::{
         allocator::Fourcc,
+        input::TabletToolDescriptor,
         renderer::{
             element::{
                 memory::{MemoryRenderBuffer, MemoryRenderBufferRenderElement},
@@ -14,7 +15,7 @@ use smithay::{
         },
     },
     input::{
-        pointer::{CursorImageAttributes, CursorImageStatus},
+        pointer::{CursorIcon, CursorImageAttributes, CursorImageStatus},
         Seat,
     },
     reexports::wayland_server::protocol::wl_surface,
@@ -49,23 +50,22 @@ pub enum CursorShape {
     SouthWestResize,
 }
 
-impl ToString for CursorShape {
-    fn to_string(&self) -> String {
-        match self {
-            CursorShape::Default => "default",
-            CursorShape::ColResize => "col-resize",
-            CursorShape::RowResize => "row-resize",
-            CursorShape::Grab => "grabbing",
-            CursorShape::EastResize => "e-resize",
-            CursorShape::WestResize => "w-resize",
-            CursorShape::NorthResize => "n-resize",
-            CursorShape::SouthResize => "s-resize",
-            CursorShape::NorthEastResize => "ne-resize",
-            CursorShape::NorthWestResize => "nw-resize",
-            CursorShape::SouthEastResize => "se-resize",
-            CursorShape::SouthWestResize => "sw-resize",
+impl From<CursorShape> for CursorIcon {
+    fn from(shape: CursorShape) -> CursorIcon {
+        match shape {
+            CursorShape::Default => CursorIcon::Default,
+            CursorShape::ColResize => CursorIcon::ColResize,
+            CursorShape::RowResize => CursorIcon::RowResize,
+            CursorShape::Grab => CursorIcon::Grabbing,
+            CursorShape::EastResize => CursorIcon::EResize,
+            CursorShape::WestResize => CursorIcon::WResize,
+            CursorShape::NorthResize => CursorIcon::NResize,
+            CursorShape::SouthResize => CursorIcon::SResize,
+            CursorShape::NorthEastResize => CursorIcon::NeResize,
+            CursorShape::NorthWestResize => CursorIcon::NwResize,
+            CursorShape::SouthEastResize => CursorIcon::SeResize,
+            CursorShape::SouthWestResize => CursorIcon::SwResize,
         }
-        .to_string()
     }
 }
 
@@ -76,8 +76,8 @@ pub struct Cursor {
 }
 
 impl Cursor {
-    pub fn load(theme: &CursorTheme, shape: CursorShape, size: u32) -> Cursor {
-        let icons = load_icon(&theme, shape)
+    pub fn load(theme: &CursorTheme, icon: CursorIcon, size: u32) -> Cursor {
+        let icons = load_icon(&theme, icon)
             .map_err(|err| warn!(?err, "Unable to load xcursor, using fallback cursor"))
             .unwrap_or_else(|_| {
                 vec![Image {
@@ -134,18 +134,20 @@ fn frame(mut millis: u32, size: u32, images: &[Image]) -> Image {
 
 #[derive(thiserror::Error, Debug)]
 enum Error {
-    #[error("Theme has no default cursor")]
-    NoDefaultCursor,
+    #[error("Theme has no {0} cursor")]
+    NoCursor(&'static str),
     #[error("Error opening xcursor file: {0}")]
     File(#[from] std::io::Error),
     #[error("Failed to parse XCursor file")]
     Parse,
 }
 
-fn load_icon(theme: &CursorTheme, shape: CursorShape) -> Result<Vec<Image>, Error> {
-    let icon_path = theme
-        .load_icon(&shape.to_string())
-        .ok_or(Error::NoDefaultCursor)?;
+fn load_icon(theme: &CursorTheme, icon: CursorIcon) -> Result<Vec<Image>, Error> {
+    // themes often only ship the legacy x11 names
+    let icon_path = std::iter::once(icon.name())
+        .chain(icon.alt_names().iter().copied())
+        .find_map(|name| theme.load_icon(name))
+        .ok_or(Error::NoCursor(icon.name()))?;
     let mut cursor_file = std::fs::File::open(&icon_path)?;
     let mut cursor_data = Vec::new();
     cursor_file.read_to_end(&mut cursor_data)?;
@@ -226,10 +228,22 @@ where
     )
 }
 
+/// Cursor of a tablet tool in proximity
+#[derive(Debug, Clone)]
+pub struct TabletToolCursor {
+    pub location: Point<f64, Global>,
+    pub image: CursorImageStatus,
+}
+
+/// Tablet tools of a seat, that are currently in proximity
+pub type TabletToolCursors = Mutex<HashMap<TabletToolDescriptor, TabletToolCursor>>;
+
 pub type CursorState = Mutex<CursorStateInner>;
 pub struct CursorStateInner {
     current_cursor: CursorShape,
-    pub cursors: HashMap<CursorShape, Cursor>,
+    theme: CursorTheme,
+    size: u32,
+    cursors: HashMap<CursorIcon, Cursor>,
     current_image: Option<Image>,
     image_cache: Vec<(Image, MemoryRenderBuffer)>,
 }
@@ -238,6 +252,14 @@ impl CursorStateInner {
     pub fn set_shape(&mut self, shape: CursorShape) {
         self.current_cursor = shape;
     }
+
+    /// Cursor of `icon`, loaded from the cursor theme on first use
+    pub fn cursor(&mut self, icon: CursorIcon) -> &Cursor {
+        let (theme, size) = (&self.theme, self.size);
+        self.cursors
+            .entry(icon)
+            .or_insert_with(|| Cursor::load(theme, icon, size))
+    }
 }
 
 pub fn load_cursor_theme() -> (CursorTheme, u32) {
@@ -254,63 +276,16 @@ pub fn load_cursor_theme() -> (CursorTheme, u32) {
 impl Default for CursorStateInner {
     fn default() -> CursorStateInner {
         let (theme, size) = load_cursor_theme();
-        CursorStateInner {
+        let mut state = CursorStateInner {
             current_cursor: CursorShape::Default,
-            cursors: {
-                let mut map = HashMap::new();
-                map.insert(
-                    CursorShape::Default,
-                    Cursor::load(&theme, CursorShape::Default, size),
-                );
-                map.insert(
-                    CursorShape::ColResize,
-                    Cursor::load(&theme, CursorShape::ColResize, size),
-                );
-                map.insert(
-                    CursorShape::RowResize,
-                    Cursor::load(&theme, CursorShape::RowResize, size),
-                );
-                map.insert(
-                    CursorShape::Grab,
-                    Cursor::load(&theme, CursorShape::Grab, size),
-                );
-                map.insert(
-                    CursorShape::NorthResize,
-                    Cursor::load(&theme, CursorShape::NorthResize, size),
-                );
-                map.insert(
-                    CursorShape::SouthResize,
-                    Cursor::load(&theme, CursorShape::SouthResize, size),
-                );
-                map.insert(
-                    CursorShape::EastResize,
-                    Cursor::load(&theme, CursorShape::EastResize, size),
-                );
-                map.insert(
-                    CursorShape::WestResize,
-                    Cursor::load(&theme, CursorShape::WestResize, size),
-                );
-                map.insert(
-                    CursorShape::NorthEastResize,
-                    Cursor::load(&theme, CursorShape::NorthEastResize, size),
-                );
-                map.insert(
-                    CursorShape::SouthEastResize,
-                    Cursor::load(&theme, CursorShape::SouthEastResize, size),
-                );
-                map.insert(
-                    CursorShape::NorthWestResize,
-                    Cursor::load(&theme, CursorShape::NorthWestResize, size),
-                );
-                map.insert(
-                    CursorShape::SouthWestResize,
-                    Cursor::load(&theme, CursorShape::SouthWestResize, size),
-                );
-                map
-            },
+            theme,
+            size,
+            cursors: HashMap::new(),
             current_image: None,
             image_cache: Vec::new(),
-        }
+        };
+        state.cursor(CursorIcon::Default);
+        state
     }
 }
 
@@ -343,45 +318,121 @@ where
         })
         .unwrap_or(CursorImageStatus::default_named());
 
-    if let CursorImageStatus::Surface(ref wl_surface) = cursor_status {
-        return draw_surface_cursor(renderer, wl_surface, location.to_i32_round(), scale);
-    // TODO: Handle other named cursors
-    } else if draw_default && CursorImageStatus::default_named() == cursor_status {
-        let integer_scale = scale.x.max(scale.y).ceil() as u32;
-
-        let seat_userdata = seat.user_data();
-        let mut state_ref = seat_userdata.get::<CursorState>().unwrap().lock().unwrap();
-        let state = &mut *state_ref;
-        let frame = state.cursors.get(&state.current_cursor).unwrap().get_image(
-            integer_scale,
-            Into::<Duration>::into(time).as_millis() as u32,
-        );
+    let icon = match cursor_status {
+        CursorImageStatus::Surface(ref wl_surface) => {
+            return draw_surface_cursor(renderer, wl_surface, location.to_i32_round(), scale);
+        }
+        // the default cursor is replaced by the shape of the compositor, e.g. while resizing
+        CursorImageStatus::Named(CursorIcon::Default) if draw_default => None,
+        CursorImageStatus::Named(CursorIcon::Default) | CursorImageStatus::Hidden => {
+            return Vec::new()
+        }
+        CursorImageStatus::Named(icon) => Some(icon),
+    };
 
-        let pointer_images = &mut state.image_cache;
-        let maybe_image =
-            pointer_images
-                .iter()
-                .find_map(|(image, texture)| if image == &frame { Some(texture) } else { None });
-        let pointer_image = match maybe_image {
-            Some(image) => image,
-            None => {
-                let buffer = MemoryRenderBuffer::from_slice(
-                    &frame.pixels_rgba,
-                    Fourcc::Argb8888,
-                    (frame.width as i32, frame.height as i32),
-                    integer_scale as i32,
-                    Transform::Normal,
-                    None,
-                );
-                pointer_images.push((frame.clone(), buffer));
-                pointer_images.last().map(|(_, i)| i).unwrap()
+    let seat_userdata = seat.user_data();
+    let mut state_ref = seat_userdata.get::<CursorState>().unwrap().lock().unwrap();
+    let state = &mut *state_ref;
+    let icon = icon.unwrap_or_else(|| state.current_cursor.into());
+    let (element, frame) = draw_shape_cursor(renderer, state, icon, location, scale, time);
+    state.current_image = Some(frame);
+    vec![element]
+}
+
+/// Draws the cursors of all tablet tools of `seat` in proximity
+pub fn draw_tablet_tool_cursors<R>(
+    renderer: &mut R,
+    seat: &Seat<State>,
+    output_location: Point<f64, Global>,
+    scale: Scale<f64>,
+    time: Time<Monotonic>,
+    draw_default: bool,
+) -> Vec<(CursorRenderElement<R>, Point<i32, BufferCoords>)>
+where
+    R: Renderer + ImportMem + ImportAll,
+    <R as Renderer>::TextureId: Send + Clone + 'static,
+{
+    let Some(tools) = seat.user_data().get::<TabletToolCursors>() else {
+        return Vec::new();
+    };
+    let tools = tools.lock().unwrap();
+
+    let mut elements = Vec::new();
+    for cursor in tools.values() {
+        let location = (cursor.location - output_location).as_logical();
+        let icon = match &cursor.image {
+            CursorImageStatus::Surface(wl_surface) if wl_surface.alive() => {
+                elements.extend(draw_surface_cursor(
+                    renderer,
+                    wl_surface,
+                    location.to_i32_round(),
+                    scale,
+                ));
+                continue;
+            }
+            CursorImageStatus::Named(icon) if *icon != CursorIcon::Default => *icon,
+            CursorImageStatus::Surface(_) | CursorImageStatus::Named(_) if draw_default => {
+                CursorIcon::Default
             }
+            _ => continue,
         };
 
-        let hotspot = Point::<i32, BufferCoords>::from((frame.xhot as i32, frame.yhot as i32));
-        state.current_image = Some(frame);
+        let mut state = seat
+            .user_data()
+            .get::<CursorState>()
+            .unwrap()
+            .lock()
+            .unwrap();
+        let (element, _) = draw_shape_cursor(renderer, &mut state, icon, location, scale, time);
+        elements.push(element);
+    }
+    elements
+}
 
-        return vec![(
+fn draw_shape_cursor<R>(
+    renderer: &mut R,
+    state: &mut CursorStateInner,
+    icon: CursorIcon,
+    location: Point<f64, Logical>,
+    scale: Scale<f64>,
+    time: Time<Monotonic>,
+) -> ((CursorRenderElement<R>, Point<i32, BufferCoords>), Image)
+where
+    R: Renderer + ImportMem + ImportAll,
+    <R as Renderer>::TextureId: Send + Clone + 'static,
+{
+    let integer_scale = scale.x.max(scale.y).ceil() as u32;
+
+    let frame = state.cursor(icon).get_image(
+        integer_scale,
+        Into::<Duration>::into(time).as_millis() as u32,
+    );
+
+    let pointer_images = &mut state.image_cache;
+    let maybe_image =
+        pointer_images
+            .iter()
+            .find_map(|(image, texture)| if image == &frame { Some(texture) } else { None });
+    let pointer_image = match maybe_image {
+        Some(image) => image,
+        None => {
+            let buffer = MemoryRenderBuffer::from_slice(
+                &frame.pixels_rgba,
+                Fourcc::Argb8888,
+                (frame.width as i32, frame.height as i32),
+                integer_scale as i32,
+                Transform::Normal,
+                None,
+            );
+            pointer_images.push((frame.clone(), buffer));
+            pointer_images.last().map(|(_, i)| i).unwrap()
+        }
+    };
+
+    let hotspot = Point::<i32, BufferCoords>::from((frame.xhot as i32, frame.yhot as i32));
+
+    (
+        (
             CursorRenderElement::Static(
                 MemoryRenderBufferRenderElement::from_buffer(
                     renderer,
@@ -395,8 +446,7 @@ where
                 .expect("Failed to import cursor bitmap"),
             ),
             hotspot,
-        )];
-    } else {
-        Vec::new()
-    }
+        ),
+        frame,
+    )
 }
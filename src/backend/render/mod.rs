@@ -11,11 +11,17 @@ use std::{
 #[cfg(feature = "debug")]
 use crate::debug::fps_ui;
 use crate::{
-    backend::{kms::render::gles::GbmGlowBackend, render::element::DamageElement},
+    backend::{
+        kms::render::gles::GbmGlowBackend,
+        render::{cursor::TabletToolCursors, element::DamageElement},
+    },
+    input::SeatBindingMode,
     shell::{
         element::CosmicMappedKey,
         focus::target::WindowGroup,
+        gamma::OutputGamma,
         grabs::{SeatMenuGrabState, SeatMoveGrabState},
+        idle::OutputPower,
         layout::tiling::ANIMATION_DURATION,
         CosmicMappedRenderElement, OverviewMode, SeatExt, SessionLock, Trigger, WorkspaceDelta,
         WorkspaceRenderElement,
@@ -39,19 +45,21 @@ use element::FromGlesError;
 use keyframe::{ease, functions::EaseInOutCubic};
 use smithay::{
     backend::{
-        allocator::dmabuf::Dmabuf,
+        allocator::{dmabuf::Dmabuf, Fourcc},
         drm::{DrmDeviceFd, DrmNode},
         renderer::{
             buffer_dimensions,
             damage::{Error as RenderError, OutputDamageTracker, RenderOutputResult},
             element::{
                 surface::{render_elements_from_surface_tree, WaylandSurfaceRenderElement},
+                texture::{TextureRenderBuffer, TextureRenderElement},
                 utils::{Relocate, RelocateRenderElement},
                 AsRenderElements, Element, Id, Kind, RenderElement,
             },
             gles::{
-                element::PixelShaderElement, GlesError, GlesPixelProgram, GlesRenderer, Uniform,
-                UniformName, UniformType,
+                element::{PixelShaderElement, TextureShaderElement},
+                GlesError, GlesPixelProgram, GlesRenderbuffer, GlesRenderer, GlesTexProgram,
+                GlesTexture, Uniform, UniformName, UniformType,
             },
             glow::GlowRenderer,
             multigpu::{Error as MultiError, MultiFrame, MultiRenderer},
@@ -60,10 +68,13 @@ use smithay::{
             TextureFilter,
         },
     },
-    desktop::{layer_map_for_output, PopupManager},
+    desktop::{layer_map_for_output, space::SpaceElement, PopupManager},
     input::Seat,
     output::{Output, OutputNoMode},
-    utils::{IsAlive, Logical, Monotonic, Physical, Point, Rectangle, Scale, Time, Transform},
+    utils::{
+        Buffer as BufferCoords, IsAlive, Logical, Monotonic, Physical, Point, Rectangle, Scale,
+        Size, Time, Transform,
+    },
     wayland::{
         dmabuf::get_dmabuf,
         shell::wlr_layer::Layer,
@@ -114,6 +125,7 @@ impl<'a> AsMut<GlowRenderer> for RendererRef<'a> {
 pub static CLEAR_COLOR: Color32F = Color32F::new(0.153, 0.161, 0.165, 1.0);
 pub static OUTLINE_SHADER: &str = include_str!("./shaders/rounded_outline.frag");
 pub static RECTANGLE_SHADER: &str = include_str!("./shaders/rounded_rectangle.frag");
+pub static GAMMA_SHADER: &str = include_str!("./shaders/gamma.frag");
 pub static GROUP_COLOR: [f32; 3] = [0.788, 0.788, 0.788];
 pub static ACTIVE_GROUP_COLOR: [f32; 3] = [0.58, 0.922, 0.922];
 
@@ -360,11 +372,26 @@ impl BackdropShader {
     }
 }
 
+pub struct GammaShader(pub GlesTexProgram);
+
+impl GammaShader {
+    pub fn get<R: AsGlowRenderer>(renderer: &R) -> GlesTexProgram {
+        Borrow::<GlesRenderer>::borrow(renderer.glow_renderer())
+            .egl_context()
+            .user_data()
+            .get::<GammaShader>()
+            .expect("Custom Shaders not initialized")
+            .0
+            .clone()
+    }
+}
+
 pub fn init_shaders(renderer: &mut GlesRenderer) -> Result<(), GlesError> {
     {
         let egl_context = renderer.egl_context();
         if egl_context.user_data().get::<IndicatorShader>().is_some()
             && egl_context.user_data().get::<BackdropShader>().is_some()
+            && egl_context.user_data().get::<GammaShader>().is_some()
         {
             return Ok(());
         }
@@ -385,6 +412,13 @@ pub fn init_shaders(renderer: &mut GlesRenderer) -> Result<(), GlesError> {
             UniformName::new("radius", UniformType::_1f),
         ],
     )?;
+    let gamma_shader = renderer.compile_custom_texture_shader(
+        GAMMA_SHADER,
+        &[
+            UniformName::new("gamma_mult", UniformType::_3f),
+            UniformName::new("gamma_exp", UniformType::_3f),
+        ],
+    )?;
 
     let egl_context = renderer.egl_context();
     egl_context
@@ -393,6 +427,9 @@ pub fn init_shaders(renderer: &mut GlesRenderer) -> Result<(), GlesError> {
     egl_context
         .user_data()
         .insert_if_missing(|| BackdropShader(rectangle_shader));
+    egl_context
+        .user_data()
+        .insert_if_missing(|| GammaShader(gamma_shader));
 
     Ok(())
 }
@@ -429,7 +466,30 @@ where
         };
         let location = pointer.current_location() - output.current_location().to_f64();
 
+        // tablet tools move the pointer as well, but replace its cursor while in proximity
+        let tablet_tool_active = seat
+            .user_data()
+            .get::<TabletToolCursors>()
+            .is_some_and(|tools| !tools.lock().unwrap().is_empty());
         if mode != CursorMode::None {
+            let tool_cursors = cursor::draw_tablet_tool_cursors(
+                renderer,
+                &seat,
+                output.current_location().to_f64().as_global(),
+                scale.into(),
+                now,
+                mode != CursorMode::NotDefault,
+            );
+            elements.extend(tool_cursors.into_iter().map(|(elem, hotspot)| {
+                CosmicElement::Cursor(RelocateRenderElement::from_element(
+                    elem,
+                    Point::from((-hotspot.x, -hotspot.y)),
+                    Relocate::Relative,
+                ))
+            }));
+        }
+
+        if mode != CursorMode::None && !tablet_tool_active {
             elements.extend(
                 cursor::draw_cursor(
                     renderer,
@@ -484,6 +544,37 @@ where
         {
             elements.extend(grab_elements.into_iter().map(Into::into));
         }
+
+        // indicator of the active binding mode at the bottom of the active output
+        if &seat.active_output() == output {
+            if let Some(indicator) = seat
+                .user_data()
+                .get::<SeatBindingMode>()
+                .unwrap()
+                .lock()
+                .unwrap()
+                .as_ref()
+                .map(|mode| mode.indicator.clone())
+            {
+                let output_size = output.geometry().as_logical().size;
+                let size = indicator.bbox().size;
+                let location = Point::<i32, Logical>::from((
+                    (output_size.w - size.w) / 2,
+                    output_size.h - size.h - 48,
+                ));
+                elements.extend(
+                    AsRenderElements::<R>::render_elements::<CosmicMappedRenderElement<R>>(
+                        &indicator,
+                        renderer,
+                        location.to_physical_precise_round(scale),
+                        Scale::from(scale),
+                        1.0,
+                    )
+                    .into_iter()
+                    .map(Into::into),
+                );
+            }
+        }
     }
 
     elements
@@ -584,6 +675,22 @@ where
         .cloned()
         .collect::<Vec<_>>();
 
+    // dim everything, while the output is idle
+    let power = OutputPower::get(output);
+    if power.is_dimmed() {
+        elements.p_elements.push(
+            Into::<CosmicMappedRenderElement<R>>::into(BackdropShader::element(
+                renderer,
+                power.dim_id.clone(),
+                Rectangle::from_loc_and_size((0, 0), output.geometry().size.as_local()),
+                0.,
+                0.5,
+                [0.0, 0.0, 0.0],
+            ))
+            .into(),
+        );
+    }
+
     elements.p_elements.extend(cursor_elements(
         renderer,
         seats.iter(),
@@ -627,7 +734,7 @@ where
         elements.p_elements.extend(
             session_lock_elements(renderer, output, session_lock)
                 .into_iter()
-                .map(|x| WorkspaceRenderElement::from(x).into()),
+                .map(Into::into),
         );
         return Ok(elements.join());
     }
@@ -694,10 +801,12 @@ where
         );
     };
 
-    let active_hint = if shell.active_hint {
-        theme.active_hint as u8
+    let active_hint = |thickness: u8| if shell.active_hint { thickness } else { 0 };
+    // only windows of the active output compete for the focus
+    let inactive_alpha = if output == &active_output {
+        1.0 - shell.dim_inactive
     } else {
-        0
+        1.0
     };
 
     // overlay redirect windows
@@ -758,8 +867,9 @@ where
                 renderer,
                 current_focus.as_ref().and_then(|stack| stack.last()),
                 resize_indicator.clone(),
-                active_hint,
+                active_hint(set.sticky_layer.active_hint()),
                 alpha,
+                inactive_alpha,
                 theme,
             ),
             (0, 0).into(),
@@ -812,7 +922,8 @@ where
                         (!move_active && is_active_space).then_some(last_active_seat),
                         overview.clone(),
                         resize_indicator.clone(),
-                        active_hint,
+                        active_hint(workspace.active_hint()),
+                        inactive_alpha,
                         theme,
                     )
                     .map_err(|_| OutputNoMode)?,
@@ -844,7 +955,8 @@ where
                     (!move_active && is_active_space).then_some(&last_active_seat),
                     overview,
                     resize_indicator,
-                    active_hint,
+                    active_hint(workspace.active_hint()),
+                    inactive_alpha,
                     theme,
                 )
                 .map_err(|_| OutputNoMode)?,
@@ -959,24 +1071,47 @@ fn session_lock_elements<R>(
     renderer: &mut R,
     output: &Output,
     session_lock: &SessionLock,
-) -> Vec<WaylandSurfaceRenderElement<R>>
+) -> Vec<WorkspaceRenderElement<R>>
 where
-    R: Renderer + ImportAll,
-    <R as Renderer>::TextureId: Clone + 'static,
+    R: Renderer + ImportAll + ImportMem + AsGlowRenderer,
+    <R as Renderer>::TextureId: Send + Clone + 'static,
+    CosmicMappedRenderElement<R>: RenderElement<R>,
 {
+    let scale = Scale::from(output.current_scale().fractional_scale());
+    let mut elements = Vec::new();
     if let Some(surface) = session_lock.surfaces.get(output) {
-        let scale = Scale::from(output.current_scale().fractional_scale());
-        render_elements_from_surface_tree(
-            renderer,
-            surface.wl_surface(),
-            (0, 0),
-            scale,
-            1.0,
-            Kind::Unspecified,
-        )
-    } else {
-        Vec::new()
+        elements.extend(
+            render_elements_from_surface_tree::<_, WaylandSurfaceRenderElement<R>>(
+                renderer,
+                surface.wl_surface(),
+                (0, 0),
+                scale,
+                1.0,
+                Kind::Unspecified,
+            )
+            .into_iter()
+            .map(Into::into),
+        );
     }
+
+    // keep showing the fallback, until a new lock client provides content
+    if elements.is_empty() {
+        if let Some(fallback) = session_lock.fallback.get(output) {
+            elements.extend(
+                AsRenderElements::<R>::render_elements::<CosmicMappedRenderElement<R>>(
+                    fallback,
+                    renderer,
+                    (0, 0).into(),
+                    scale,
+                    1.0,
+                )
+                .into_iter()
+                .map(Into::into),
+            );
+        }
+    }
+
+    elements
 }
 
 #[profiling::function]
@@ -1134,6 +1269,138 @@ where
     }
 }
 
+/// Offscreen buffer of an output, that has its gamma ramp applied by a shader
+#[derive(Debug)]
+pub struct GammaFallback {
+    texture: TextureRenderBuffer<GlesTexture>,
+    damage_tracker: OutputDamageTracker,
+    size: Size<i32, BufferCoords>,
+    curve: ([f32; 3], [f32; 3]),
+}
+
+/// Renders like [`render_output`], but applies the gamma ramp of `output` with a shader.
+///
+/// Used by the nested backends, which can't change the gamma ramp of the host.
+pub fn render_output_with_gamma<'d, Target>(
+    renderer: &mut GlowRenderer,
+    target: Target,
+    damage_tracker: &'d mut OutputDamageTracker,
+    age: usize,
+    fallback: &mut Option<GammaFallback>,
+    shell: &Arc<RwLock<Shell>>,
+    now: Time<Monotonic>,
+    output: &Output,
+    cursor_mode: CursorMode,
+) -> Result<RenderOutputResult<'d>, RenderError<GlowRenderer>>
+where
+    GlowRenderer: Bind<Target> + Blit<Target>,
+    Target: Clone,
+{
+    let Some(ramp) = OutputGamma::get(output).ramp() else {
+        *fallback = None;
+        return render_output::<_, _, GlesRenderbuffer>(
+            None,
+            renderer,
+            target,
+            damage_tracker,
+            age,
+            shell,
+            now,
+            output,
+            cursor_mode,
+        );
+    };
+
+    let transform = output.current_transform();
+    let size = output
+        .current_mode()
+        .map(|mode| mode.size)
+        .unwrap_or_default()
+        .to_logical(1)
+        .to_buffer(1, Transform::Normal);
+    if fallback
+        .as_ref()
+        .map_or(true, |fallback| fallback.size != size)
+    {
+        let texture = Offscreen::<GlesTexture>::create_buffer(renderer, Fourcc::Abgr8888, size)
+            .map_err(RenderError::Rendering)?;
+        let opaque_regions = vec![Rectangle::from_loc_and_size((0, 0), size)];
+        *fallback = Some(GammaFallback {
+            texture: TextureRenderBuffer::from_texture(
+                renderer,
+                texture,
+                1,
+                transform,
+                Some(opaque_regions),
+            ),
+            damage_tracker: OutputDamageTracker::from_output(output),
+            size,
+            curve: Default::default(),
+        });
+    }
+    let fallback = fallback.as_mut().unwrap();
+
+    let mut states = None;
+    fallback
+        .texture
+        .render()
+        .draw::<_, RenderError<GlowRenderer>>(|tex| {
+            let res = render_output::<_, _, GlesRenderbuffer>(
+                None,
+                renderer,
+                tex.clone(),
+                &mut fallback.damage_tracker,
+                1,
+                shell,
+                now,
+                output,
+                cursor_mode,
+            )?;
+            renderer.wait(&res.sync).map_err(RenderError::Rendering)?;
+            states = Some(res.states);
+
+            let area = tex.size().to_logical(1, transform);
+            Ok(res
+                .damage
+                .cloned()
+                .map(|v| {
+                    v.into_iter()
+                        .map(|r| r.to_logical(1).to_buffer(1, transform, &area))
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default())
+        })?;
+
+    // the shader uniforms aren't tracked as damage
+    let curve = ramp.curve();
+    let age = if fallback.curve != curve { 0 } else { age };
+    fallback.curve = curve;
+
+    let elem = TextureShaderElement::new(
+        TextureRenderElement::from_texture_render_buffer(
+            (0., 0.),
+            &fallback.texture,
+            Some(1.0),
+            None,
+            Some(output.geometry().size.as_logical()),
+            Kind::Unspecified,
+        ),
+        GammaShader::get(renderer),
+        vec![
+            Uniform::new("gamma_mult", curve.0),
+            Uniform::new("gamma_exp", curve.1),
+        ],
+    );
+
+    renderer.bind(target).map_err(RenderError::Rendering)?;
+    let res = damage_tracker.render_output(renderer, age, &[elem], CLEAR_COLOR)?;
+    Ok(RenderOutputResult {
+        damage: res.damage,
+        sync: res.sync,
+        states: states.unwrap_or_default(),
+    })
+}
+
 #[profiling::function]
 pub fn render_workspace<'d, R, Target, OffTarget>(
     gpu: Option<&DrmNode>,
@@ -14,7 +14,6 @@ use smithay::{
         egl::EGLDevice,
         renderer::{
             damage::{OutputDamageTracker, RenderOutputResult},
-            gles::GlesRenderbuffer,
             glow::GlowRenderer,
             ImportDma,
         },
@@ -34,7 +33,7 @@ use smithay::{
 use std::{borrow::BorrowMut, cell::RefCell, time::Duration};
 use tracing::{error, info, warn};
 
-use super::render::{init_shaders, CursorMode};
+use super::render::{init_shaders, CursorMode, GammaFallback};
 
 #[derive(Debug)]
 pub struct WinitState {
@@ -42,6 +41,7 @@ pub struct WinitState {
     pub backend: WinitGraphicsBackend<GlowRenderer>,
     output: Output,
     damage_tracker: OutputDamageTracker,
+    gamma: Option<GammaFallback>,
 }
 
 impl WinitState {
@@ -53,12 +53,12 @@ impl WinitState {
         let age = self.backend.buffer_age().unwrap_or(0);
 
         let surface = self.backend.egl_surface();
-        match render::render_output::<_, _, GlesRenderbuffer>(
-            None,
+        match render::render_output_with_gamma(
             self.backend.renderer(),
             surface.clone(),
             &mut self.damage_tracker,
             age,
+            &mut self.gamma,
             &state.shell,
             state.clock.now(),
             &self.output,
@@ -208,6 +208,7 @@ pub fn init_backend(
         backend,
         output: output.clone(),
         damage_tracker: OutputDamageTracker::from_output(&output),
+        gamma: None,
     });
 
     state
@@ -20,7 +20,6 @@ use smithay::{
         input::{Event, InputEvent},
         renderer::{
             damage::{OutputDamageTracker, RenderOutputResult},
-            gles::GlesRenderbuffer,
             glow::GlowRenderer,
             Bind, ImportDma,
         },
@@ -41,7 +40,7 @@ use smithay::{
 use std::{borrow::BorrowMut, cell::RefCell, os::unix::io::OwnedFd, time::Duration};
 use tracing::{debug, error, info, warn};
 
-use super::render::init_shaders;
+use super::render::{init_shaders, GammaFallback};
 
 #[derive(Debug)]
 enum Allocator {
@@ -142,6 +141,7 @@ impl X11State {
             window,
             surface,
             damage_tracker: OutputDamageTracker::from_output(&output),
+            gamma: None,
             output: output.clone(),
             render: ping.clone(),
             dirty: false,
@@ -193,6 +193,7 @@ impl X11State {
 pub struct Surface {
     window: Window,
     damage_tracker: OutputDamageTracker,
+    gamma: Option<GammaFallback>,
     surface: X11Surface,
     output: Output,
     render: ping::Ping,
@@ -206,12 +207,12 @@ impl Surface {
             .surface
             .buffer()
             .with_context(|| "Failed to allocate buffer")?;
-        match render::render_output::<_, _, GlesRenderbuffer>(
-            None,
+        match render::render_output_with_gamma(
             renderer,
             buffer.clone(),
             &mut self.damage_tracker,
             age as usize,
+            &mut self.gamma,
             &state.shell,
             state.clock.now(),
             &self.output,
@@ -80,6 +80,9 @@ pub fn for_device(device: &InputDevice) -> InputConfig {
             None
         },
         map_to_output: None,
+        map_to_area: None,
+        keep_aspect_ratio: None,
+        xkb_config: None,
     }
 }
 
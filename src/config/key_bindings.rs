@@ -1,7 +1,14 @@
-use cosmic_comp_config::workspace::WorkspaceLayout;
+use std::collections::HashMap;
+
+use cosmic_comp_config::{
+    bindings::{self, CompositorAction, ModeAction},
+    gestures::{Gesture, GestureKind},
+    workspace::WorkspaceLayout,
+};
 use cosmic_settings_config::shortcuts::State as KeyState;
 use cosmic_settings_config::shortcuts::{self, Modifiers, Shortcuts};
 use smithay::input::keyboard::ModifiersState;
+use tracing::warn;
 use xkbcommon::xkb;
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -10,6 +17,8 @@ pub enum Action {
     Private(PrivateAction),
     /// Behaviors managed via cosmic-settings.
     Shortcut(shortcuts::Action),
+    /// Behaviors specific to cosmic-comp, bound via its `compositor_bindings` config.
+    Compositor(CompositorAction),
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -21,6 +30,31 @@ pub enum PrivateAction {
         shortcuts::action::ResizeEdge,
         shortcuts::State,
     ),
+    /// Escape binding of shortcut inhibitors
+    ToggleShortcutsInhibitor,
+    /// Activate the binding mode of the given name
+    EnterMode(String),
+    /// Return to the regular bindings
+    ExitMode,
+}
+
+impl From<ModeAction> for Action {
+    fn from(action: ModeAction) -> Action {
+        match action {
+            ModeAction::Shortcut(action) => Action::Shortcut(action),
+            ModeAction::Compositor(action) => Action::Compositor(action),
+            ModeAction::EnterMode(name) => Action::Private(PrivateAction::EnterMode(name)),
+            ModeAction::ExitMode => Action::Private(PrivateAction::ExitMode),
+        }
+    }
+}
+
+/// Parsed [`bindings::BindingMode`]
+#[derive(Debug)]
+pub struct BindingMode {
+    pub enter: Option<shortcuts::Binding>,
+    pub bindings: Vec<(shortcuts::Binding, Action)>,
+    pub oneshot: bool,
 }
 
 pub fn add_default_bindings(shortcuts: &mut Shortcuts, workspace_layout: WorkspaceLayout) {
@@ -105,6 +139,192 @@ pub fn add_default_bindings(shortcuts: &mut Shortcuts, workspace_layout: Workspa
     );
 }
 
+/// Default 4-finger swipes between workspaces, along the axis of the `workspace_layout`.
+pub fn default_gesture(
+    gesture: &Gesture,
+    workspace_layout: WorkspaceLayout,
+) -> Option<shortcuts::Action> {
+    if gesture.fingers != 4 {
+        return None;
+    }
+
+    match (workspace_layout, gesture.kind) {
+        (WorkspaceLayout::Horizontal, GestureKind::Swipe(bindings::Direction::Left))
+        | (WorkspaceLayout::Vertical, GestureKind::Swipe(bindings::Direction::Up)) => {
+            Some(shortcuts::Action::PreviousWorkspace)
+        }
+        (WorkspaceLayout::Horizontal, GestureKind::Swipe(bindings::Direction::Right))
+        | (WorkspaceLayout::Vertical, GestureKind::Swipe(bindings::Direction::Down)) => {
+            Some(shortcuts::Action::NextWorkspace)
+        }
+        _ => None,
+    }
+}
+
+/// Parse the key bindings of the `compositor_bindings` config, skipping invalid bindings.
+pub fn compositor_bindings(
+    config: &HashMap<String, CompositorAction>,
+) -> Vec<(shortcuts::Binding, CompositorAction)> {
+    config
+        .iter()
+        .filter(|(binding, _)| parse_pointer_binding(binding).is_none())
+        .filter_map(|(binding, action)| match parse_binding(binding) {
+            Some(parsed) => Some((parsed, action.clone())),
+            None => {
+                warn!(?binding, "Ignoring invalid compositor binding");
+                None
+            }
+        })
+        .collect()
+}
+
+/// Parse the pointer bindings of the `compositor_bindings` config.
+pub fn pointer_bindings(
+    config: &HashMap<String, CompositorAction>,
+) -> Vec<(PointerBinding, CompositorAction)> {
+    config
+        .iter()
+        .filter_map(|(binding, action)| Some((parse_pointer_binding(binding)?, action.clone())))
+        .collect()
+}
+
+pub fn binding_modes(
+    config: &HashMap<String, bindings::BindingMode>,
+) -> HashMap<String, BindingMode> {
+    config
+        .iter()
+        .map(|(name, mode)| {
+            let enter = parse_binding(&mode.enter);
+            if enter.is_none() {
+                warn!(?name, binding = ?mode.enter, "Ignoring invalid binding mode binding");
+            }
+            let bindings = mode
+                .bindings
+                .iter()
+                .filter_map(|(binding, action)| match parse_binding(binding) {
+                    Some(parsed) => Some((parsed, action.clone().into())),
+                    None => {
+                        warn!(?name, ?binding, "Ignoring invalid binding mode binding");
+                        None
+                    }
+                })
+                .collect();
+            (
+                name.clone(),
+                BindingMode {
+                    enter,
+                    bindings,
+                    oneshot: mode.oneshot,
+                },
+            )
+        })
+        .collect()
+}
+
+/// Button or scroll direction of a pointer binding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointerTrigger {
+    /// evdev button code
+    Button(u32),
+    Scroll(bindings::Direction),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PointerBinding {
+    pub modifiers: Modifiers,
+    pub trigger: PointerTrigger,
+}
+
+/// Parse a pointer binding like `"Super+MouseMiddle"`, `"Super+ScrollUp"` or `"Button277"`.
+///
+/// Button names are prefixed, so they don't clash with keysyms like `Left`.
+fn parse_pointer_binding(value: &str) -> Option<PointerBinding> {
+    let mut modifiers = Modifiers::new();
+    let mut trigger = None;
+    for part in value.split('+').map(str::trim) {
+        match part.to_lowercase().as_str() {
+            "super" | "logo" => modifiers.logo = true,
+            "ctrl" | "control" => modifiers.ctrl = true,
+            "alt" => modifiers.alt = true,
+            "shift" => modifiers.shift = true,
+            part if trigger.is_none() => {
+                trigger = Some(match part {
+                    "mouseleft" => PointerTrigger::Button(0x110),
+                    "mouseright" => PointerTrigger::Button(0x111),
+                    "mousemiddle" => PointerTrigger::Button(0x112),
+                    "mouseside" | "mouseback" => PointerTrigger::Button(0x113),
+                    "mouseextra" | "mouseforward" => PointerTrigger::Button(0x114),
+                    "scrollup" => PointerTrigger::Scroll(bindings::Direction::Up),
+                    "scrolldown" => PointerTrigger::Scroll(bindings::Direction::Down),
+                    "scrollleft" => PointerTrigger::Scroll(bindings::Direction::Left),
+                    "scrollright" => PointerTrigger::Scroll(bindings::Direction::Right),
+                    part => PointerTrigger::Button(part.strip_prefix("button")?.parse().ok()?),
+                });
+            }
+            _ => return None,
+        }
+    }
+
+    Some(PointerBinding {
+        modifiers,
+        trigger: trigger?,
+    })
+}
+
+/// Parse the `escape_binding` of the shortcuts inhibit config.
+pub fn shortcuts_inhibit_escape(binding: &str) -> Option<shortcuts::Binding> {
+    let parsed = parse_binding(binding);
+    if parsed.is_none() {
+        warn!(?binding, "Ignoring invalid escape binding");
+    }
+    parsed
+}
+
+/// Whether a key press triggers the `escape` binding of shortcut inhibitors.
+///
+/// It only applies while the focused window has a shortcuts inhibitor, so it doesn't shadow
+/// regular shortcuts bound to the same keys, like the default `Super+Escape` to lock the screen.
+pub fn is_shortcuts_inhibit_escape(
+    escape: Option<&shortcuts::Binding>,
+    has_inhibitor: bool,
+    syms: &[xkb::Keysym],
+    modifiers: &ModifiersState,
+) -> bool {
+    has_inhibitor
+        && escape.is_some_and(|binding| {
+            binding.key.is_some_and(|key| syms.contains(&key))
+                && cosmic_modifiers_eq_smithay(&binding.modifiers, modifiers)
+        })
+}
+
+/// Parse a binding like `"Super+Shift+m"`.
+fn parse_binding(value: &str) -> Option<shortcuts::Binding> {
+    let mut modifiers = Modifiers::new();
+    let mut key = None;
+    for part in value.split('+').map(str::trim) {
+        match part.to_lowercase().as_str() {
+            "super" | "logo" => modifiers.logo = true,
+            "ctrl" | "control" => modifiers.ctrl = true,
+            "alt" => modifiers.alt = true,
+            "shift" => modifiers.shift = true,
+            _ if key.is_none() => {
+                let keysym = xkb::keysym_from_name(part, xkb::KEYSYM_CASE_INSENSITIVE);
+                if keysym == xkb::Keysym::NoSymbol {
+                    return None;
+                }
+                key = Some(keysym);
+            }
+            _ => return None,
+        }
+    }
+
+    Some(shortcuts::Binding {
+        modifiers,
+        key: Some(key?),
+        description: None,
+    })
+}
+
 /// Convert `cosmic_settings_config::shortcuts::State` to `smithay::backend::input::KeyState`.
 pub fn cosmic_keystate_to_smithay(value: KeyState) -> smithay::backend::input::KeyState {
     match value {
@@ -138,3 +358,151 @@ pub fn cosmic_modifiers_from_smithay(value: ModifiersState) -> Modifiers {
         logo: value.logo,
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{
+        compositor_bindings, cosmic_modifiers_eq_smithay, is_shortcuts_inhibit_escape,
+        parse_binding, parse_pointer_binding, pointer_bindings, shortcuts_inhibit_escape,
+        PointerBinding, PointerTrigger,
+    };
+    use cosmic_comp_config::{
+        bindings::{CompositorAction, Direction},
+        shortcuts_inhibit::ShortcutsInhibitConfig,
+    };
+    use cosmic_settings_config::shortcuts::{self, Modifiers};
+    use smithay::input::keyboard::ModifiersState;
+    use std::collections::HashMap;
+    use xkbcommon::xkb::Keysym;
+
+    #[test]
+    fn test_parse_binding() {
+        let binding = parse_binding("Super+Shift+m").unwrap();
+        assert_eq!(binding.modifiers, Modifiers::new().logo().shift());
+        assert_eq!(binding.key, Some(Keysym::m));
+
+        let binding = parse_binding(" ctrl + alt + Left ").unwrap();
+        assert_eq!(binding.modifiers, Modifiers::new().ctrl().alt());
+        assert_eq!(binding.key, Some(Keysym::Left));
+
+        assert!(parse_binding("Super").is_none());
+        assert!(parse_binding("Super+a+b").is_none());
+        assert!(parse_binding("Super+NotAKey").is_none());
+        assert!(parse_binding("").is_none());
+    }
+
+    #[test]
+    fn test_parse_pointer_binding() {
+        assert_eq!(
+            parse_pointer_binding("Super+MouseMiddle"),
+            Some(PointerBinding {
+                modifiers: Modifiers::new().logo(),
+                trigger: PointerTrigger::Button(0x112),
+            })
+        );
+        assert_eq!(
+            parse_pointer_binding("Super+Ctrl+ScrollUp"),
+            Some(PointerBinding {
+                modifiers: Modifiers::new().logo().ctrl(),
+                trigger: PointerTrigger::Scroll(Direction::Up),
+            })
+        );
+        assert_eq!(
+            parse_pointer_binding("MouseBack").map(|binding| binding.trigger),
+            parse_pointer_binding("mouseside").map(|binding| binding.trigger),
+        );
+        assert_eq!(
+            parse_pointer_binding("Button277").map(|binding| binding.trigger),
+            Some(PointerTrigger::Button(277))
+        );
+
+        // keysyms aren't pointer bindings
+        assert!(parse_pointer_binding("Super+Left").is_none());
+        assert!(parse_pointer_binding("Super").is_none());
+        assert!(parse_pointer_binding("MouseLeft+MouseRight").is_none());
+        assert!(parse_pointer_binding("ButtonX").is_none());
+    }
+
+    #[test]
+    fn test_split_bindings() {
+        let config = HashMap::from([
+            ("Super+m".to_string(), CompositorAction::CycleLayoutStrategy),
+            (
+                "Super+MouseMiddle".to_string(),
+                CompositorAction::Shortcut(shortcuts::Action::ToggleWindowFloating),
+            ),
+            (
+                "Super+ScrollDown".to_string(),
+                CompositorAction::ToggleScrolling,
+            ),
+            ("Super+Invalid".to_string(), CompositorAction::BalanceSplits),
+        ]);
+
+        let keys = compositor_bindings(&config);
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].0.key, Some(Keysym::m));
+        assert_eq!(keys[0].1, CompositorAction::CycleLayoutStrategy);
+
+        let mut pointer = pointer_bindings(&config);
+        pointer.sort_by_key(|(binding, _)| binding.trigger == PointerTrigger::Button(0x112));
+        assert_eq!(
+            pointer
+                .iter()
+                .map(|(binding, action)| (binding.trigger, action.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    PointerTrigger::Scroll(Direction::Down),
+                    CompositorAction::ToggleScrolling
+                ),
+                (
+                    PointerTrigger::Button(0x112),
+                    CompositorAction::Shortcut(shortcuts::Action::ToggleWindowFloating)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_shortcuts_inhibit_escape() {
+        let escape = shortcuts_inhibit_escape(&ShortcutsInhibitConfig::default().escape_binding);
+        let modifiers = ModifiersState {
+            logo: true,
+            ..Default::default()
+        };
+
+        assert!(is_shortcuts_inhibit_escape(
+            escape.as_ref(),
+            true,
+            &[Keysym::Escape],
+            &modifiers
+        ));
+        assert!(!is_shortcuts_inhibit_escape(
+            escape.as_ref(),
+            true,
+            &[Keysym::q],
+            &modifiers
+        ));
+
+        // without an inhibitor, the default escape binding still locks the screen
+        assert!(!is_shortcuts_inhibit_escape(
+            escape.as_ref(),
+            false,
+            &[Keysym::Escape],
+            &modifiers
+        ));
+        let defaults: HashMap<shortcuts::Binding, shortcuts::Action> =
+            ron::from_str(include_str!("../../data/keybindings.ron")).unwrap();
+        let action = defaults.iter().find_map(|(binding, action)| {
+            (binding.key == Some(Keysym::Escape)
+                && cosmic_modifiers_eq_smithay(&binding.modifiers, &modifiers))
+            .then_some(action)
+        });
+        assert_eq!(
+            action,
+            Some(&shortcuts::Action::System(
+                shortcuts::action::System::LockScreen
+            ))
+        );
+    }
+}
@@ -1,7 +1,8 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
 use crate::{
-    shell::Shell,
+    input::ActiveKeymap,
+    shell::{session::SessionState, window_rules::WindowRules, Shell},
     state::{BackendData, State},
     wayland::protocols::{
         output_configuration::OutputConfigurationState, workspace::WorkspaceUpdateGuard,
@@ -28,8 +29,10 @@ use std::{
     cell::RefCell,
     collections::{BTreeMap, HashMap},
     fs::OpenOptions,
-    path::PathBuf,
-    sync::{atomic::AtomicBool, Arc, RwLock},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{atomic::AtomicBool, mpsc, Arc, RwLock},
+    thread::JoinHandle,
 };
 use tracing::{error, warn};
 
@@ -40,7 +43,19 @@ mod types;
 pub use self::types::*;
 use cosmic::config::CosmicTk;
 use cosmic_comp_config::{
-    input::InputConfig, workspace::WorkspaceConfig, CosmicCompConfig, TileBehavior, XkbConfig,
+    accessibility::KeyboardAccessibility,
+    bindings::{BindingMode as BindingModeConfig, CompositorAction},
+    gaps::GapsConfig,
+    gestures::{Gesture, GestureKind},
+    idle::IdleConfig,
+    input::{InputConfig, MapToArea},
+    night_light::NightLightConfig,
+    seats::SeatConfig,
+    shortcuts_inhibit::ShortcutsInhibitConfig,
+    switches::SwitchConfig,
+    window_rules::WindowRule,
+    workspace::{LayoutStrategy, MasterStackConfig, WorkspaceConfig},
+    CosmicCompConfig, TileBehavior, XkbConfig,
 };
 
 #[derive(Debug)]
@@ -54,11 +69,21 @@ pub struct Config {
     pub shortcuts: Shortcuts,
     /// System actions from `com.system76.CosmicSettings.Shortcuts`
     pub system_actions: BTreeMap<shortcuts::action::System, String>,
+    /// Parsed key bindings of the `compositor_bindings` of the cosmic-comp config
+    pub compositor_bindings: Vec<(shortcuts::Binding, CompositorAction)>,
+    /// Parsed pointer bindings of the `compositor_bindings` of the cosmic-comp config
+    pub pointer_bindings: Vec<(key_bindings::PointerBinding, CompositorAction)>,
+    /// Parsed `binding_modes` of the cosmic-comp config
+    pub binding_modes: HashMap<String, key_bindings::BindingMode>,
+    /// Parsed `escape_binding` of the shortcuts inhibit config
+    pub shortcuts_inhibit_escape: Option<shortcuts::Binding>,
 }
 
 #[derive(Debug)]
 pub struct DynamicConfig {
     outputs: (Option<PathBuf>, OutputsConfig),
+    session: (Option<PathBuf>, SessionState),
+    writer: StateWriter,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -245,6 +270,16 @@ impl Config {
 
         Config {
             dynamic_conf: Self::load_dynamic(xdg.as_ref()),
+            compositor_bindings: key_bindings::compositor_bindings(
+                &cosmic_comp_config.compositor_bindings,
+            ),
+            pointer_bindings: key_bindings::pointer_bindings(
+                &cosmic_comp_config.compositor_bindings,
+            ),
+            binding_modes: key_bindings::binding_modes(&cosmic_comp_config.binding_modes),
+            shortcuts_inhibit_escape: key_bindings::shortcuts_inhibit_escape(
+                &cosmic_comp_config.shortcuts_inhibit.escape_binding,
+            ),
             cosmic_conf: cosmic_comp_config,
             cosmic_helper: config,
             settings_context,
@@ -257,12 +292,37 @@ impl Config {
         let output_path =
             xdg.and_then(|base| base.place_state_file("cosmic-comp/outputs.ron").ok());
         let outputs = Self::load_outputs(&output_path);
+        let session_path =
+            xdg.and_then(|base| base.place_state_file("cosmic-comp/session.ron").ok());
+        let session = Self::load_session(&session_path);
 
         DynamicConfig {
             outputs: (output_path, outputs),
+            session: (session_path, session),
+            writer: StateWriter::new(),
         }
     }
 
+    fn load_session(path: &Option<PathBuf>) -> SessionState {
+        if let Some(path) = path.as_ref() {
+            if path.exists() {
+                match ron::de::from_reader::<_, SessionState>(
+                    OpenOptions::new().read(true).open(path).unwrap(),
+                ) {
+                    Ok(session) => return session,
+                    Err(err) => {
+                        warn!(?err, "Failed to read session state, resetting..");
+                        if let Err(err) = std::fs::remove_file(path) {
+                            error!(?err, "Failed to remove session state.");
+                        }
+                    }
+                };
+            }
+        }
+
+        SessionState::default()
+    }
+
     fn load_outputs(path: &Option<PathBuf>) -> OutputsConfig {
         if let Some(path) = path.as_ref() {
             if path.exists() {
@@ -311,6 +371,40 @@ impl Config {
         self.shortcuts.shortcut_for_action(action)
     }
 
+    /// Action bound to `gesture`, falling back to the default workspace swipes
+    pub fn gesture_action(&self, gesture: &Gesture) -> Option<shortcuts::Action> {
+        match self.cosmic_conf.gestures.get(gesture) {
+            Some(shortcuts::Action::Disable) => None,
+            Some(action) => Some(action.clone()),
+            None => {
+                key_bindings::default_gesture(gesture, self.cosmic_conf.workspaces.workspace_layout)
+            }
+        }
+    }
+
+    /// Action bound to `trigger` while `modifiers` are held
+    pub fn pointer_binding_action(
+        &self,
+        modifiers: &ModifiersState,
+        trigger: key_bindings::PointerTrigger,
+    ) -> Option<Action> {
+        self.pointer_bindings
+            .iter()
+            .find(|(binding, action)| {
+                binding.trigger == trigger
+                    && *action != CompositorAction::Shortcut(shortcuts::Action::Disable)
+                    && key_bindings::cosmic_modifiers_eq_smithay(&binding.modifiers, modifiers)
+            })
+            .map(|(_, action)| Action::Compositor(action.clone()))
+    }
+
+    /// Whether any of the given gesture `kinds` is bound for `fingers`
+    pub fn gesture_bound(&self, fingers: u32, kinds: &[GestureKind]) -> bool {
+        kinds
+            .iter()
+            .any(|kind| self.gesture_action(&Gesture::new(fingers, *kind)).is_some())
+    }
+
     pub fn read_outputs(
         &mut self,
         output_state: &mut OutputConfigurationState<State>,
@@ -465,13 +559,53 @@ impl Config {
             .insert(infos, configs);
     }
 
-    pub fn xkb_config(&self) -> XkbConfig {
-        self.cosmic_conf.xkb_config.clone()
+    /// Keyboard layout of the seat called `seat`
+    pub fn xkb_config(&self, seat: &str) -> XkbConfig {
+        self.cosmic_conf
+            .seats
+            .get(seat)
+            .and_then(|seat| seat.xkb_config.clone())
+            .unwrap_or_else(|| self.cosmic_conf.xkb_config.clone())
+    }
+
+    /// Keyboard layout override of `device`, if any
+    pub fn device_xkb_config(&self, device: &InputDevice) -> Option<XkbConfig> {
+        self.cosmic_conf
+            .input_devices
+            .get(device.name())?
+            .xkb_config
+            .clone()
+    }
+
+    /// Name of the seat `device` is assigned to, if any
+    pub fn seat_for_device(&self, device: &InputDevice) -> Option<&str> {
+        self.cosmic_conf
+            .seats
+            .iter()
+            .find(|(_, seat)| {
+                seat.devices
+                    .iter()
+                    .any(|pattern| device_matches(device, pattern))
+            })
+            .map(|(name, _)| name.as_str())
     }
 
-    pub fn read_device(&self, device: &mut InputDevice) {
+    pub fn read_device(&self, device: &mut InputDevice, tablet_mode: bool) {
         let (device_config, default_config) = self.get_device_config(device);
         input_config::update_device(device, device_config, default_config);
+
+        if tablet_mode
+            && self.cosmic_conf.switches.tablet_mode_disable_touchpad
+            && device.config_tap_finger_count() > 0
+        {
+            if let Err(err) = device.config_send_events_set_mode(SendEventsMode::DISABLED) {
+                warn!(
+                    ?err,
+                    "Failed to disable touchpad {:?} in tablet mode.",
+                    device.name()
+                );
+            }
+        }
     }
 
     pub fn scroll_factor(&self, device: &InputDevice) -> f64 {
@@ -492,6 +626,17 @@ impl Config {
         )
     }
 
+    pub fn map_to_area(&self, device: &InputDevice) -> Option<MapToArea> {
+        let (device_config, default_config) = self.get_device_config(device);
+        Some(input_config::get_config(device_config, default_config, |x| x.map_to_area)?.0)
+    }
+
+    pub fn keep_aspect_ratio(&self, device: &InputDevice) -> bool {
+        let (device_config, default_config) = self.get_device_config(device);
+        input_config::get_config(device_config, default_config, |x| x.keep_aspect_ratio)
+            .map_or(false, |x| x.0)
+    }
+
     fn get_device_config(&self, device: &InputDevice) -> (Option<&InputConfig>, &InputConfig) {
         let default_config = if device.config_tap_finger_count() > 0 {
             &self.cosmic_conf.input_touchpad
@@ -503,7 +648,77 @@ impl Config {
     }
 }
 
-pub struct PersistenceGuard<'a, T: Serialize>(Option<PathBuf>, &'a mut T);
+/// Writes state files on a background thread
+#[derive(Debug)]
+struct StateWriter {
+    sender: Option<mpsc::Sender<(PathBuf, String)>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl StateWriter {
+    fn new() -> StateWriter {
+        let (sender, receiver) = mpsc::channel::<(PathBuf, String)>();
+        let thread = std::thread::Builder::new()
+            .name("cosmic-comp state writer".into())
+            .spawn(move || {
+                while let Ok((path, content)) = receiver.recv() {
+                    if let Err(err) = write_atomic(&path, &content) {
+                        warn!(?err, "Failed to persist {}.", path.display());
+                    }
+                }
+            })
+            .map_err(|err| error!(?err, "Failed to spawn state writer thread."))
+            .ok();
+
+        StateWriter {
+            sender: thread.is_some().then_some(sender),
+            thread,
+        }
+    }
+
+    fn write(&self, path: PathBuf, content: String) {
+        match self.sender.as_ref() {
+            Some(sender) => {
+                if let Err(mpsc::SendError((path, _))) = sender.send((path, content)) {
+                    warn!("Failed to persist {}, writer exited.", path.display());
+                }
+            }
+            None => {
+                if let Err(err) = write_atomic(&path, &content) {
+                    warn!(?err, "Failed to persist {}.", path.display());
+                }
+            }
+        }
+    }
+}
+
+impl Drop for StateWriter {
+    fn drop(&mut self) {
+        // finish pending writes before exiting
+        self.sender.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Replaces `path`, so that readers never see a partially written file
+fn write_atomic(path: &Path, content: &str) -> std::io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .truncate(true)
+        .write(true)
+        .open(&tmp)?;
+    file.write_all(content.as_bytes())?;
+    file.sync_all()?;
+    std::fs::rename(&tmp, path)
+}
+
+pub struct PersistenceGuard<'a, T: Serialize>(Option<PathBuf>, &'a mut T, &'a StateWriter);
 
 impl<'a, T: Serialize> std::ops::Deref for PersistenceGuard<'a, T> {
     type Target = T;
@@ -520,21 +735,10 @@ impl<'a, T: Serialize> std::ops::DerefMut for PersistenceGuard<'a, T> {
 
 impl<'a, T: Serialize> Drop for PersistenceGuard<'a, T> {
     fn drop(&mut self) {
-        if let Some(path) = self.0.as_ref() {
-            let writer = match OpenOptions::new()
-                .create(true)
-                .truncate(true)
-                .write(true)
-                .open(path)
-            {
-                Ok(writer) => writer,
-                Err(err) => {
-                    warn!(?err, "Failed to persist {}.", path.display());
-                    return;
-                }
-            };
-            if let Err(err) = ron::ser::to_writer_pretty(writer, &self.1, Default::default()) {
-                warn!(?err, "Failed to persist {}", path.display());
+        if let Some(path) = self.0.take() {
+            match ron::ser::to_string_pretty(&self.1, Default::default()) {
+                Ok(content) => self.2.write(path, content),
+                Err(err) => warn!(?err, "Failed to persist {}", path.display()),
             }
         }
     }
@@ -546,7 +750,15 @@ impl DynamicConfig {
     }
 
     pub fn outputs_mut(&mut self) -> PersistenceGuard<'_, OutputsConfig> {
-        PersistenceGuard(self.outputs.0.clone(), &mut self.outputs.1)
+        PersistenceGuard(self.outputs.0.clone(), &mut self.outputs.1, &self.writer)
+    }
+
+    pub fn session(&self) -> &SessionState {
+        &self.session.1
+    }
+
+    pub fn session_mut(&mut self) -> PersistenceGuard<'_, SessionState> {
+        PersistenceGuard(self.session.0.clone(), &mut self.session.1, &self.writer)
     }
 }
 
@@ -560,41 +772,96 @@ fn get_config<T: Default + serde::de::DeserializeOwned>(
     })
 }
 
-fn update_input(state: &mut State) {
+pub fn update_input(state: &mut State) {
     if let BackendData::Kms(ref mut kms_state) = &mut state.backend {
         for device in kms_state.input_devices.values_mut() {
-            state.common.config.read_device(device);
+            state
+                .common
+                .config
+                .read_device(device, state.common.tablet_mode);
+        }
+    }
+}
+
+fn update_xkb(state: &mut State) {
+    let seats = state
+        .common
+        .shell
+        .read()
+        .unwrap()
+        .seats
+        .iter()
+        .cloned()
+        .collect::<Vec<_>>();
+    for seat in seats.into_iter() {
+        let value = state.common.config.xkb_config(seat.name());
+        if let Some(active_keymap) = seat.user_data().get::<ActiveKeymap>() {
+            active_keymap.reset();
+        }
+        if let Some(keyboard) = seat.get_keyboard() {
+            keyboard.change_repeat_info(
+                (value.repeat_rate as i32).abs(), // Negative values are illegal
+                (value.repeat_delay as i32).abs(),
+            );
+            if let Err(err) = keyboard.set_xkb_config(state, xkb_config_to_wl(&value)) {
+                error!(?err, "Failed to load provided xkb config");
+                // TODO Revert to default?
+            }
         }
     }
 }
 
+/// Matches `device` by name, device node, sysfs path or udev `ID_PATH`
+fn device_matches(device: &InputDevice, pattern: &str) -> bool {
+    if device.name() == pattern || format!("/dev/input/{}", device.sysname()) == pattern {
+        return true;
+    }
+
+    // SAFETY: the udev device is only used for reading properties right away
+    let Some(udev_device) = (unsafe { device.udev_device() }) else {
+        return false;
+    };
+    udev_device.syspath() == Path::new(pattern)
+        || udev_device
+            .property_value("ID_PATH")
+            .is_some_and(|path| path == pattern)
+}
+
 fn config_changed(config: cosmic_config::Config, keys: Vec<String>, state: &mut State) {
     for key in &keys {
         match key.as_str() {
             "xkb_config" => {
                 let value = get_config::<XkbConfig>(&config, "xkb_config");
-                let seats = state
-                    .common
-                    .shell
-                    .read()
-                    .unwrap()
-                    .seats
-                    .iter()
-                    .cloned()
-                    .collect::<Vec<_>>();
-                for seat in seats.into_iter() {
-                    if let Some(keyboard) = seat.get_keyboard() {
-                        keyboard.change_repeat_info(
-                            (value.repeat_rate as i32).abs(), // Negative values are illegal
-                            (value.repeat_delay as i32).abs(),
-                        );
-                        if let Err(err) = keyboard.set_xkb_config(state, xkb_config_to_wl(&value)) {
-                            error!(?err, "Failed to load provided xkb config");
-                            // TODO Revert to default?
-                        }
-                    }
-                }
                 state.common.config.cosmic_conf.xkb_config = value;
+                update_xkb(state);
+            }
+            "seats" => {
+                let value = get_config::<HashMap<String, SeatConfig>>(&config, "seats");
+                if value != state.common.config.cosmic_conf.seats {
+                    state.common.config.cosmic_conf.seats = value;
+                    state.update_seats();
+                    update_xkb(state);
+                }
+            }
+            "keyboard_accessibility" => {
+                state.common.config.cosmic_conf.keyboard_accessibility =
+                    get_config::<KeyboardAccessibility>(&config, "keyboard_accessibility");
+            }
+            "idle" => {
+                state.common.config.cosmic_conf.idle = get_config::<IdleConfig>(&config, "idle");
+                state
+                    .common
+                    .idle_policy
+                    .rearm(&state.common.config, &state.common.event_loop_handle);
+            }
+            "night_light" => {
+                state.common.config.cosmic_conf.night_light =
+                    get_config::<NightLightConfig>(&config, "night_light");
+                state.refresh_night_light();
+            }
+            "keyboard_layout_per_window" => {
+                state.common.config.cosmic_conf.keyboard_layout_per_window =
+                    get_config::<bool>(&config, "keyboard_layout_per_window");
             }
             "input_default" => {
                 let value = get_config::<InputConfig>(&config, "input_default");
@@ -610,6 +877,7 @@ fn config_changed(config: cosmic_config::Config, keys: Vec<String>, state: &mut
                 let value = get_config::<HashMap<String, InputConfig>>(&config, "input_devices");
                 state.common.config.cosmic_conf.input_devices = value;
                 update_input(state);
+                update_xkb(state);
             }
             "workspaces" => {
                 state.common.config.cosmic_conf.workspaces =
@@ -623,6 +891,7 @@ fn config_changed(config: cosmic_config::Config, keys: Vec<String>, state: &mut
 
                     let mut shell = state.common.shell.write().unwrap();
                     let shell_ref = &mut *shell;
+                    shell_ref.session.mark_dirty();
                     shell_ref.workspaces.update_autotile(
                         new,
                         &mut state.common.workspace_state.update(),
@@ -637,6 +906,7 @@ fn config_changed(config: cosmic_config::Config, keys: Vec<String>, state: &mut
 
                     let mut shell = state.common.shell.write().unwrap();
                     let shell_ref = &mut *shell;
+                    shell_ref.session.mark_dirty();
                     shell_ref.workspaces.update_autotile_behavior(
                         new,
                         &mut state.common.workspace_state.update(),
@@ -651,6 +921,76 @@ fn config_changed(config: cosmic_config::Config, keys: Vec<String>, state: &mut
                     state.common.update_config();
                 }
             }
+            "dim_inactive" => {
+                let new = get_config::<f32>(&config, "dim_inactive");
+                if new != state.common.config.cosmic_conf.dim_inactive {
+                    state.common.config.cosmic_conf.dim_inactive = new;
+                    state.common.update_config();
+                }
+            }
+            "gaps" => {
+                let new = get_config::<GapsConfig>(&config, "gaps");
+                if new != state.common.config.cosmic_conf.gaps {
+                    state.common.config.cosmic_conf.gaps = new;
+                    state.common.update_config();
+                }
+            }
+            "layout_strategy" => {
+                let new = get_config::<LayoutStrategy>(&config, "layout_strategy");
+                if new != state.common.config.cosmic_conf.layout_strategy {
+                    state.common.config.cosmic_conf.layout_strategy = new;
+                    state.common.update_config();
+                }
+            }
+            "master_stack" => {
+                let new = get_config::<MasterStackConfig>(&config, "master_stack");
+                if new != state.common.config.cosmic_conf.master_stack {
+                    state.common.config.cosmic_conf.master_stack = new;
+                    state.common.update_config();
+                }
+            }
+            "compositor_bindings" => {
+                let new =
+                    get_config::<HashMap<String, CompositorAction>>(&config, "compositor_bindings");
+                state.common.config.compositor_bindings = key_bindings::compositor_bindings(&new);
+                state.common.config.pointer_bindings = key_bindings::pointer_bindings(&new);
+                state.common.config.cosmic_conf.compositor_bindings = new;
+            }
+            "binding_modes" => {
+                let new =
+                    get_config::<HashMap<String, BindingModeConfig>>(&config, "binding_modes");
+                state.common.config.binding_modes = key_bindings::binding_modes(&new);
+                state.common.config.cosmic_conf.binding_modes = new;
+            }
+            "switches" => {
+                let new = get_config::<SwitchConfig>(&config, "switches");
+                if new != state.common.config.cosmic_conf.switches {
+                    state.common.config.cosmic_conf.switches = new;
+                    update_input(state);
+                }
+            }
+            "tablet_pad_bindings" => {
+                let new =
+                    get_config::<HashMap<u32, shortcuts::Action>>(&config, "tablet_pad_bindings");
+                state.common.config.cosmic_conf.tablet_pad_bindings = new;
+            }
+            "gestures" => {
+                let new = get_config::<HashMap<Gesture, shortcuts::Action>>(&config, "gestures");
+                state.common.config.cosmic_conf.gestures = new;
+            }
+            "shortcuts_inhibit" => {
+                let new = get_config::<ShortcutsInhibitConfig>(&config, "shortcuts_inhibit");
+                state.common.config.shortcuts_inhibit_escape =
+                    key_bindings::shortcuts_inhibit_escape(&new.escape_binding);
+                state.common.config.cosmic_conf.shortcuts_inhibit = new;
+            }
+            "window_rules" => {
+                let new = get_config::<Vec<WindowRule>>(&config, "window_rules");
+                if new != state.common.config.cosmic_conf.window_rules {
+                    state.common.shell.write().unwrap().window_rules = WindowRules::new(&new);
+                    state.common.config.cosmic_conf.window_rules = new;
+                }
+            }
             "descale_xwayland" => {
                 let new = get_config::<bool>(&config, "descale_xwayland");
                 if new != state.common.config.cosmic_conf.descale_xwayland {
@@ -0,0 +1,75 @@
+use zbus::{blocking::Connection, zvariant::OwnedFd};
+
+#[zbus::proxy(
+    interface = "org.freedesktop.login1.Manager",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1"
+)]
+trait Manager {
+    /// Inhibit method
+    fn inhibit(&self, what: &str, who: &str, why: &str, mode: &str) -> zbus::Result<OwnedFd>;
+
+    /// Suspend method
+    fn suspend(&self, interactive: bool) -> zbus::Result<()>;
+
+    /// LidClosed property
+    #[zbus(property)]
+    fn lid_closed(&self) -> zbus::Result<bool>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.login1.Session",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1/session/auto"
+)]
+trait Session {
+    /// Lock method
+    fn lock(&self) -> zbus::Result<()>;
+}
+
+/// Take over handling of the lid switch from logind, for as long as the returned fd is kept open.
+///
+/// The fd has to be closed while the session is inactive, so logind handles the lid again.
+pub fn inhibit_lid_switch() -> anyhow::Result<OwnedFd> {
+    let conn = Connection::system()?;
+    let proxy = ManagerProxyBlocking::new(&conn)?;
+    Ok(proxy.inhibit(
+        "handle-lid-switch",
+        "cosmic-comp",
+        "Lid switch policy is handled by the compositor",
+        "block",
+    )?)
+}
+
+/// Whether the lid is closed right now, as lid switches only report changes
+pub fn lid_closed() -> anyhow::Result<bool> {
+    let conn = Connection::system()?;
+    let proxy = ManagerProxyBlocking::new(&conn)?;
+    Ok(proxy.lid_closed()?)
+}
+
+pub fn lock_session() {
+    spawn_call("lock", |conn| SessionProxyBlocking::new(conn)?.lock());
+}
+
+pub fn suspend() {
+    spawn_call("suspend", |conn| {
+        ManagerProxyBlocking::new(conn)?.suspend(false)
+    });
+}
+
+fn spawn_call(
+    name: &'static str,
+    call: impl FnOnce(&Connection) -> zbus::Result<()> + Send + 'static,
+) {
+    let result = std::thread::Builder::new()
+        .name(format!("logind-{}", name))
+        .spawn(move || {
+            if let Err(err) = Connection::system().and_then(|conn| call(&conn)) {
+                tracing::warn!(?err, "Failed to {} via logind", name);
+            }
+        });
+    if let Err(err) = result {
+        tracing::warn!(?err, "Failed to start logind helper thread");
+    }
+}
@@ -0,0 +1,351 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+
+use calloop::{
+    timer::{TimeoutAction, Timer},
+    RegistrationToken,
+};
+use smithay::{backend::input::KeyState, input::Seat};
+use tracing::warn;
+use xkbcommon::xkb::Keycode;
+
+use crate::state::State;
+
+/// Physical modifier keys (ctrl, shift, alt and super), as evdev codes offset by 8
+const MODIFIER_KEYS: [u32; 8] = [
+    29 + 8,
+    42 + 8,
+    54 + 8,
+    56 + 8,
+    97 + 8,
+    100 + 8,
+    125 + 8,
+    126 + 8,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Sticky {
+    /// Applies to the next key press
+    Latched,
+    /// Applies until the modifier is tapped again
+    Locked,
+}
+
+#[derive(Default)]
+struct FilterState {
+    /// Keys, whose press was passed on
+    down: HashSet<Keycode>,
+    /// Presses waiting for the slow keys delay
+    slow: HashMap<Keycode, RegistrationToken>,
+    /// Time of the last release of every key
+    released: HashMap<Keycode, u32>,
+    sticky: HashMap<Keycode, Sticky>,
+    /// Modifiers held down without pressing any other key so far
+    tapped: HashSet<Keycode>,
+}
+
+impl FilterState {
+    /// Whether a press follows the last release of the same key within the bounce keys `delay`
+    fn bounced(&self, keycode: Keycode, time: u32, delay: u32) -> bool {
+        self.released
+            .get(&keycode)
+            .is_some_and(|released| time.wrapping_sub(*released) < delay)
+    }
+
+    /// Applies sticky keys to an event, returning the events to pass on
+    fn sticky(
+        &mut self,
+        keycode: Keycode,
+        state: KeyState,
+        sticky_keys: bool,
+    ) -> Vec<(Keycode, KeyState)> {
+        let mut events = Vec::new();
+        if !sticky_keys {
+            // release modifiers, that were still latched or locked when sticky keys got disabled
+            events.extend(
+                self.sticky
+                    .drain()
+                    .map(|(key, _)| (key, KeyState::Released)),
+            );
+            events.push((keycode, state));
+        } else if MODIFIER_KEYS.contains(&keycode.raw()) {
+            if state == KeyState::Pressed {
+                self.tapped.insert(keycode);
+                // a sticky modifier is still held down
+                if !self.sticky.contains_key(&keycode) {
+                    events.push((keycode, state));
+                }
+            } else {
+                let tapped = self.tapped.remove(&keycode);
+                match (self.sticky.get(&keycode).copied(), tapped) {
+                    (None, true) => {
+                        self.sticky.insert(keycode, Sticky::Latched);
+                    }
+                    (Some(Sticky::Latched), true) => {
+                        self.sticky.insert(keycode, Sticky::Locked);
+                    }
+                    (Some(Sticky::Locked), false) => {}
+                    _ => {
+                        self.sticky.remove(&keycode);
+                        events.push((keycode, state));
+                    }
+                }
+            }
+        } else {
+            events.push((keycode, state));
+            if state == KeyState::Pressed {
+                self.tapped.clear();
+            } else {
+                let latched = self
+                    .sticky
+                    .iter()
+                    .filter(|(_, sticky)| **sticky == Sticky::Latched)
+                    .map(|(key, _)| *key)
+                    .collect::<Vec<_>>();
+                for key in latched {
+                    self.sticky.remove(&key);
+                    events.push((key, KeyState::Released));
+                }
+            }
+        }
+        events
+    }
+
+    /// Tracks passed on presses, returns whether the event should be passed on
+    fn pass(&mut self, keycode: Keycode, state: KeyState) -> bool {
+        match state {
+            KeyState::Pressed => self.down.insert(keycode),
+            KeyState::Released => self.down.remove(&keycode),
+        }
+    }
+}
+
+/// State of the keyboard accessibility filters of a seat
+#[derive(Default)]
+struct KeyboardFilter(RefCell<FilterState>);
+
+impl State {
+    /// Applies the bounce, slow and sticky keys filters to a key event
+    /// and passes the remaining events on to `process_key`.
+    pub(super) fn filter_key(
+        &mut self,
+        seat: &Seat<State>,
+        keycode: Keycode,
+        state: KeyState,
+        time: u32,
+        needs_key_repetition: bool,
+    ) {
+        let config = self.common.config.cosmic_conf.keyboard_accessibility;
+        seat.user_data().insert_if_missing(KeyboardFilter::default);
+        let filter = &seat.user_data().get::<KeyboardFilter>().unwrap().0;
+
+        if state == KeyState::Released {
+            filter.borrow_mut().released.insert(keycode, time);
+            let pending = filter.borrow_mut().slow.remove(&keycode);
+            if let Some(token) = pending {
+                // released before the slow keys delay passed
+                self.common.event_loop_handle.remove(token);
+                return;
+            }
+            self.accept_key(seat, keycode, state, time, needs_key_repetition);
+            return;
+        }
+
+        if let Some(delay) = config.bounce_keys {
+            if filter.borrow().bounced(keycode, time, delay) {
+                return;
+            }
+        }
+
+        if let Some(delay) = config.slow_keys {
+            if filter.borrow().slow.contains_key(&keycode) {
+                return;
+            }
+            let seat_clone = seat.clone();
+            let res = self.common.event_loop_handle.insert_source(
+                Timer::from_duration(Duration::from_millis(delay as u64)),
+                move |_, _, state| {
+                    let filter = &seat_clone.user_data().get::<KeyboardFilter>().unwrap().0;
+                    let accepted = filter.borrow_mut().slow.remove(&keycode).is_some();
+                    if accepted {
+                        state.accept_key(
+                            &seat_clone,
+                            keycode,
+                            KeyState::Pressed,
+                            time.wrapping_add(delay),
+                            needs_key_repetition,
+                        );
+                    }
+                    TimeoutAction::Drop
+                },
+            );
+            match res {
+                Ok(token) => {
+                    filter.borrow_mut().slow.insert(keycode, token);
+                    return;
+                }
+                Err(err) => warn!(?err, "Failed to start slow keys timer."),
+            }
+        }
+
+        self.accept_key(seat, keycode, state, time, needs_key_repetition);
+    }
+
+    /// Applies sticky keys and only passes on releases of keys, whose press was passed on
+    fn accept_key(
+        &mut self,
+        seat: &Seat<State>,
+        keycode: Keycode,
+        state: KeyState,
+        time: u32,
+        needs_key_repetition: bool,
+    ) {
+        let sticky_keys = self
+            .common
+            .config
+            .cosmic_conf
+            .keyboard_accessibility
+            .sticky_keys;
+        let filter = &seat.user_data().get::<KeyboardFilter>().unwrap().0;
+
+        let events = filter.borrow_mut().sticky(keycode, state, sticky_keys);
+        for (keycode, state) in events {
+            let passed = filter.borrow_mut().pass(keycode, state);
+            if passed {
+                self.process_key(seat, keycode, state, time, needs_key_repetition);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{FilterState, Sticky};
+    use smithay::backend::input::KeyState;
+    use xkbcommon::xkb::Keycode;
+
+    const CTRL: u32 = 29 + 8;
+    const SHIFT: u32 = 42 + 8;
+    const A: u32 = 30 + 8;
+
+    fn key(filter: &mut FilterState, code: u32, state: KeyState) -> Vec<(u32, KeyState)> {
+        filter
+            .sticky(Keycode::new(code), state, true)
+            .into_iter()
+            .filter(|(keycode, state)| filter.pass(*keycode, *state))
+            .map(|(keycode, state)| (keycode.raw(), state))
+            .collect()
+    }
+
+    fn tap(filter: &mut FilterState, code: u32) -> Vec<(u32, KeyState)> {
+        let mut events = key(filter, code, KeyState::Pressed);
+        events.extend(key(filter, code, KeyState::Released));
+        events
+    }
+
+    #[test]
+    fn test_bounce() {
+        let mut filter = FilterState::default();
+        filter.released.insert(Keycode::new(A), 1000);
+        assert!(filter.bounced(Keycode::new(A), 1050, 100));
+        assert!(!filter.bounced(Keycode::new(A), 1100, 100));
+        assert!(!filter.bounced(Keycode::new(SHIFT), 1050, 100));
+        // timestamps wrap around
+        filter.released.insert(Keycode::new(A), u32::MAX - 10);
+        assert!(filter.bounced(Keycode::new(A), 20, 100));
+    }
+
+    #[test]
+    fn test_sticky_latch() {
+        let mut filter = FilterState::default();
+        assert_eq!(tap(&mut filter, CTRL), vec![(CTRL, KeyState::Pressed)]);
+        assert_eq!(
+            filter.sticky.get(&Keycode::new(CTRL)),
+            Some(&Sticky::Latched)
+        );
+
+        // applies to the next key only
+        assert_eq!(
+            tap(&mut filter, A),
+            vec![
+                (A, KeyState::Pressed),
+                (A, KeyState::Released),
+                (CTRL, KeyState::Released)
+            ]
+        );
+        assert!(filter.sticky.is_empty());
+        assert_eq!(
+            tap(&mut filter, A),
+            vec![(A, KeyState::Pressed), (A, KeyState::Released)]
+        );
+        assert!(filter.down.is_empty());
+    }
+
+    #[test]
+    fn test_sticky_lock() {
+        let mut filter = FilterState::default();
+        tap(&mut filter, SHIFT);
+        assert!(tap(&mut filter, SHIFT).is_empty());
+        assert_eq!(
+            filter.sticky.get(&Keycode::new(SHIFT)),
+            Some(&Sticky::Locked)
+        );
+
+        // applies until tapped again
+        for _ in 0..2 {
+            assert_eq!(
+                tap(&mut filter, A),
+                vec![(A, KeyState::Pressed), (A, KeyState::Released)]
+            );
+        }
+        assert_eq!(tap(&mut filter, SHIFT), vec![(SHIFT, KeyState::Released)]);
+        assert!(filter.sticky.is_empty());
+        assert!(filter.down.is_empty());
+    }
+
+    #[test]
+    fn test_held_modifier() {
+        let mut filter = FilterState::default();
+        // used like a regular modifier, when held down while pressing another key
+        assert_eq!(
+            key(&mut filter, CTRL, KeyState::Pressed),
+            vec![(CTRL, KeyState::Pressed)]
+        );
+        tap(&mut filter, A);
+        assert_eq!(
+            key(&mut filter, CTRL, KeyState::Released),
+            vec![(CTRL, KeyState::Released)]
+        );
+        assert!(filter.sticky.is_empty());
+        assert!(filter.down.is_empty());
+    }
+
+    #[test]
+    fn test_disable_sticky() {
+        let mut filter = FilterState::default();
+        tap(&mut filter, CTRL);
+        tap(&mut filter, SHIFT);
+        tap(&mut filter, SHIFT);
+
+        let mut events = filter.sticky(Keycode::new(A), KeyState::Pressed, false);
+        events.sort_by_key(|(keycode, _)| keycode.raw());
+        assert_eq!(
+            events
+                .into_iter()
+                .filter(|(keycode, state)| filter.pass(*keycode, *state))
+                .map(|(keycode, state)| (keycode.raw(), state))
+                .collect::<Vec<_>>(),
+            vec![
+                (CTRL, KeyState::Released),
+                (A, KeyState::Pressed),
+                (SHIFT, KeyState::Released)
+            ]
+        );
+        assert!(filter.sticky.is_empty());
+    }
+}
@@ -1,10 +1,15 @@
-use cosmic_settings_config::shortcuts::action::Direction;
+use cosmic_settings_config::shortcuts::{self, action::Direction};
 use smithay::utils::{Logical, Point};
 use std::{collections::VecDeque, time::Duration};
 use tracing::trace;
 
 const HISTORY_LIMIT: Duration = Duration::from_millis(150);
 const DECELERATION_TOUCHPAD: f64 = 0.997;
+/// Distance a swipe has to travel to trigger a discrete action
+const SWIPE_THRESHOLD: f64 = 100.0;
+/// Scale a pinch has to cross to trigger a pinch in/out action
+const PINCH_IN_THRESHOLD: f64 = 0.8;
+const PINCH_OUT_THRESHOLD: f64 = 1.25;
 
 #[derive(Debug, Clone, Copy)]
 pub struct SwipeEvent {
@@ -12,33 +17,64 @@ pub struct SwipeEvent {
     timestamp: Duration,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum SwipeAction {
+    /// Interactive workspace switch, following the fingers
     NextWorkspace,
     PrevWorkspace,
+    /// Any other action, triggered once the gesture ends
+    Shortcut(shortcuts::Action),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GestureType {
+    Swipe,
+    Pinch,
+    Hold,
 }
 
 #[derive(Debug, Clone)]
 pub struct GestureState {
+    pub gesture_type: GestureType,
     pub fingers: u32,
     pub direction: Option<Direction>,
     pub action: Option<SwipeAction>,
     pub delta: f64,
+    /// Current scale of a pinch gesture
+    pub scale: f64,
     // Delta tracking inspired by Niri (GPL-3.0) https://github.com/YaLTeR/niri/tree/v0.1.3
     pub history: VecDeque<SwipeEvent>,
 }
 
 impl GestureState {
-    pub fn new(fingers: u32) -> Self {
+    pub fn new(gesture_type: GestureType, fingers: u32) -> Self {
         GestureState {
+            gesture_type,
             fingers,
             direction: None,
             action: None,
             delta: 0.0,
+            scale: 1.0,
             history: VecDeque::new(),
         }
     }
 
+    /// Whether a swipe travelled far enough to trigger a discrete action
+    pub fn swipe_completed(&self) -> bool {
+        self.delta >= SWIPE_THRESHOLD
+    }
+
+    /// Whether a pinch ended zoomed in (`Some(true)`), out (`Some(false)`) or neither
+    pub fn pinch_in(&self) -> Option<bool> {
+        if self.scale <= PINCH_IN_THRESHOLD {
+            Some(true)
+        } else if self.scale >= PINCH_OUT_THRESHOLD {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
     pub fn update(&mut self, movement: Point<f64, Logical>, timestamp: Duration) -> bool {
         let first_update = self.direction.is_none();
 
@@ -129,7 +165,7 @@ impl GestureState {
 
 impl Default for GestureState {
     fn default() -> Self {
-        GestureState::new(0)
+        GestureState::new(GestureType::Swipe, 0)
     }
 }
 
@@ -1,15 +1,20 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
 use crate::{
+    backend::render::cursor::{TabletToolCursor, TabletToolCursors},
     config::{
         key_bindings::{
             cosmic_keystate_from_smithay, cosmic_modifiers_eq_smithay,
-            cosmic_modifiers_from_smithay,
+            cosmic_modifiers_from_smithay, is_shortcuts_inhibit_escape, PointerTrigger,
         },
-        Action, Config, PrivateAction,
+        xkb_config_to_wl, Action, Config, PrivateAction,
     },
-    input::gestures::{GestureState, SwipeAction},
+    input::gestures::{GestureState, GestureType, SwipeAction},
     shell::{
+        element::{
+            mode_indicator::{mode_indicator, ModeIndicator},
+            stack::MoveMode as StackMoveMode,
+        },
         focus::target::{KeyboardFocusTarget, PointerFocusTarget},
         grabs::{ReleaseMode, ResizeEdge},
         layout::{
@@ -28,17 +33,23 @@ use crate::{
     },
 };
 use calloop::{timer::Timer, RegistrationToken};
-use cosmic_comp_config::{workspace::WorkspaceLayout, TileBehavior};
+use cosmic_comp_config::{
+    bindings::{self, CompositorAction},
+    gestures::{Gesture, GestureKind},
+    input::MapToArea,
+    workspace::WorkspaceLayout,
+    TileBehavior,
+};
 use cosmic_config::ConfigSet;
 use cosmic_settings_config::shortcuts;
 use cosmic_settings_config::shortcuts::action::{Direction, FocusDirection, ResizeDirection};
 use smithay::{
     backend::input::{
-        AbsolutePositionEvent, Axis, AxisSource, Device, DeviceCapability, GestureBeginEvent,
-        GestureEndEvent, GesturePinchUpdateEvent as _, GestureSwipeUpdateEvent as _, InputBackend,
-        InputEvent, KeyState, PointerAxisEvent, ProximityState, TabletToolButtonEvent,
-        TabletToolEvent, TabletToolProximityEvent, TabletToolTipEvent, TabletToolTipState,
-        TouchEvent,
+        AbsolutePositionEvent, Axis, AxisSource, Device, GestureBeginEvent, GestureEndEvent,
+        GesturePinchUpdateEvent as _, GestureSwipeUpdateEvent as _, InputBackend, InputEvent,
+        KeyState, PointerAxisEvent, ProximityState, Switch, SwitchState, SwitchToggleEvent,
+        TabletToolButtonEvent, TabletToolEvent, TabletToolProximityEvent, TabletToolTipEvent,
+        TabletToolTipState, TouchEvent,
     },
     desktop::{
         layer_map_for_output, space::SpaceElement, utils::under_from_surface_tree,
@@ -47,7 +58,7 @@ use smithay::{
     input::{
         keyboard::{FilterResult, KeysymHandle},
         pointer::{
-            AxisFrame, ButtonEvent, GestureHoldBeginEvent, GestureHoldEndEvent,
+            AxisFrame, ButtonEvent, CursorImageStatus, GestureHoldBeginEvent, GestureHoldEndEvent,
             GesturePinchBeginEvent, GesturePinchEndEvent, GesturePinchUpdateEvent,
             GestureSwipeBeginEvent, GestureSwipeEndEvent, GestureSwipeUpdateEvent, MotionEvent,
             PointerGrab, RelativeMotionEvent,
@@ -57,9 +68,16 @@ use smithay::{
     },
     output::Output,
     reexports::{
-        input::Device as InputDevice, wayland_server::protocol::wl_shm::Format as ShmFormat,
+        input::{
+            event::{
+                tablet_pad::{ButtonState as PadButtonState, TabletPadEvent},
+                EventTrait,
+            },
+            Device as InputDevice,
+        },
+        wayland_server::protocol::wl_shm::Format as ShmFormat,
     },
-    utils::{Point, Serial, SERIAL_COUNTER},
+    utils::{Logical, Point, Rectangle, Serial, Size, SERIAL_COUNTER},
     wayland::{
         keyboard_shortcuts_inhibit::KeyboardShortcutsInhibitorSeat,
         pointer_constraints::{with_pointer_constraint, PointerConstraint},
@@ -71,7 +89,7 @@ use smithay::{
 };
 #[cfg(not(feature = "debug"))]
 use tracing::info;
-use tracing::{error, trace};
+use tracing::{error, trace, warn};
 use xkbcommon::xkb::{Keycode, Keysym};
 
 use std::{
@@ -80,11 +98,14 @@ use std::{
     cell::RefCell,
     collections::HashSet,
     os::unix::process::CommandExt,
+    sync::Mutex,
     thread,
     time::{Duration, Instant},
 };
 
+mod accessibility;
 pub mod gestures;
+mod switches;
 
 #[derive(Default)]
 pub struct SupressedKeys(RefCell<Vec<(Keycode, Option<RegistrationToken>)>>);
@@ -92,6 +113,26 @@ pub struct SupressedKeys(RefCell<Vec<(Keycode, Option<RegistrationToken>)>>);
 pub struct SupressedButtons(RefCell<HashSet<u32>>);
 #[derive(Default, Debug)]
 pub struct ModifiersShortcutQueue(RefCell<Option<shortcuts::Binding>>);
+/// Wheel scrolling accumulated towards the next scroll binding, in v120 units
+#[derive(Default)]
+struct ScrollBindingAccumulator(RefCell<[f64; 2]>);
+
+/// Binding mode active on a seat and its on-screen indicator
+pub struct BindingModeState {
+    pub name: String,
+    pub indicator: ModeIndicator,
+}
+pub type SeatBindingMode = Mutex<Option<BindingModeState>>;
+/// Device, whose keyboard layout override is currently loaded into the keymap of a seat
+#[derive(Default, Debug)]
+pub struct ActiveKeymap(RefCell<Option<String>>);
+
+impl ActiveKeymap {
+    /// Forgets the override, after the keymap of the seat was reloaded
+    pub fn reset(&self) {
+        self.0.borrow_mut().take();
+    }
+}
 
 impl SupressedKeys {
     fn add(&self, keysym: &KeysymHandle, token: impl Into<Option<RegistrationToken>>) {
@@ -160,39 +201,28 @@ impl State {
         <B as InputBackend>::Device: 'static,
     {
         use smithay::backend::input::Event;
+        if !matches!(
+            event,
+            InputEvent::DeviceAdded { .. } | InputEvent::DeviceRemoved { .. }
+        ) {
+            self.common
+                .idle_policy
+                .notify_activity(&self.common.event_loop_handle);
+        }
         match event {
             InputEvent::DeviceAdded { device } => {
                 let shell = self.common.shell.read().unwrap();
-                let seat = shell.seats.last_active();
-                seat.devices().add_device(&device);
-                if device.has_capability(DeviceCapability::TabletTool) {
-                    seat.tablet_seat().add_tablet::<Self>(
-                        &self.common.display_handle,
-                        &TabletDescriptor::from(&device),
-                    );
-                }
+                let seat = shell.seats.assigned_seat(&self.common.config, &device);
+                seat.attach_device(&self.common.display_handle, &device);
             }
             InputEvent::DeviceRemoved { device } => {
-                for seat in &mut self.common.shell.read().unwrap().seats.iter() {
-                    let devices = seat.devices();
-                    if devices.has_device(&device) {
-                        devices.remove_device(&device);
-                        if device.has_capability(DeviceCapability::TabletTool) {
-                            seat.tablet_seat()
-                                .remove_tablet(&TabletDescriptor::from(&device));
-                            if seat.tablet_seat().count_tablets() == 0 {
-                                seat.tablet_seat().clear_tools();
-                            }
-                        }
-                        break;
-                    }
+                if let Some(seat) = self.common.shell.read().unwrap().seats.for_device(&device) {
+                    seat.detach_device(&device);
                 }
             }
             InputEvent::Keyboard { event, .. } => {
                 use smithay::backend::input::KeyboardKeyEvent;
 
-                let loop_handle = self.common.event_loop_handle.clone();
-
                 let maybe_seat = self
                     .common
                     .shell
@@ -203,315 +233,20 @@ impl State {
                     .cloned();
                 if let Some(seat) = maybe_seat {
                     self.common.idle_notifier_state.notify_activity(&seat);
-                    let current_output = seat.active_output();
-                    let shortcuts_inhibited = self
-                        .common
+                    self.common
                         .shell
-                        .read()
+                        .write()
                         .unwrap()
-                        .active_space(&current_output)
-                        .focus_stack
-                        .get(&seat)
-                        .last()
-                        .and_then(|window| {
-                            window.wl_surface().and_then(|surface| {
-                                seat.keyboard_shortcuts_inhibitor_for_surface(&surface)
-                            })
-                        })
-                        .map(|inhibitor| inhibitor.is_active())
-                        .unwrap_or(false);
+                        .seats
+                        .update_last_active(&seat);
+                    self.update_keymap(&seat, &event.device());
 
                     let keycode = event.key_code();
                     let state = event.state();
                     trace!(?keycode, ?state, "key");
 
-                    let serial = SERIAL_COUNTER.next_serial();
                     let time = Event::time_msec(&event);
-                    let keyboard = seat.get_keyboard().unwrap();
-                    let pointer = seat.get_pointer().unwrap();
-                    let is_grabbed = keyboard.is_grabbed() || pointer.is_grabbed();
-                    let current_focus = keyboard.current_focus();
-                    let shell_ref = self.common.shell.clone();
-                    let mut shell = shell_ref.write().unwrap();
-                    if let Some((action, pattern)) = keyboard
-                            .input(
-                                self,
-                                keycode,
-                                state,
-                                serial,
-                                time,
-                                |data, modifiers, handle| {
-                                    // Leave move overview mode, if any modifier was released
-                                    if let Some(Trigger::KeyboardMove(action_modifiers)) =
-                                        shell.overview_mode().0.active_trigger()
-                                    {
-                                        if (action_modifiers.ctrl && !modifiers.ctrl)
-                                            || (action_modifiers.alt && !modifiers.alt)
-                                            || (action_modifiers.logo && !modifiers.logo)
-                                            || (action_modifiers.shift && !modifiers.shift)
-                                        {
-                                            shell.set_overview_mode(None, data.common.event_loop_handle.clone());
-                                        }
-                                    }
-                                    // Leave swap overview mode, if any key was released
-                                    if let Some(Trigger::KeyboardSwap(action_pattern, old_descriptor)) =
-                                        shell.overview_mode().0.active_trigger()
-                                    {
-                                        if (action_pattern.modifiers.ctrl && !modifiers.ctrl)
-                                            || (action_pattern.modifiers.alt && !modifiers.alt)
-                                            || (action_pattern.modifiers.logo && !modifiers.logo)
-                                            || (action_pattern.modifiers.shift && !modifiers.shift)
-                                            || (action_pattern.key.is_some() && handle.raw_syms().contains(&action_pattern.key.unwrap()) && state == KeyState::Released)
-                                        {
-                                            shell.set_overview_mode(None, data.common.event_loop_handle.clone());
-
-                                            if let Some(focus) = current_focus {
-                                                if let Some(new_descriptor) = shell.workspaces.active(&current_output).1.node_desc(focus) {
-                                                    let mut spaces = shell.workspaces.spaces_mut();
-                                                    if old_descriptor.handle != new_descriptor.handle {
-                                                        let (mut old_w, mut other_w) = spaces.partition::<Vec<_>, _>(|w| w.handle == old_descriptor.handle);
-                                                        if let Some(old_workspace) = old_w.get_mut(0) {
-                                                            if let Some(new_workspace) = other_w.iter_mut().find(|w| w.handle == new_descriptor.handle) {
-                                                                if let Some(focus) = TilingLayout::swap_trees(&mut old_workspace.tiling_layer, Some(&mut new_workspace.tiling_layer), &old_descriptor, &new_descriptor) {
-                                                                    let seat = seat.clone();
-                                                                    data.common.event_loop_handle.insert_idle(move |state| {
-                                                                        Shell::set_focus(state, Some(&focus), &seat, None);
-                                                                    });
-                                                                }
-                                                                old_workspace.refresh_focus_stack();
-                                                                new_workspace.refresh_focus_stack();
-                                                            }
-                                                        }
-                                                    } else {
-                                                        if let Some(workspace) = spaces.find(|w| w.handle == new_descriptor.handle) {
-                                                            if let Some(focus) = TilingLayout::swap_trees(&mut workspace.tiling_layer, None, &old_descriptor, &new_descriptor) {
-                                                                std::mem::drop(spaces);
-                                                                let seat = seat.clone();
-                                                                data.common.event_loop_handle.insert_idle(move |state| {
-                                                                    Shell::set_focus(state, Some(&focus), &seat, None);
-                                                                });
-                                                            }
-                                                            workspace.refresh_focus_stack();
-                                                        }
-                                                    }
-                                                }
-                                            } else {
-                                                let new_workspace = shell.workspaces.active(&current_output).1.handle;
-                                                if new_workspace != old_descriptor.handle {
-                                                    let spaces = shell.workspaces.spaces_mut();
-                                                    let (mut old_w, mut other_w) = spaces.partition::<Vec<_>, _>(|w| w.handle == old_descriptor.handle);
-                                                    if let Some(old_workspace) = old_w.get_mut(0) {
-                                                        if let Some(new_workspace) = other_w.iter_mut().find(|w| w.handle == new_workspace) {
-                                                            if new_workspace.tiling_layer.windows().next().is_none() {
-                                                                if let Some(focus) = TilingLayout::move_tree(&mut old_workspace.tiling_layer, &mut new_workspace.tiling_layer, &new_workspace.handle, &seat, new_workspace.focus_stack.get(&seat).iter(), old_descriptor.clone()) {
-                                                                    let seat = seat.clone();
-                                                                    data.common.event_loop_handle.insert_idle(move |state| {
-                                                                        Shell::set_focus(state, Some(&focus), &seat, None);
-                                                                    });
-                                                                }
-                                                                old_workspace.refresh_focus_stack();
-                                                            }
-                                                        }
-                                                    }
-                                                }
-                                            }
-                                        }
-                                    }
-
-                                    // Leave or update resize mode, if modifiers changed or initial key was released
-                                    if let Some(action_pattern) =
-                                        shell.resize_mode().0.active_binding()
-                                    {
-                                        if action_pattern.key.is_some() && state == KeyState::Released
-                                            && handle.raw_syms().contains(&action_pattern.key.unwrap())
-                                        {
-                                            shell.set_resize_mode(None, &data.common.config, data.common.event_loop_handle.clone());
-                                        } else if !cosmic_modifiers_eq_smithay(&action_pattern.modifiers, modifiers) {
-                                            let mut new_pattern = action_pattern.clone();
-                                            new_pattern.modifiers = cosmic_modifiers_from_smithay(modifiers.clone());
-                                            let enabled = data
-                                                .common
-                                                .config
-                                                .shortcuts
-                                                .iter()
-                                                .find_map(move |(binding, action)| {
-                                                    if binding == &new_pattern
-                                                        && matches!(action, shortcuts::Action::Resizing(_))
-                                                    {
-                                                        let shortcuts::Action::Resizing(direction) = action else { unreachable!() };
-                                                        Some((new_pattern.clone(), *direction))
-                                                    } else {
-                                                        None
-                                                    }
-                                                });
-                                            shell.set_resize_mode(enabled, &data.common.config, data.common.event_loop_handle.clone());
-                                        }
-                                    }
-
-                                    // Special case resizing with regards to arrow keys
-                                    if let Some(direction) =
-                                        shell.resize_mode().0.active_direction()
-                                    {
-                                        let resize_edge = match handle.modified_sym() {
-                                            Keysym::Left | Keysym::h | Keysym::H => Some(ResizeEdge::LEFT),
-                                            Keysym::Down | Keysym::j | Keysym::J => Some(ResizeEdge::BOTTOM),
-                                            Keysym::Up | Keysym::k | Keysym::K => Some(ResizeEdge::TOP),
-                                            Keysym::Right | Keysym::l | Keysym::L => Some(ResizeEdge::RIGHT),
-                                            _ => None,
-                                        };
-
-                                        if let Some(mut edge) = resize_edge {
-                                            if direction == ResizeDirection::Inwards {
-                                                edge.flip_direction();
-                                            }
-                                            let action = Action::Private(PrivateAction::Resizing(direction, edge.into(), cosmic_keystate_from_smithay(state)));
-                                            let key_pattern = shortcuts::Binding {
-                                                modifiers: cosmic_modifiers_from_smithay(modifiers.clone()),
-                                                key: Some(Keysym::new(handle.raw_code().raw())),
-                                                description: None,
-                                            };
-
-                                            if state == KeyState::Released {
-                                                if let Some(tokens) = seat.supressed_keys().filter(&handle) {
-                                                    for token in tokens {
-                                                        loop_handle.remove(token);
-                                                    }
-                                                }
-                                            } else {
-                                                let token = if needs_key_repetition {
-                                                    let seat_clone = seat.clone();
-                                                    let action_clone = action.clone();
-                                                    let key_pattern_clone = key_pattern.clone();
-                                                    let start = Instant::now();
-                                                    loop_handle.insert_source(Timer::from_duration(Duration::from_millis(200)), move |current, _, state| {
-                                                        let duration = current.duration_since(start).as_millis();
-                                                        state.handle_action(action_clone.clone(), &seat_clone, serial, time.overflowing_add(duration as u32).0, key_pattern_clone.clone(), None, true);
-                                                        calloop::timer::TimeoutAction::ToDuration(Duration::from_millis(25))
-                                                    }).ok()
-                                                } else { None };
-
-                                                seat.supressed_keys()
-                                                        .add(&handle, token);
-                                            }
-                                            return FilterResult::Intercept(Some((
-                                                action,
-                                                key_pattern
-                                            )));
-                                        }
-                                    }
-
-                                    std::mem::drop(shell);
-
-                                    // cancel grabs
-                                    if is_grabbed
-                                        && handle.modified_sym() == Keysym::Escape
-                                        && state == KeyState::Pressed
-                                        && !modifiers.alt
-                                        && !modifiers.ctrl
-                                        && !modifiers.logo
-                                        && !modifiers.shift
-                                    {
-                                        seat.supressed_keys()
-                                                .add(&handle, None);
-                                        return FilterResult::Intercept(Some((
-                                            Action::Private(PrivateAction::Escape),
-                                            shortcuts::Binding {
-                                                modifiers: shortcuts::Modifiers::default(),
-                                                key: Some(Keysym::Escape),
-                                                description: None,
-                                            }
-                                        )));
-                                    }
-
-                                    // Skip released events for initially surpressed keys
-                                    if state == KeyState::Released {
-                                        if let Some(tokens) = seat.supressed_keys().filter(&handle) {
-                                            for token in tokens {
-                                                loop_handle.remove(token);
-                                            }
-                                            return FilterResult::Intercept(None);
-                                        }
-                                    }
-
-                                    // Handle VT switches
-                                    if state == KeyState::Pressed
-                                        && (Keysym::XF86_Switch_VT_1.raw() ..= Keysym::XF86_Switch_VT_12.raw())
-                                            .contains(&handle.modified_sym().raw())
-                                    {
-                                        if let Err(err) = data.backend.kms().switch_vt(
-                                            (handle.modified_sym().raw() - Keysym::XF86_Switch_VT_1.raw()
-                                                + 1)
-                                                as i32,
-                                        ) {
-                                            error!(?err, "Failed switching virtual terminal.");
-                                        }
-                                        seat.supressed_keys().add(&handle, None);
-                                        return FilterResult::Intercept(None);
-                                    }
-
-                                    // handle the rest of the global shortcuts
-                                    let mut clear_queue = true;
-                                    if !shortcuts_inhibited {
-                                        let modifiers_queue = seat.modifiers_shortcut_queue();
-
-                                        for (binding, action) in
-                                            data.common.config.shortcuts.iter()
-                                        {
-                                            if *action == shortcuts::Action::Disable {
-                                                continue;
-                                            }
-
-                                            // is this a released (triggered) modifier-only binding?
-                                            if binding.key.is_none()
-                                                && state == KeyState::Released
-                                                && !cosmic_modifiers_eq_smithay(&binding.modifiers, modifiers)
-                                                && modifiers_queue.take(binding)
-                                            {
-                                                modifiers_queue.clear();
-                                                return FilterResult::Intercept(Some((
-                                                    Action::Shortcut(action.clone()),
-                                                    binding.clone(),
-                                                )));
-                                            }
-
-                                            // could this potentially become a modifier-only binding?
-                                            if binding.key.is_none() && state == KeyState::Pressed && cosmic_modifiers_eq_smithay(&binding.modifiers, modifiers) {
-                                                modifiers_queue.set(binding.clone());
-                                                clear_queue = false;
-                                            }
-
-                                            // is this a normal binding?
-                                            if binding.key.is_some()
-                                                && state == KeyState::Pressed
-                                                && handle.raw_syms().contains(&binding.key.unwrap())
-                                                && cosmic_modifiers_eq_smithay(&binding.modifiers, modifiers)
-                                            {
-                                                modifiers_queue.clear();
-                                                seat.supressed_keys().add(&handle, None);
-                                                return FilterResult::Intercept(Some((
-                                                    Action::Shortcut(action.clone()),
-                                                    binding.clone(),
-                                                )));
-                                            }
-                                        }
-                                    }
-
-                                    // no binding
-                                    if clear_queue {
-                                        seat.modifiers_shortcut_queue().clear();
-                                    }
-                                    // keys are passed through to apps
-                                    FilterResult::Forward
-                                },
-                            )
-                            .flatten()
-                        {
-                            if pattern.key.is_none() && state == KeyState::Released {
-                                // we still want to send release-events and not have apps stuck on some modifiers.
-                                keyboard.input(self, keycode, state, serial, time, |_, _, _| FilterResult::<()>::Forward);
-                            }
-                            self.handle_action(action, &seat, serial, time, pattern, None, true)
-                        }
+                    self.filter_key(&seat, keycode, state, time, needs_key_repetition);
                 }
             }
             InputEvent::PointerMotion { event, .. } => {
@@ -783,10 +518,28 @@ impl State {
                 let mut shell = self.common.shell.write().unwrap();
                 if let Some(seat) = shell.seats.for_device(&event.device()).cloned() {
                     self.common.idle_notifier_state.notify_activity(&seat);
+                    shell.seats.update_last_active(&seat);
 
                     let serial = SERIAL_COUNTER.next_serial();
                     let button = event.button_code();
                     let mut pass_event = !seat.supressed_buttons().remove(button);
+
+                    // configured pointer bindings replace the builtin ones
+                    let binding_action = (event.state() == ButtonState::Pressed
+                        && !seat.get_pointer().unwrap().is_grabbed()
+                        && shell.session_lock.is_none())
+                    .then(|| {
+                        self.common.config.pointer_binding_action(
+                            &seat.get_keyboard().unwrap().modifier_state(),
+                            PointerTrigger::Button(button),
+                        )
+                    })
+                    .flatten();
+                    if binding_action.is_some() {
+                        pass_event = false;
+                        seat.supressed_buttons().add(button);
+                    }
+
                     if event.state() == ButtonState::Pressed {
                         // change the keyboard focus unless the pointer is grabbed
                         // We test for any matching surface type here but always use the root
@@ -859,7 +612,9 @@ impl State {
                                     // Don't check override redirect windows, because we don't set keyboard focus to them explicitly.
                                     // These cases are handled by the XwaylandKeyboardGrab.
                                     if let Some(target) = shell.element_under(pos, &output) {
-                                        if seat.get_keyboard().unwrap().modifier_state().logo {
+                                        if seat.get_keyboard().unwrap().modifier_state().logo
+                                            && binding_action.is_none()
+                                        {
                                             if let Some(surface) =
                                                 target.toplevel().map(Cow::into_owned)
                                             {
@@ -989,6 +744,10 @@ impl State {
                             }
                             std::mem::drop(shell);
                             Shell::set_focus(self, under.as_ref(), &seat, Some(serial));
+                            // act on the window, that was just focused by the click
+                            if let Some(action) = binding_action {
+                                self.trigger_action(action, &seat, event.time_msec());
+                            }
                         } else {
                             std::mem::drop(shell);
                         }
@@ -1040,6 +799,9 @@ impl State {
                     .cloned();
                 if let Some(seat) = maybe_seat {
                     self.common.idle_notifier_state.notify_activity(&seat);
+                    if self.process_scroll_binding::<B>(&seat, &event) {
+                        return;
+                    }
 
                     let mut frame = AxisFrame::new(event.time_msec()).source(event.source());
                     if let Some(horizontal_amount) = event.amount(Axis::Horizontal) {
@@ -1085,8 +847,20 @@ impl State {
                     .cloned();
                 if let Some(seat) = maybe_seat {
                     self.common.idle_notifier_state.notify_activity(&seat);
-                    if event.fingers() >= 3 && !workspace_overview_is_open(&seat.active_output()) {
-                        self.common.gesture_state = Some(GestureState::new(event.fingers()));
+                    if event.fingers() >= 3
+                        && !workspace_overview_is_open(&seat.active_output())
+                        && self.common.config.gesture_bound(
+                            event.fingers(),
+                            &[
+                                GestureKind::Swipe(bindings::Direction::Left),
+                                GestureKind::Swipe(bindings::Direction::Right),
+                                GestureKind::Swipe(bindings::Direction::Up),
+                                GestureKind::Swipe(bindings::Direction::Down),
+                            ],
+                        )
+                    {
+                        self.common.gesture_state =
+                            Some(GestureState::new(GestureType::Swipe, event.fingers()));
                     } else {
                         let serial = SERIAL_COUNTER.next_serial();
                         let pointer = seat.get_pointer().unwrap();
@@ -1113,7 +887,12 @@ impl State {
                 if let Some(seat) = maybe_seat {
                     self.common.idle_notifier_state.notify_activity(&seat);
                     let mut activate_action: Option<SwipeAction> = None;
-                    if let Some(ref mut gesture_state) = self.common.gesture_state {
+                    if let Some(gesture_state) = self
+                        .common
+                        .gesture_state
+                        .as_mut()
+                        .filter(|state| state.gesture_type == GestureType::Swipe)
+                    {
                         let first_update = gesture_state.update(
                             event.delta(),
                             Duration::from_millis(event.time_msec() as u64),
@@ -1121,58 +900,34 @@ impl State {
                         // Decide on action if first update
                         if first_update {
                             let mut natural_scroll = false;
-                            if let Some(scroll_config) = &self.common.config.cosmic_conf.input_touchpad.scroll_config {
+                            if let Some(scroll_config) =
+                                &self.common.config.cosmic_conf.input_touchpad.scroll_config
+                            {
                                 if let Some(natural) = scroll_config.natural_scroll {
                                     natural_scroll = natural;
                                 }
                             }
-                            activate_action = match gesture_state.fingers {
-                                3 => None, // TODO: 3 finger gestures
-                                4 => {
-                                    if self.common.config.cosmic_conf.workspaces.workspace_layout
-                                        == WorkspaceLayout::Horizontal
-                                    {
-                                        match gesture_state.direction {
-                                            Some(Direction::Left) => {
-                                                if natural_scroll {
-                                                    Some(SwipeAction::NextWorkspace)
-                                                } else {
-                                                    Some(SwipeAction::PrevWorkspace)
-                                                }
-                                            }
-                                            Some(Direction::Right) => {
-                                                if natural_scroll {
-                                                    Some(SwipeAction::PrevWorkspace)
-                                                } else {
-                                                    Some(SwipeAction::NextWorkspace)
-                                                }
-                                            }
-                                            _ => None, // TODO: Other actions
-                                        }
-                                    } else {
-                                        match gesture_state.direction {
-                                            Some(Direction::Up) => {
-                                                if natural_scroll {
-                                                    Some(SwipeAction::NextWorkspace)
-                                                } else {
-                                                    Some(SwipeAction::PrevWorkspace)
-                                                }
-                                            },
-                                            Some(Direction::Down) => {
-                                                if natural_scroll {
-                                                    Some(SwipeAction::PrevWorkspace)
-                                                } else {
-                                                    Some(SwipeAction::NextWorkspace)
-                                                }
-                                            }
-                                            _ => None, // TODO: Other actions
-                                        }
+                            activate_action = gesture_state
+                                .direction
+                                .map(|direction| {
+                                    Gesture::new(
+                                        gesture_state.fingers,
+                                        GestureKind::Swipe(gesture_direction(
+                                            direction,
+                                            natural_scroll,
+                                        )),
+                                    )
+                                })
+                                .and_then(|gesture| self.common.config.gesture_action(&gesture))
+                                .map(|action| match action {
+                                    shortcuts::Action::NextWorkspace => SwipeAction::NextWorkspace,
+                                    shortcuts::Action::PreviousWorkspace => {
+                                        SwipeAction::PrevWorkspace
                                     }
-                                }
-                                _ => None,
-                            };
+                                    action => SwipeAction::Shortcut(action),
+                                });
 
-                            gesture_state.action = activate_action;
+                            gesture_state.action = activate_action.clone();
                         }
 
                         match gesture_state.action {
@@ -1226,7 +981,11 @@ impl State {
                     .cloned();
                 if let Some(seat) = maybe_seat {
                     self.common.idle_notifier_state.notify_activity(&seat);
-                    if let Some(ref gesture_state) = self.common.gesture_state {
+                    if let Some(gesture_state) = self
+                        .common
+                        .gesture_state
+                        .take_if(|state| state.gesture_type == GestureType::Swipe)
+                    {
                         match gesture_state.action {
                             Some(SwipeAction::NextWorkspace) | Some(SwipeAction::PrevWorkspace) => {
                                 let velocity = gesture_state.velocity();
@@ -1244,9 +1003,17 @@ impl State {
                                     &mut self.common.workspace_state.update(),
                                 );
                             }
+                            Some(SwipeAction::Shortcut(ref action))
+                                if !event.cancelled() && gesture_state.swipe_completed() =>
+                            {
+                                self.trigger_shortcut_action(
+                                    action.clone(),
+                                    &seat,
+                                    event.time_msec(),
+                                );
+                            }
                             _ => {}
                         }
-                        self.common.gesture_state = None;
                     } else {
                         let serial = SERIAL_COUNTER.next_serial();
                         let pointer = seat.get_pointer().unwrap();
@@ -1272,16 +1039,24 @@ impl State {
                     .cloned();
                 if let Some(seat) = maybe_seat {
                     self.common.idle_notifier_state.notify_activity(&seat);
-                    let serial = SERIAL_COUNTER.next_serial();
-                    let pointer = seat.get_pointer().unwrap();
-                    pointer.gesture_pinch_begin(
-                        self,
-                        &GesturePinchBeginEvent {
-                            serial,
-                            time: event.time_msec(),
-                            fingers: event.fingers(),
-                        },
-                    );
+                    if self.common.config.gesture_bound(
+                        event.fingers(),
+                        &[GestureKind::PinchIn, GestureKind::PinchOut],
+                    ) {
+                        self.common.gesture_state =
+                            Some(GestureState::new(GestureType::Pinch, event.fingers()));
+                    } else {
+                        let serial = SERIAL_COUNTER.next_serial();
+                        let pointer = seat.get_pointer().unwrap();
+                        pointer.gesture_pinch_begin(
+                            self,
+                            &GesturePinchBeginEvent {
+                                serial,
+                                time: event.time_msec(),
+                                fingers: event.fingers(),
+                            },
+                        );
+                    }
                 }
             }
             InputEvent::GesturePinchUpdate { event, .. } => {
@@ -1295,16 +1070,25 @@ impl State {
                     .cloned();
                 if let Some(seat) = maybe_seat {
                     self.common.idle_notifier_state.notify_activity(&seat);
-                    let pointer = seat.get_pointer().unwrap();
-                    pointer.gesture_pinch_update(
-                        self,
-                        &GesturePinchUpdateEvent {
-                            time: event.time_msec(),
-                            delta: event.delta(),
-                            scale: event.scale(),
-                            rotation: event.rotation(),
-                        },
-                    );
+                    if let Some(gesture_state) = self
+                        .common
+                        .gesture_state
+                        .as_mut()
+                        .filter(|state| state.gesture_type == GestureType::Pinch)
+                    {
+                        gesture_state.scale = event.scale();
+                    } else {
+                        let pointer = seat.get_pointer().unwrap();
+                        pointer.gesture_pinch_update(
+                            self,
+                            &GesturePinchUpdateEvent {
+                                time: event.time_msec(),
+                                delta: event.delta(),
+                                scale: event.scale(),
+                                rotation: event.rotation(),
+                            },
+                        );
+                    }
                 }
             }
             InputEvent::GesturePinchEnd { event, .. } => {
@@ -1318,16 +1102,35 @@ impl State {
                     .cloned();
                 if let Some(seat) = maybe_seat {
                     self.common.idle_notifier_state.notify_activity(&seat);
-                    let serial = SERIAL_COUNTER.next_serial();
-                    let pointer = seat.get_pointer().unwrap();
-                    pointer.gesture_pinch_end(
-                        self,
-                        &GesturePinchEndEvent {
-                            serial,
-                            time: event.time_msec(),
-                            cancelled: event.cancelled(),
-                        },
-                    );
+                    if let Some(gesture_state) = self
+                        .common
+                        .gesture_state
+                        .take_if(|state| state.gesture_type == GestureType::Pinch)
+                    {
+                        let kind = match gesture_state.pinch_in() {
+                            Some(true) => Some(GestureKind::PinchIn),
+                            Some(false) => Some(GestureKind::PinchOut),
+                            None => None,
+                        };
+                        if let Some(action) = kind.filter(|_| !event.cancelled()).and_then(|kind| {
+                            self.common
+                                .config
+                                .gesture_action(&Gesture::new(gesture_state.fingers, kind))
+                        }) {
+                            self.trigger_shortcut_action(action, &seat, event.time_msec());
+                        }
+                    } else {
+                        let serial = SERIAL_COUNTER.next_serial();
+                        let pointer = seat.get_pointer().unwrap();
+                        pointer.gesture_pinch_end(
+                            self,
+                            &GesturePinchEndEvent {
+                                serial,
+                                time: event.time_msec(),
+                                cancelled: event.cancelled(),
+                            },
+                        );
+                    }
                 }
             }
             InputEvent::GestureHoldBegin { event, .. } => {
@@ -1341,16 +1144,25 @@ impl State {
                     .cloned();
                 if let Some(seat) = maybe_seat {
                     self.common.idle_notifier_state.notify_activity(&seat);
-                    let serial = SERIAL_COUNTER.next_serial();
-                    let pointer = seat.get_pointer().unwrap();
-                    pointer.gesture_hold_begin(
-                        self,
-                        &GestureHoldBeginEvent {
-                            serial,
-                            time: event.time_msec(),
-                            fingers: event.fingers(),
-                        },
-                    );
+                    if self
+                        .common
+                        .config
+                        .gesture_bound(event.fingers(), &[GestureKind::Hold])
+                    {
+                        self.common.gesture_state =
+                            Some(GestureState::new(GestureType::Hold, event.fingers()));
+                    } else {
+                        let serial = SERIAL_COUNTER.next_serial();
+                        let pointer = seat.get_pointer().unwrap();
+                        pointer.gesture_hold_begin(
+                            self,
+                            &GestureHoldBeginEvent {
+                                serial,
+                                time: event.time_msec(),
+                                fingers: event.fingers(),
+                            },
+                        );
+                    }
                 }
             }
             InputEvent::GestureHoldEnd { event, .. } => {
@@ -1364,36 +1176,44 @@ impl State {
                     .cloned();
                 if let Some(seat) = maybe_seat {
                     self.common.idle_notifier_state.notify_activity(&seat);
-                    let serial = SERIAL_COUNTER.next_serial();
-                    let pointer = seat.get_pointer().unwrap();
-                    pointer.gesture_hold_end(
-                        self,
-                        &GestureHoldEndEvent {
-                            serial,
-                            time: event.time_msec(),
-                            cancelled: event.cancelled(),
-                        },
-                    );
+                    if let Some(gesture_state) = self
+                        .common
+                        .gesture_state
+                        .take_if(|state| state.gesture_type == GestureType::Hold)
+                    {
+                        // a cancelled hold means the fingers started moving, e.g. into a swipe
+                        if let Some(action) = self
+                            .common
+                            .config
+                            .gesture_action(&Gesture::new(gesture_state.fingers, GestureKind::Hold))
+                            .filter(|_| !event.cancelled())
+                        {
+                            self.trigger_shortcut_action(action, &seat, event.time_msec());
+                        }
+                    } else {
+                        let serial = SERIAL_COUNTER.next_serial();
+                        let pointer = seat.get_pointer().unwrap();
+                        pointer.gesture_hold_end(
+                            self,
+                            &GestureHoldEndEvent {
+                                serial,
+                                time: event.time_msec(),
+                                cancelled: event.cancelled(),
+                            },
+                        );
+                    }
                 }
             }
             InputEvent::TouchDown { event, .. } => {
                 let mut shell = self.common.shell.write().unwrap();
                 if let Some(seat) = shell.seats.for_device(&event.device()).cloned() {
                     self.common.idle_notifier_state.notify_activity(&seat);
-                    let Some(output) =
-                        mapped_output_for_device(&self.common.config, &*shell, &event.device())
-                            .cloned()
+                    let Some((output, position)) =
+                        mapped_position(&self.common.config, &*shell, &seat, &event)
                     else {
                         return;
                     };
 
-                    let geometry = output.geometry();
-
-                    let position = geometry.loc.to_f64()
-                        + event
-                            .position_transformed(geometry.size.as_logical())
-                            .as_global();
-
                     let under = State::surface_under(position, &output, &mut *shell)
                         .map(|(target, pos)| (target, pos.as_logical()));
 
@@ -1417,20 +1237,12 @@ impl State {
                 let mut shell = self.common.shell.write().unwrap();
                 if let Some(seat) = shell.seats.for_device(&event.device()).cloned() {
                     self.common.idle_notifier_state.notify_activity(&seat);
-                    let Some(output) =
-                        mapped_output_for_device(&self.common.config, &*shell, &event.device())
-                            .cloned()
+                    let Some((output, position)) =
+                        mapped_position(&self.common.config, &*shell, &seat, &event)
                     else {
                         return;
                     };
 
-                    let geometry = output.geometry();
-
-                    let position = geometry.loc.to_f64()
-                        + event
-                            .position_transformed(geometry.size.as_logical())
-                            .as_global();
-
                     let under = State::surface_under(position, &output, &mut *shell)
                         .map(|(target, pos)| (target, pos.as_logical()));
 
@@ -1506,18 +1318,11 @@ impl State {
                 let mut shell = self.common.shell.write().unwrap();
                 if let Some(seat) = shell.seats.for_device(&event.device()).cloned() {
                     self.common.idle_notifier_state.notify_activity(&seat);
-                    let Some(output) =
-                        mapped_output_for_device(&self.common.config, &shell, &event.device())
-                            .cloned()
+                    let Some((output, position)) =
+                        mapped_position(&self.common.config, &*shell, &seat, &event)
                     else {
                         return;
                     };
-                    let geometry = output.geometry();
-
-                    let position = event
-                        .position_transformed(geometry.size.as_logical())
-                        .as_global()
-                        + geometry.loc.to_f64();
 
                     let under = State::surface_under(position, &output, &mut *shell)
                         .map(|(target, pos)| (target, pos.as_logical()));
@@ -1540,6 +1345,17 @@ impl State {
                     let tablet = tablet_seat.get_tablet(&TabletDescriptor::from(&event.device()));
                     let tool = tablet_seat.get_tool(&event.tool());
 
+                    if let Some(cursor) = seat
+                        .user_data()
+                        .get::<TabletToolCursors>()
+                        .unwrap()
+                        .lock()
+                        .unwrap()
+                        .get_mut(&event.tool())
+                    {
+                        cursor.location = position;
+                    }
+
                     if let (Some(tablet), Some(tool)) = (tablet, tool) {
                         if event.pressure_has_changed() {
                             tool.pressure(event.pressure());
@@ -1575,18 +1391,11 @@ impl State {
                 let mut shell = self.common.shell.write().unwrap();
                 if let Some(seat) = shell.seats.for_device(&event.device()).cloned() {
                     self.common.idle_notifier_state.notify_activity(&seat);
-                    let Some(output) =
-                        mapped_output_for_device(&self.common.config, &shell, &event.device())
-                            .cloned()
+                    let Some((output, position)) =
+                        mapped_position(&self.common.config, &*shell, &seat, &event)
                     else {
                         return;
                     };
-                    let geometry = output.geometry();
-
-                    let position = event
-                        .position_transformed(geometry.size.as_logical())
-                        .as_global()
-                        + geometry.loc.to_f64();
 
                     let under = State::surface_under(position, &output, &mut *shell)
                         .map(|(target, pos)| (target, pos.as_logical()));
@@ -1610,6 +1419,29 @@ impl State {
                     let dh = self.common.display_handle.clone();
                     let tool = tablet_seat.add_tool::<Self>(self, &dh, &event.tool());
 
+                    {
+                        let mut tool_cursors = seat
+                            .user_data()
+                            .get::<TabletToolCursors>()
+                            .unwrap()
+                            .lock()
+                            .unwrap();
+                        match event.state() {
+                            ProximityState::In => {
+                                tool_cursors.insert(
+                                    event.tool(),
+                                    TabletToolCursor {
+                                        location: position,
+                                        image: CursorImageStatus::default_named(),
+                                    },
+                                );
+                            }
+                            ProximityState::Out => {
+                                tool_cursors.remove(&event.tool());
+                            }
+                        }
+                    }
+
                     if let Some(tablet) = tablet {
                         match event.state() {
                             ProximityState::In => {
@@ -1675,11 +1507,668 @@ impl State {
                 }
             }
             InputEvent::Special(_) => {}
-            InputEvent::SwitchToggle { event: _ } => {}
+            InputEvent::SwitchToggle { event } => {
+                let on = event.state() == SwitchState::On;
+                match event.switch() {
+                    Some(Switch::Lid) => self.lid_switch_toggled(on),
+                    Some(Switch::TabletMode) => self.tablet_mode_toggled(on),
+                    None => {}
+                }
+            }
         }
     }
 
-    pub fn handle_action(
+    /// Matches a key event against the compositor shortcuts or passes it on to clients
+    fn process_key(
+        &mut self,
+        seat: &Seat<State>,
+        keycode: Keycode,
+        state: KeyState,
+        time: u32,
+        needs_key_repetition: bool,
+    ) {
+        let loop_handle = self.common.event_loop_handle.clone();
+
+        let current_output = seat.active_output();
+        let shortcuts_inhibitor = self.focused_shortcuts_inhibitor(&seat);
+        let shortcuts_inhibited = shortcuts_inhibitor
+            .as_ref()
+            .map(|inhibitor| inhibitor.is_active())
+            .unwrap_or(false);
+
+        let serial = SERIAL_COUNTER.next_serial();
+        let keyboard = seat.get_keyboard().unwrap();
+        let pointer = seat.get_pointer().unwrap();
+        let is_grabbed = keyboard.is_grabbed() || pointer.is_grabbed();
+        let current_focus = keyboard.current_focus();
+        let shell_ref = self.common.shell.clone();
+        let mut shell = shell_ref.write().unwrap();
+        if let Some((action, pattern)) = keyboard
+            .input(
+                self,
+                keycode,
+                state,
+                serial,
+                time,
+                |data, modifiers, handle| {
+                    // Leave move overview mode, if any modifier was released
+                    if let Some(Trigger::KeyboardMove(action_modifiers)) =
+                        shell.overview_mode().0.active_trigger()
+                    {
+                        if (action_modifiers.ctrl && !modifiers.ctrl)
+                            || (action_modifiers.alt && !modifiers.alt)
+                            || (action_modifiers.logo && !modifiers.logo)
+                            || (action_modifiers.shift && !modifiers.shift)
+                        {
+                            shell.set_overview_mode(None, data.common.event_loop_handle.clone());
+                        }
+                    }
+                    // Leave swap overview mode, if any key was released
+                    if let Some(Trigger::KeyboardSwap(action_pattern, old_descriptor)) =
+                        shell.overview_mode().0.active_trigger()
+                    {
+                        if (action_pattern.modifiers.ctrl && !modifiers.ctrl)
+                            || (action_pattern.modifiers.alt && !modifiers.alt)
+                            || (action_pattern.modifiers.logo && !modifiers.logo)
+                            || (action_pattern.modifiers.shift && !modifiers.shift)
+                            || (action_pattern.key.is_some()
+                                && handle.raw_syms().contains(&action_pattern.key.unwrap())
+                                && state == KeyState::Released)
+                        {
+                            shell.set_overview_mode(None, data.common.event_loop_handle.clone());
+
+                            if let Some(focus) = current_focus {
+                                if let Some(new_descriptor) =
+                                    shell.workspaces.active(&current_output).1.node_desc(focus)
+                                {
+                                    let mut spaces = shell.workspaces.spaces_mut();
+                                    if old_descriptor.handle != new_descriptor.handle {
+                                        let (mut old_w, mut other_w) = spaces
+                                            .partition::<Vec<_>, _>(|w| {
+                                                w.handle == old_descriptor.handle
+                                            });
+                                        if let Some(old_workspace) = old_w.get_mut(0) {
+                                            if let Some(new_workspace) = other_w
+                                                .iter_mut()
+                                                .find(|w| w.handle == new_descriptor.handle)
+                                            {
+                                                if let Some(focus) = TilingLayout::swap_trees(
+                                                    &mut old_workspace.tiling_layer,
+                                                    Some(&mut new_workspace.tiling_layer),
+                                                    &old_descriptor,
+                                                    &new_descriptor,
+                                                ) {
+                                                    let seat = seat.clone();
+                                                    data.common.event_loop_handle.insert_idle(
+                                                        move |state| {
+                                                            Shell::set_focus(
+                                                                state,
+                                                                Some(&focus),
+                                                                &seat,
+                                                                None,
+                                                            );
+                                                        },
+                                                    );
+                                                }
+                                                old_workspace.refresh_focus_stack();
+                                                new_workspace.refresh_focus_stack();
+                                            }
+                                        }
+                                    } else {
+                                        if let Some(workspace) =
+                                            spaces.find(|w| w.handle == new_descriptor.handle)
+                                        {
+                                            if let Some(focus) = TilingLayout::swap_trees(
+                                                &mut workspace.tiling_layer,
+                                                None,
+                                                &old_descriptor,
+                                                &new_descriptor,
+                                            ) {
+                                                std::mem::drop(spaces);
+                                                let seat = seat.clone();
+                                                data.common.event_loop_handle.insert_idle(
+                                                    move |state| {
+                                                        Shell::set_focus(
+                                                            state,
+                                                            Some(&focus),
+                                                            &seat,
+                                                            None,
+                                                        );
+                                                    },
+                                                );
+                                            }
+                                            workspace.refresh_focus_stack();
+                                        }
+                                    }
+                                }
+                            } else {
+                                let new_workspace =
+                                    shell.workspaces.active(&current_output).1.handle;
+                                if new_workspace != old_descriptor.handle {
+                                    let spaces = shell.workspaces.spaces_mut();
+                                    let (mut old_w, mut other_w) =
+                                        spaces.partition::<Vec<_>, _>(|w| {
+                                            w.handle == old_descriptor.handle
+                                        });
+                                    if let Some(old_workspace) = old_w.get_mut(0) {
+                                        if let Some(new_workspace) =
+                                            other_w.iter_mut().find(|w| w.handle == new_workspace)
+                                        {
+                                            if new_workspace.tiling_layer.windows().next().is_none()
+                                            {
+                                                if let Some(focus) = TilingLayout::move_tree(
+                                                    &mut old_workspace.tiling_layer,
+                                                    &mut new_workspace.tiling_layer,
+                                                    &new_workspace.handle,
+                                                    &seat,
+                                                    new_workspace.focus_stack.get(&seat).iter(),
+                                                    old_descriptor.clone(),
+                                                ) {
+                                                    let seat = seat.clone();
+                                                    data.common.event_loop_handle.insert_idle(
+                                                        move |state| {
+                                                            Shell::set_focus(
+                                                                state,
+                                                                Some(&focus),
+                                                                &seat,
+                                                                None,
+                                                            );
+                                                        },
+                                                    );
+                                                }
+                                                old_workspace.refresh_focus_stack();
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+
+                    // Leave or update resize mode, if modifiers changed or initial key was released
+                    if let Some(action_pattern) = shell.resize_mode().0.active_binding() {
+                        if action_pattern.key.is_some()
+                            && state == KeyState::Released
+                            && handle.raw_syms().contains(&action_pattern.key.unwrap())
+                        {
+                            shell.set_resize_mode(
+                                None,
+                                &data.common.config,
+                                data.common.event_loop_handle.clone(),
+                            );
+                        } else if !cosmic_modifiers_eq_smithay(&action_pattern.modifiers, modifiers)
+                        {
+                            let mut new_pattern = action_pattern.clone();
+                            new_pattern.modifiers =
+                                cosmic_modifiers_from_smithay(modifiers.clone());
+                            let enabled = data.common.config.shortcuts.iter().find_map(
+                                move |(binding, action)| {
+                                    if binding == &new_pattern
+                                        && matches!(action, shortcuts::Action::Resizing(_))
+                                    {
+                                        let shortcuts::Action::Resizing(direction) = action else {
+                                            unreachable!()
+                                        };
+                                        Some((new_pattern.clone(), *direction))
+                                    } else {
+                                        None
+                                    }
+                                },
+                            );
+                            shell.set_resize_mode(
+                                enabled,
+                                &data.common.config,
+                                data.common.event_loop_handle.clone(),
+                            );
+                        }
+                    }
+
+                    // Special case resizing with regards to arrow keys
+                    if let Some(direction) = shell.resize_mode().0.active_direction() {
+                        let resize_edge = match handle.modified_sym() {
+                            Keysym::Left | Keysym::h | Keysym::H => Some(ResizeEdge::LEFT),
+                            Keysym::Down | Keysym::j | Keysym::J => Some(ResizeEdge::BOTTOM),
+                            Keysym::Up | Keysym::k | Keysym::K => Some(ResizeEdge::TOP),
+                            Keysym::Right | Keysym::l | Keysym::L => Some(ResizeEdge::RIGHT),
+                            _ => None,
+                        };
+
+                        if let Some(mut edge) = resize_edge {
+                            if direction == ResizeDirection::Inwards {
+                                edge.flip_direction();
+                            }
+                            let action = Action::Private(PrivateAction::Resizing(
+                                direction,
+                                edge.into(),
+                                cosmic_keystate_from_smithay(state),
+                            ));
+                            let key_pattern = shortcuts::Binding {
+                                modifiers: cosmic_modifiers_from_smithay(modifiers.clone()),
+                                key: Some(Keysym::new(handle.raw_code().raw())),
+                                description: None,
+                            };
+
+                            if state == KeyState::Released {
+                                if let Some(tokens) = seat.supressed_keys().filter(&handle) {
+                                    for token in tokens {
+                                        loop_handle.remove(token);
+                                    }
+                                }
+                            } else {
+                                let token = if needs_key_repetition {
+                                    let seat_clone = seat.clone();
+                                    let action_clone = action.clone();
+                                    let key_pattern_clone = key_pattern.clone();
+                                    let start = Instant::now();
+                                    loop_handle
+                                        .insert_source(
+                                            Timer::from_duration(Duration::from_millis(200)),
+                                            move |current, _, state| {
+                                                let duration =
+                                                    current.duration_since(start).as_millis();
+                                                state.handle_action(
+                                                    action_clone.clone(),
+                                                    &seat_clone,
+                                                    serial,
+                                                    time.overflowing_add(duration as u32).0,
+                                                    key_pattern_clone.clone(),
+                                                    None,
+                                                    true,
+                                                );
+                                                calloop::timer::TimeoutAction::ToDuration(
+                                                    Duration::from_millis(25),
+                                                )
+                                            },
+                                        )
+                                        .ok()
+                                } else {
+                                    None
+                                };
+
+                                seat.supressed_keys().add(&handle, token);
+                            }
+                            return FilterResult::Intercept(Some((action, key_pattern)));
+                        }
+                    }
+
+                    std::mem::drop(shell);
+
+                    // cancel grabs
+                    if is_grabbed
+                        && handle.modified_sym() == Keysym::Escape
+                        && state == KeyState::Pressed
+                        && !modifiers.alt
+                        && !modifiers.ctrl
+                        && !modifiers.logo
+                        && !modifiers.shift
+                    {
+                        seat.supressed_keys().add(&handle, None);
+                        return FilterResult::Intercept(Some((
+                            Action::Private(PrivateAction::Escape),
+                            shortcuts::Binding {
+                                modifiers: shortcuts::Modifiers::default(),
+                                key: Some(Keysym::Escape),
+                                description: None,
+                            },
+                        )));
+                    }
+
+                    // Skip released events for initially surpressed keys
+                    if state == KeyState::Released {
+                        if let Some(tokens) = seat.supressed_keys().filter(&handle) {
+                            for token in tokens {
+                                loop_handle.remove(token);
+                            }
+                            return FilterResult::Intercept(None);
+                        }
+                    }
+
+                    // Handle VT switches
+                    if state == KeyState::Pressed
+                        && (Keysym::XF86_Switch_VT_1.raw()..=Keysym::XF86_Switch_VT_12.raw())
+                            .contains(&handle.modified_sym().raw())
+                    {
+                        if let Err(err) = data.backend.kms().switch_vt(
+                            (handle.modified_sym().raw() - Keysym::XF86_Switch_VT_1.raw() + 1)
+                                as i32,
+                        ) {
+                            error!(?err, "Failed switching virtual terminal.");
+                        }
+                        seat.supressed_keys().add(&handle, None);
+                        return FilterResult::Intercept(None);
+                    }
+
+                    // the escape binding of shortcut inhibitors always reaches us
+                    let escape = data.common.config.shortcuts_inhibit_escape.as_ref();
+                    if state == KeyState::Pressed
+                        && is_shortcuts_inhibit_escape(
+                            escape,
+                            shortcuts_inhibitor.is_some(),
+                            handle.raw_syms(),
+                            modifiers,
+                        )
+                    {
+                        seat.modifiers_shortcut_queue().clear();
+                        seat.supressed_keys().add(&handle, None);
+                        return FilterResult::Intercept(Some((
+                            Action::Private(PrivateAction::ToggleShortcutsInhibitor),
+                            escape.unwrap().clone(),
+                        )));
+                    }
+
+                    // bindings of the active binding mode replace all other shortcuts
+                    if let Some(name) = seat.binding_mode() {
+                        if state == KeyState::Pressed && !handle.modified_sym().is_modifier_key() {
+                            seat.modifiers_shortcut_queue().clear();
+                            let pattern = shortcuts::Binding {
+                                modifiers: cosmic_modifiers_from_smithay(modifiers.clone()),
+                                key: Some(handle.modified_sym()),
+                                description: None,
+                            };
+                            let mode = data.common.config.binding_modes.get(&name);
+                            let oneshot = mode.map_or(true, |mode| mode.oneshot);
+                            let action = mode
+                                .and_then(|mode| {
+                                    mode.bindings.iter().find(|(binding, _)| {
+                                        handle.raw_syms().contains(&binding.key.unwrap())
+                                            && cosmic_modifiers_eq_smithay(
+                                                &binding.modifiers,
+                                                modifiers,
+                                            )
+                                    })
+                                })
+                                .map(|(binding, action)| (action.clone(), binding.clone()));
+
+                            let action = match action {
+                                Some(action) => Some(action),
+                                // unbound keys abort key sequences
+                                None if handle.modified_sym() == Keysym::Escape || oneshot => {
+                                    Some((Action::Private(PrivateAction::ExitMode), pattern))
+                                }
+                                None => None,
+                            };
+                            if let Some(action) = action {
+                                if oneshot {
+                                    data.set_binding_mode(&seat, None);
+                                }
+                                seat.supressed_keys().add(&handle, None);
+                                return FilterResult::Intercept(Some(action));
+                            }
+                        }
+                        // other keys are passed through to apps
+                        return FilterResult::Forward;
+                    }
+
+                    // handle the rest of the global shortcuts
+                    let mut clear_queue = true;
+                    if !shortcuts_inhibited {
+                        let modifiers_queue = seat.modifiers_shortcut_queue();
+
+                        for (binding, action) in data.common.config.shortcuts.iter() {
+                            if *action == shortcuts::Action::Disable {
+                                continue;
+                            }
+
+                            // is this a released (triggered) modifier-only binding?
+                            if binding.key.is_none()
+                                && state == KeyState::Released
+                                && !cosmic_modifiers_eq_smithay(&binding.modifiers, modifiers)
+                                && modifiers_queue.take(binding)
+                            {
+                                modifiers_queue.clear();
+                                return FilterResult::Intercept(Some((
+                                    Action::Shortcut(action.clone()),
+                                    binding.clone(),
+                                )));
+                            }
+
+                            // could this potentially become a modifier-only binding?
+                            if binding.key.is_none()
+                                && state == KeyState::Pressed
+                                && cosmic_modifiers_eq_smithay(&binding.modifiers, modifiers)
+                            {
+                                modifiers_queue.set(binding.clone());
+                                clear_queue = false;
+                            }
+
+                            // is this a normal binding?
+                            if binding.key.is_some()
+                                && state == KeyState::Pressed
+                                && handle.raw_syms().contains(&binding.key.unwrap())
+                                && cosmic_modifiers_eq_smithay(&binding.modifiers, modifiers)
+                            {
+                                modifiers_queue.clear();
+                                seat.supressed_keys().add(&handle, None);
+                                return FilterResult::Intercept(Some((
+                                    Action::Shortcut(action.clone()),
+                                    binding.clone(),
+                                )));
+                            }
+                        }
+
+                        for (binding, action) in data.common.config.compositor_bindings.iter() {
+                            if state == KeyState::Pressed
+                                && handle.raw_syms().contains(&binding.key.unwrap())
+                                && cosmic_modifiers_eq_smithay(&binding.modifiers, modifiers)
+                            {
+                                modifiers_queue.clear();
+                                seat.supressed_keys().add(&handle, None);
+                                return FilterResult::Intercept(Some((
+                                    Action::Compositor(action.clone()),
+                                    binding.clone(),
+                                )));
+                            }
+                        }
+
+                        for (name, mode) in data.common.config.binding_modes.iter() {
+                            let Some(binding) = mode.enter.as_ref() else {
+                                continue;
+                            };
+                            if state == KeyState::Pressed
+                                && handle.raw_syms().contains(&binding.key.unwrap())
+                                && cosmic_modifiers_eq_smithay(&binding.modifiers, modifiers)
+                            {
+                                modifiers_queue.clear();
+                                seat.supressed_keys().add(&handle, None);
+                                return FilterResult::Intercept(Some((
+                                    Action::Private(PrivateAction::EnterMode(name.clone())),
+                                    binding.clone(),
+                                )));
+                            }
+                        }
+                    }
+
+                    // no binding
+                    if clear_queue {
+                        seat.modifiers_shortcut_queue().clear();
+                    }
+                    // keys are passed through to apps
+                    FilterResult::Forward
+                },
+            )
+            .flatten()
+        {
+            if pattern.key.is_none() && state == KeyState::Released {
+                // we still want to send release-events and not have apps stuck on some modifiers.
+                keyboard.input(self, keycode, state, serial, time, |_, _, _| {
+                    FilterResult::<()>::Forward
+                });
+            }
+            self.handle_action(action, &seat, serial, time, pattern, None, true)
+        }
+    }
+
+    /// Triggers scroll bindings for wheel scrolling, returns whether the event was consumed
+    fn process_scroll_binding<B: InputBackend>(
+        &mut self,
+        seat: &Seat<State>,
+        event: &B::PointerAxisEvent,
+    ) -> bool {
+        if !matches!(event.source(), AxisSource::Wheel | AxisSource::WheelTilt)
+            || seat.get_pointer().unwrap().is_grabbed()
+            || self.common.shell.read().unwrap().session_lock.is_some()
+        {
+            return false;
+        }
+
+        let modifiers = seat.get_keyboard().unwrap().modifier_state();
+        seat.user_data()
+            .insert_if_missing(ScrollBindingAccumulator::default);
+        let mut consumed = false;
+        let mut actions = Vec::new();
+        for (i, axis, (negative, positive)) in [
+            (
+                0,
+                Axis::Horizontal,
+                (bindings::Direction::Left, bindings::Direction::Right),
+            ),
+            (
+                1,
+                Axis::Vertical,
+                (bindings::Direction::Up, bindings::Direction::Down),
+            ),
+        ] {
+            let Some(amount) = event.amount(axis).filter(|amount| *amount != 0.0) else {
+                continue;
+            };
+            let direction = if amount < 0.0 { negative } else { positive };
+            let Some(action) = self
+                .common
+                .config
+                .pointer_binding_action(&modifiers, PointerTrigger::Scroll(direction))
+            else {
+                continue;
+            };
+            consumed = true;
+
+            let accumulator = seat.user_data().get::<ScrollBindingAccumulator>().unwrap();
+            let mut accumulated = accumulator.0.borrow_mut();
+            // reset when changing direction
+            if accumulated[i] * amount < 0.0 {
+                accumulated[i] = 0.0;
+            }
+            accumulated[i] += event.amount_v120(axis).unwrap_or(120.0 * amount.signum());
+            if accumulated[i].abs() >= 120.0 {
+                accumulated[i] -= 120.0 * accumulated[i].signum();
+                actions.push(action);
+            }
+        }
+
+        for action in actions {
+            self.trigger_action(action, seat, event.time_msec());
+        }
+        consumed
+    }
+
+    /// Activates the binding mode `name` on `seat` or returns to the regular bindings
+    pub fn set_binding_mode(&mut self, seat: &Seat<State>, name: Option<String>) {
+        let mode = name.map(|name| {
+            let indicator = mode_indicator(
+                name.clone(),
+                self.common.event_loop_handle.clone(),
+                self.common.theme.clone(),
+            );
+            for output in self.common.shell.read().unwrap().outputs() {
+                indicator.output_enter(output, indicator.bbox());
+            }
+            BindingModeState { name, indicator }
+        });
+        *seat
+            .user_data()
+            .get::<SeatBindingMode>()
+            .unwrap()
+            .lock()
+            .unwrap() = mode;
+        self.backend.schedule_render(&seat.active_output());
+    }
+
+    /// Loads the keyboard layout override of `device` into the keymap of `seat`,
+    /// or restores the layout of the seat, if the device has none.
+    fn update_keymap<D: Device + 'static>(&mut self, seat: &Seat<State>, device: &D) {
+        let Some(device) = <dyn Any>::downcast_ref::<InputDevice>(device) else {
+            return;
+        };
+        let device_config = self.common.config.device_xkb_config(device);
+        let name = device_config.as_ref().map(|_| device.name().to_string());
+
+        let active_keymap = seat.user_data().get::<ActiveKeymap>().unwrap();
+        if *active_keymap.0.borrow() == name {
+            return;
+        }
+        *active_keymap.0.borrow_mut() = name;
+
+        let value = device_config.unwrap_or_else(|| self.common.config.xkb_config(seat.name()));
+        let keyboard = seat.get_keyboard().unwrap();
+        keyboard.change_repeat_info(
+            (value.repeat_rate as i32).abs(),
+            (value.repeat_delay as i32).abs(),
+        );
+        if let Err(err) = keyboard.set_xkb_config(self, xkb_config_to_wl(&value)) {
+            error!(?err, "Failed to load xkb config of {}", device.name());
+        }
+    }
+
+    /// Triggers tablet pad button bindings.
+    ///
+    /// Pad events are not forwarded to clients, as `zwp_tablet_pad_v2` isn't provided by smithay yet.
+    pub fn process_tablet_pad_event(&mut self, event: &TabletPadEvent) {
+        // rings and strips can't be bound and without the pad protocol no client could use them
+        let TabletPadEvent::Button(event) = event else {
+            return;
+        };
+        if event.button_state() != PadButtonState::Pressed {
+            return;
+        }
+
+        let maybe_seat = self
+            .common
+            .shell
+            .read()
+            .unwrap()
+            .seats
+            .for_device(&event.device())
+            .cloned();
+        let Some(seat) = maybe_seat else {
+            return;
+        };
+        self.common.idle_notifier_state.notify_activity(&seat);
+
+        let bindings = &self.common.config.cosmic_conf.tablet_pad_bindings;
+        if let Some(action) = bindings.get(&event.button_number()).cloned() {
+            let time = self.common.clock.now().as_millis();
+            self.trigger_shortcut_action(action, &seat, time);
+        }
+    }
+
+    /// Triggers a shortcut `action` that isn't bound to a key, like gesture or pad button bindings
+    fn trigger_shortcut_action(
+        &mut self,
+        action: shortcuts::Action,
+        seat: &Seat<State>,
+        time: u32,
+    ) {
+        self.trigger_action(Action::Shortcut(action), seat, time)
+    }
+
+    /// Triggers an `action` that isn't bound to a key, like pointer bindings
+    fn trigger_action(&mut self, action: Action, seat: &Seat<State>, time: u32) {
+        let serial = SERIAL_COUNTER.next_serial();
+        self.handle_action(
+            action,
+            seat,
+            serial,
+            time,
+            shortcuts::Binding {
+                modifiers: Default::default(),
+                key: None,
+                description: None,
+            },
+            None,
+            false,
+        );
+    }
+
+    pub fn handle_action(
         &mut self,
         action: Action,
         seat: &Seat<State>,
@@ -1702,8 +2191,11 @@ impl State {
         }
 
         match action {
-            Action::Shortcut(action) => self
-                .handle_shortcut_action(action, seat, serial, time, pattern, direction, propagate),
+            Action::Shortcut(action) | Action::Compositor(CompositorAction::Shortcut(action)) => {
+                self.handle_shortcut_action(
+                    action, seat, serial, time, pattern, direction, propagate,
+                )
+            }
 
             Action::Private(PrivateAction::Escape) => {
                 {
@@ -1740,6 +2232,173 @@ impl State {
                         .finish_resize(direction, edge.into());
                 }
             }
+
+            Action::Private(PrivateAction::ToggleShortcutsInhibitor) => {
+                self.toggle_shortcuts_inhibitor(seat)
+            }
+
+            Action::Private(PrivateAction::EnterMode(name)) => {
+                if self.common.config.binding_modes.contains_key(&name) {
+                    self.set_binding_mode(seat, Some(name));
+                } else {
+                    warn!(?name, "Unknown binding mode");
+                }
+            }
+
+            Action::Private(PrivateAction::ExitMode) => self.set_binding_mode(seat, None),
+
+            Action::Compositor(action) => self.handle_compositor_action(action, seat, serial),
+        }
+    }
+
+    pub fn handle_compositor_action(
+        &mut self,
+        action: CompositorAction,
+        seat: &Seat<State>,
+        serial: Serial,
+    ) {
+        match action {
+            // dispatched by `handle_action`
+            CompositorAction::Shortcut(_) => {}
+
+            CompositorAction::SetLayoutStrategy(strategy) => {
+                let res = self
+                    .common
+                    .shell
+                    .write()
+                    .unwrap()
+                    .set_layout_strategy(seat, |_| strategy);
+                if let Some(new_focus) = res {
+                    Shell::set_focus(self, Some(&new_focus), seat, Some(serial));
+                }
+            }
+
+            CompositorAction::CycleLayoutStrategy => {
+                let res = self
+                    .common
+                    .shell
+                    .write()
+                    .unwrap()
+                    .set_layout_strategy(seat, |strategy| strategy.next());
+                if let Some(new_focus) = res {
+                    Shell::set_focus(self, Some(&new_focus), seat, Some(serial));
+                }
+            }
+
+            CompositorAction::ToggleScrolling => {
+                let output = seat.active_output();
+                let mut shell = self.common.shell.write().unwrap();
+                shell.session.mark_dirty();
+                let workspace = shell.workspaces.active_mut(&output);
+                let mut guard = self.common.workspace_state.update();
+                workspace.toggle_scrolling(seat, &mut guard);
+            }
+
+            CompositorAction::TogglePinWorkspace => {
+                {
+                    let output = seat.active_output();
+                    let mut shell = self.common.shell.write().unwrap();
+                    let workspace = shell.workspaces.active_mut(&output);
+                    workspace.pinned = !workspace.pinned;
+                }
+                self.common.persist_workspaces();
+            }
+
+            CompositorAction::MoveToScratchpad => {
+                let current_output = seat.active_output();
+                let mut shell = self.common.shell.write().unwrap();
+                let workspace = shell.active_space(&current_output);
+                let focus_stack = workspace.focus_stack.get(seat);
+                let focused_window = focus_stack.last().cloned();
+                if let Some(window) = focused_window {
+                    shell.move_to_scratchpad(&window);
+                    // the hidden window was removed from the focus stack
+                    let new_focus = shell
+                        .active_space(&current_output)
+                        .focus_stack
+                        .get(seat)
+                        .last()
+                        .cloned()
+                        .map(KeyboardFocusTarget::from);
+                    std::mem::drop(shell);
+                    Shell::set_focus(self, new_focus.as_ref(), seat, Some(serial));
+                }
+            }
+
+            CompositorAction::ToggleScratchpad => {
+                let res = self
+                    .common
+                    .shell
+                    .write()
+                    .unwrap()
+                    .toggle_scratchpad(seat, None);
+                if let Some(new_focus) = res {
+                    Shell::set_focus(self, Some(&new_focus), seat, Some(serial));
+                }
+            }
+
+            CompositorAction::ToggleScratchpadApp(app_id) => {
+                let res = self
+                    .common
+                    .shell
+                    .write()
+                    .unwrap()
+                    .toggle_scratchpad(seat, Some(&app_id));
+                if let Some(new_focus) = res {
+                    Shell::set_focus(self, Some(&new_focus), seat, Some(serial));
+                }
+            }
+
+            CompositorAction::MoveTab(direction) => {
+                let _ = self.common.shell.write().unwrap().move_current_tab(
+                    config_direction(direction),
+                    seat,
+                    StackMoveMode::Reorder,
+                );
+            }
+
+            CompositorAction::PullOutTab(direction) => {
+                let res = self.common.shell.write().unwrap().move_current_tab(
+                    config_direction(direction),
+                    seat,
+                    StackMoveMode::PullOut,
+                );
+                if let MoveResult::ShiftFocus(new_focus) = res {
+                    Shell::set_focus(self, Some(&new_focus), seat, Some(serial));
+                }
+            }
+
+            CompositorAction::MergeIntoStack(direction) => {
+                let res = self
+                    .common
+                    .shell
+                    .write()
+                    .unwrap()
+                    .merge_current_into_stack(config_direction(direction), seat);
+                if let MoveResult::ShiftFocus(new_focus) = res {
+                    Shell::set_focus(self, Some(&new_focus), seat, Some(serial));
+                }
+            }
+
+            CompositorAction::ResizeToRatio(ratio) => {
+                self.common
+                    .shell
+                    .write()
+                    .unwrap()
+                    .resize_to_ratio(seat, ratio.as_f64());
+            }
+
+            CompositorAction::BalanceSplits => {
+                self.common.shell.write().unwrap().balance_splits(seat);
+            }
+
+            CompositorAction::SetFloatingGeometry { size, position } => {
+                self.common.shell.write().unwrap().set_floating_geometry(
+                    seat,
+                    size.map(Size::from),
+                    position.map(|position| Point::<i32, Logical>::from(position).as_local()),
+                );
+            }
         }
     }
 
@@ -2499,6 +3158,7 @@ impl State {
                     {
                         let mut shell = self.common.shell.write().unwrap();
                         let shell_ref = &mut *shell;
+                        shell_ref.session.mark_dirty();
                         shell_ref.workspaces.update_autotile(
                             self.common.config.cosmic_conf.autotile,
                             &mut self.common.workspace_state.update(),
@@ -2514,6 +3174,7 @@ impl State {
                 } else {
                     let output = seat.active_output();
                     let mut shell = self.common.shell.write().unwrap();
+                    shell.session.mark_dirty();
                     let workspace = shell.workspaces.active_mut(&output);
                     let mut guard = self.common.workspace_state.update();
                     workspace.toggle_tiling(seat, &mut guard);
@@ -2523,6 +3184,7 @@ impl State {
             Action::ToggleWindowFloating => {
                 let output = seat.active_output();
                 let mut shell = self.common.shell.write().unwrap();
+                shell.session.mark_dirty();
                 let workspace = shell.active_space_mut(&output);
                 workspace.toggle_floating_window_focused(seat);
             }
@@ -2799,21 +3461,144 @@ fn cursor_sessions_for_output(
         .chain(output.cursor_sessions().into_iter())
 }
 
+fn config_direction(direction: bindings::Direction) -> Direction {
+    match direction {
+        bindings::Direction::Left => Direction::Left,
+        bindings::Direction::Right => Direction::Right,
+        bindings::Direction::Up => Direction::Up,
+        bindings::Direction::Down => Direction::Down,
+    }
+}
+
+/// Direction of a swipe as used by gesture bindings, which are independent of natural scrolling
+fn gesture_direction(direction: Direction, natural_scroll: bool) -> bindings::Direction {
+    match (direction, natural_scroll) {
+        (Direction::Left, false) | (Direction::Right, true) => bindings::Direction::Left,
+        (Direction::Right, false) | (Direction::Left, true) => bindings::Direction::Right,
+        (Direction::Up, false) | (Direction::Down, true) => bindings::Direction::Up,
+        (Direction::Down, false) | (Direction::Up, true) => bindings::Direction::Down,
+    }
+}
+
+/// Position of an absolute event in the global space and the output it lands on,
+/// following the `map_to_area` and `map_to_output` settings of the device.
+fn mapped_position<B: InputBackend, E: AbsolutePositionEvent<B>>(
+    config: &Config,
+    shell: &Shell,
+    seat: &Seat<State>,
+    event: &E,
+) -> Option<(Output, Point<f64, Global>)>
+where
+    <B as InputBackend>::Device: 'static,
+{
+    use smithay::backend::input::Event;
+
+    let device = event.device();
+    let device = <dyn Any>::downcast_ref::<InputDevice>(&device);
+
+    let mut area = mapped_area_for_device(config, shell, seat, device)?;
+    if area.size.w <= 0. || area.size.h <= 0. {
+        return None;
+    }
+
+    if let Some((width, height)) = device
+        .filter(|device| config.keep_aspect_ratio(device))
+        .and_then(|device| device.size())
+        .filter(|(w, h)| *w > 0. && *h > 0.)
+    {
+        // letterbox the area to the aspect ratio of the device
+        let ratio = width / height;
+        if area.size.w / area.size.h > ratio {
+            let w = area.size.h * ratio;
+            area.loc.x += (area.size.w - w) / 2.;
+            area.size.w = w;
+        } else {
+            let h = area.size.w / ratio;
+            area.loc.y += (area.size.h - h) / 2.;
+            area.size.h = h;
+        }
+    }
+
+    let position = area.loc
+        + event
+            .position_transformed(area.size.as_logical())
+            .as_global();
+
+    // areas may span gaps between outputs, snap to the closest one
+    let distance = |output: &Output| {
+        let geometry = output.geometry().to_f64();
+        let x = position.x
+            - position
+                .x
+                .clamp(geometry.loc.x, geometry.loc.x + geometry.size.w);
+        let y = position.y
+            - position
+                .y
+                .clamp(geometry.loc.y, geometry.loc.y + geometry.size.h);
+        x * x + y * y
+    };
+    let output = shell
+        .outputs()
+        .min_by(|a, b| distance(a).total_cmp(&distance(b)))?
+        .clone();
+    let geometry = output.geometry().to_f64();
+    let position = Point::from((
+        position.x.clamp(
+            geometry.loc.x,
+            (geometry.loc.x + geometry.size.w).next_lower(),
+        ),
+        position.y.clamp(
+            geometry.loc.y,
+            (geometry.loc.y + geometry.size.h).next_lower(),
+        ),
+    ));
+
+    Some((output, position))
+}
+
 // TODO Is it possible to determine mapping for external touchscreen?
-// Support map_to_region like sway?
-fn mapped_output_for_device<'a, D: Device + 'static>(
+fn mapped_area_for_device(
     config: &Config,
-    shell: &'a Shell,
-    device: &D,
-) -> Option<&'a Output> {
-    let map_to_output = if let Some(device) = <dyn Any>::downcast_ref::<InputDevice>(device) {
-        config
-            .map_to_output(device)
-            .and_then(|name| shell.outputs().find(|output| output.name() == name))
-    } else {
-        None
+    shell: &Shell,
+    seat: &Seat<State>,
+    device: Option<&InputDevice>,
+) -> Option<Rectangle<f64, Global>> {
+    let map_to_output = |name: &str| {
+        shell
+            .outputs()
+            .find(|output| output.name() == name)
+            .map(|output| output.geometry().to_f64())
     };
-    map_to_output.or_else(|| shell.builtin_output())
+
+    let area = device.and_then(|device| match config.map_to_area(device) {
+        Some(MapToArea::Region {
+            x,
+            y,
+            width,
+            height,
+        }) => {
+            let region = Rectangle::<i32, Global>::from_loc_and_size((x, y), (width, height));
+            Some(region.to_f64())
+        }
+        Some(MapToArea::FocusedWindow) => {
+            let Some(KeyboardFocusTarget::Element(mapped)) =
+                seat.get_keyboard().unwrap().current_focus()
+            else {
+                return None;
+            };
+            shell
+                .global_element_geometry(&mapped)
+                .map(|geometry| geometry.to_f64())
+        }
+        Some(MapToArea::AllOutputs) => Some(shell.global_space().to_f64()),
+        None => config.map_to_output(device).and_then(map_to_output),
+    });
+
+    area.or_else(|| {
+        shell
+            .builtin_output()
+            .map(|output| output.geometry().to_f64())
+    })
 }
 
 // FIXME: When f64::next_down reaches stable rust, use that instead
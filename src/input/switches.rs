@@ -0,0 +1,158 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::cell::RefCell;
+
+use cosmic_comp_config::switches::LidAction;
+use smithay::output::Output;
+use tracing::{debug, error, warn};
+
+use crate::{
+    config::{update_input, OutputConfig, OutputState},
+    dbus::logind,
+    state::State,
+};
+
+impl State {
+    pub fn lid_switch_toggled(&mut self, closed: bool) {
+        self.common.lid_closed = closed;
+        if !closed {
+            if let Some((output, previous)) = self.common.lid_disabled_output.take() {
+                self.set_output_state(&output, previous);
+            }
+            return;
+        }
+
+        let (builtin, docked) = self.builtin_output_docked();
+
+        let switches = &self.common.config.cosmic_conf.switches;
+        let action = if docked {
+            switches.lid_close_docked
+        } else {
+            switches.lid_close
+        };
+        debug!(?action, docked, "Lid closed");
+
+        match action {
+            LidAction::Nothing => {}
+            LidAction::DisableOutput => {
+                // never disable the last remaining output
+                if let Some(output) = builtin.filter(|_| docked) {
+                    self.disable_lid_output(output);
+                }
+            }
+            LidAction::Lock => logind::lock_session(),
+            LidAction::Suspend => logind::suspend(),
+        }
+    }
+
+    /// Applies a lid state, that was read instead of reported by the switch,
+    /// e.g. because the lid was already closed on startup.
+    ///
+    /// Only the outputs are updated, the session is never locked or suspended by this.
+    pub fn sync_lid_state(&mut self, closed: bool) {
+        if closed {
+            self.common.lid_closed = true;
+            self.apply_lid_state();
+        } else {
+            self.lid_switch_toggled(false);
+        }
+    }
+
+    /// Disables the builtin output, if the lid is closed and another output got connected
+    pub fn apply_lid_state(&mut self) {
+        if !self.common.lid_closed
+            || self.common.lid_disabled_output.is_some()
+            || self.common.config.cosmic_conf.switches.lid_close_docked != LidAction::DisableOutput
+        {
+            return;
+        }
+
+        if let (Some(output), true) = self.builtin_output_docked() {
+            debug!("Lid closed while docked");
+            self.disable_lid_output(output);
+        }
+    }
+
+    /// The builtin output and whether other outputs are connected
+    fn builtin_output_docked(&self) -> (Option<Output>, bool) {
+        let shell = self.common.shell.read().unwrap();
+        let builtin = shell.builtin_output().cloned();
+        let docked = builtin
+            .as_ref()
+            .is_some_and(|builtin| shell.outputs().any(|output| output != builtin));
+        (builtin, docked)
+    }
+
+    fn disable_lid_output(&mut self, output: Output) {
+        let previous = output
+            .user_data()
+            .get::<RefCell<OutputConfig>>()
+            .unwrap()
+            .borrow()
+            .enabled
+            .clone();
+        if self.set_output_state(&output, OutputState::Disabled) {
+            self.common.lid_disabled_output = Some((output, previous));
+        }
+    }
+
+    pub fn tablet_mode_toggled(&mut self, enabled: bool) {
+        debug!(enabled, "Tablet mode toggled");
+        self.common.tablet_mode = enabled;
+        update_input(self);
+    }
+
+    /// Enables or disables `output` without persisting the change
+    fn set_output_state(&mut self, output: &Output, state: OutputState) -> bool {
+        let backup = std::mem::replace(
+            &mut output
+                .user_data()
+                .get::<RefCell<OutputConfig>>()
+                .unwrap()
+                .borrow_mut()
+                .enabled,
+            state.clone(),
+        );
+
+        let res = self.backend.apply_config_for_outputs(
+            false,
+            &self.common.event_loop_handle,
+            self.common.shell.clone(),
+            &mut self.common.workspace_state.update(),
+            &self.common.xdg_activation_state,
+            self.common.startup_done.clone(),
+        );
+        if let Err(err) = res {
+            warn!(?err, "Failed to apply lid switch output config. Resetting");
+            output
+                .user_data()
+                .get::<RefCell<OutputConfig>>()
+                .unwrap()
+                .borrow_mut()
+                .enabled = backup;
+            if let Err(err) = self.backend.apply_config_for_outputs(
+                false,
+                &self.common.event_loop_handle,
+                self.common.shell.clone(),
+                &mut self.common.workspace_state.update(),
+                &self.common.xdg_activation_state,
+                self.common.startup_done.clone(),
+            ) {
+                error!(?err, "Failed to reset output config.");
+            }
+            return false;
+        }
+        self.common.refresh();
+
+        if state == OutputState::Enabled {
+            self.common.output_configuration_state.enable_head(output);
+        } else {
+            self.common.output_configuration_state.disable_head(output);
+        }
+        self.common.event_loop_handle.insert_idle(move |state| {
+            state.common.output_configuration_state.update();
+        });
+
+        true
+    }
+}
@@ -106,6 +106,11 @@ fn main() -> Result<()> {
     );
     // init backend
     backend::init_backend_auto(&display, &mut event_loop, &mut state)?;
+    state
+        .common
+        .idle_policy
+        .rearm(&state.common.config, &state.common.event_loop_handle);
+    state.refresh_night_light();
 
     if let Err(err) = theme::watch_theme(event_loop.handle()) {
         warn!(?err, "Failed to watch theme");
@@ -116,6 +121,7 @@ fn main() -> Result<()> {
         // shall we shut down?
         if state.common.should_stop {
             info!("Shutting down");
+            state.common.flush_session();
             state.common.event_loop_signal.stop();
             state.common.event_loop_signal.wakeup();
             return;
@@ -130,6 +136,7 @@ fn main() -> Result<()> {
             }
         }
         state.common.refresh();
+        state.refresh_session_lock();
         state::Common::refresh_focus(state);
         state.common.update_x11_stacking_order();
 
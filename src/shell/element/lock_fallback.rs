@@ -0,0 +1,65 @@
+use crate::{
+    fl,
+    utils::iced::{IcedElement, Program},
+};
+
+use calloop::LoopHandle;
+use cosmic::{
+    iced::widget::{column, container, vertical_space},
+    iced_core::{Alignment, Background, Color, Length},
+    theme,
+    widget::{icon::from_name, text},
+    Apply,
+};
+use smithay::utils::{Logical, Size};
+
+pub type LockFallback = IcedElement<LockFallbackInternal>;
+
+/// Screen shown instead of the lock surfaces, after the lock client died
+pub fn lock_fallback(
+    size: Size<i32, Logical>,
+    evlh: LoopHandle<'static, crate::state::State>,
+    theme: cosmic::Theme,
+) -> LockFallback {
+    LockFallback::new(LockFallbackInternal, size, evlh, theme)
+}
+
+pub struct LockFallbackInternal;
+
+impl Program for LockFallbackInternal {
+    type Message = ();
+
+    fn view(&self) -> cosmic::Element<'_, Self::Message> {
+        column(vec![
+            from_name("system-lock-screen-symbolic")
+                .size(64)
+                .prefer_svg(true)
+                .icon()
+                .into(),
+            vertical_space(24).into(),
+            text(fl!("session-locked"))
+                .font(cosmic::font::FONT_SEMIBOLD)
+                .size(24)
+                .into(),
+            vertical_space(8).into(),
+            text(fl!("session-locked-fallback"))
+                .font(cosmic::font::FONT)
+                .size(14)
+                .into(),
+        ])
+        .align_items(Alignment::Center)
+        .apply(container)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .center_x()
+        .center_y()
+        .style(theme::Container::custom(|theme| container::Appearance {
+            icon_color: Some(Color::from(theme.cosmic().background.on)),
+            text_color: Some(Color::from(theme.cosmic().background.on)),
+            background: Some(Background::Color(theme.cosmic().background.base.into())),
+            border: Default::default(),
+            shadow: Default::default(),
+        }))
+        .into()
+    }
+}
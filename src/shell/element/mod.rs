@@ -52,12 +52,14 @@ use std::{
 };
 
 pub mod surface;
-use self::stack::MoveResult;
+use self::stack::{MoveMode, MoveResult};
 pub use self::surface::CosmicSurface;
 pub mod stack;
 pub use self::stack::CosmicStack;
 pub mod window;
 pub use self::window::CosmicWindow;
+pub mod lock_fallback;
+pub mod mode_indicator;
 pub mod resize_indicator;
 pub mod stack_hover;
 pub mod swap_indicator;
@@ -313,9 +315,9 @@ impl CosmicMapped {
         }
     }
 
-    pub fn handle_move(&self, direction: Direction) -> MoveResult {
+    pub fn handle_move(&self, direction: Direction, mode: MoveMode) -> MoveResult {
         if let CosmicMappedInternal::Stack(stack) = &self.element {
-            stack.handle_move(direction)
+            stack.handle_move(direction, mode)
         } else {
             MoveResult::Default
         }
@@ -847,6 +849,7 @@ impl CosmicMapped {
             p_elements: Vec::new(),
         };
 
+        let alpha = alpha * self.active_window().opacity();
         #[cfg_attr(not(feature = "debug"), allow(unused_mut))]
         elements.extend_map(
             match &self.element {
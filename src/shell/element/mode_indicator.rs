@@ -0,0 +1,66 @@
+use crate::utils::iced::{IcedElement, Program};
+
+use calloop::LoopHandle;
+use cosmic::{
+    iced::widget::{container, horizontal_space, row},
+    iced_core::{Alignment, Background, Border, Color},
+    theme,
+    widget::{icon::from_name, text},
+    Apply,
+};
+use smithay::utils::Size;
+
+pub type ModeIndicator = IcedElement<ModeIndicatorInternal>;
+
+pub fn mode_indicator(
+    name: String,
+    evlh: LoopHandle<'static, crate::state::State>,
+    theme: cosmic::Theme,
+) -> ModeIndicator {
+    let indicator = ModeIndicator::new(
+        ModeIndicatorInternal { name },
+        Size::from((1, 1)),
+        evlh,
+        theme,
+    );
+    indicator.resize(indicator.minimum_size());
+    indicator
+}
+
+pub struct ModeIndicatorInternal {
+    pub name: String,
+}
+
+impl Program for ModeIndicatorInternal {
+    type Message = ();
+
+    fn view(&self) -> cosmic::Element<'_, Self::Message> {
+        row(vec![
+            from_name("input-keyboard-symbolic")
+                .size(24)
+                .prefer_svg(true)
+                .icon()
+                .into(),
+            horizontal_space(12).into(),
+            text(&self.name)
+                .font(cosmic::font::FONT_SEMIBOLD)
+                .size(18)
+                .into(),
+        ])
+        .align_items(Alignment::Center)
+        .apply(container)
+        .padding(12)
+        .style(theme::Container::custom(|theme| container::Appearance {
+            icon_color: Some(Color::from(theme.cosmic().accent.on)),
+            text_color: Some(Color::from(theme.cosmic().accent.on)),
+            background: Some(Background::Color(theme.cosmic().accent_color().into())),
+            border: Border {
+                radius: 18.0.into(),
+                width: 0.0,
+                color: Color::TRANSPARENT,
+            },
+            shadow: Default::default(),
+        }))
+        .into()
+    }
+}
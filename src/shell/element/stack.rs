@@ -127,6 +127,17 @@ pub enum MoveResult {
     Default,
 }
 
+/// How a move of the active tab is handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveMode {
+    /// Reorder the active tab, moving it out of the stack at either end
+    Default,
+    /// Only reorder the active tab
+    Reorder,
+    /// Move the active tab out of the stack
+    PullOut,
+}
+
 impl CosmicStack {
     pub fn new<I: Into<CosmicSurface>>(
         windows: impl Iterator<Item = I>,
@@ -334,7 +345,7 @@ impl CosmicStack {
         result
     }
 
-    pub fn handle_move(&self, direction: Direction) -> MoveResult {
+    pub fn handle_move(&self, direction: Direction, mode: MoveMode) -> MoveResult {
         let loop_handle = self.0.loop_handle();
         let result = self.0.with_program(|p| {
             if p.group_focused.load(Ordering::SeqCst) {
@@ -344,10 +355,11 @@ impl CosmicStack {
             let active = p.active.load(Ordering::SeqCst);
             let mut windows = p.windows.lock().unwrap();
 
-            let next = match direction {
-                Direction::Left => active.checked_sub(1),
-                Direction::Right => (active + 1 < windows.len()).then_some(active + 1),
-                Direction::Down | Direction::Up => None,
+            let next = match (mode, direction) {
+                (MoveMode::PullOut, _) => None,
+                (_, Direction::Left) => active.checked_sub(1),
+                (_, Direction::Right) => (active + 1 < windows.len()).then_some(active + 1),
+                (_, Direction::Down | Direction::Up) => None,
             };
 
             if let Some(val) = next {
@@ -356,6 +368,9 @@ impl CosmicStack {
                 p.previous_keyboard.store(old, Ordering::SeqCst);
                 p.scroll_to_focus.store(true, Ordering::SeqCst);
                 MoveResult::Handled
+            } else if mode == MoveMode::Reorder {
+                // already at the end of the stack
+                MoveResult::Handled
             } else {
                 if windows.len() == 1 {
                     return MoveResult::Default;
@@ -1449,6 +1464,10 @@ impl TouchTarget<State> for CosmicStack {
             p.windows.lock().unwrap()[p.active.load(Ordering::SeqCst)].geometry()
         });
         event.location -= active_window_geo.loc.to_f64();
+        // in tablet mode the resize border above the tabs is part of them
+        if data.common.touch_friendly() && (-RESIZE_BORDER as f64..0.).contains(&event.location.y) {
+            event.location.y = 0.;
+        }
         TouchTarget::down(&self.0, seat, data, &event, seq)
     }
 
@@ -1,6 +1,9 @@
 use std::{
     borrow::Cow,
-    sync::atomic::{AtomicBool, Ordering},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
     time::Duration,
 };
 
@@ -84,6 +87,14 @@ impl PartialEq<X11Surface> for CosmicSurface {
 #[derive(Default)]
 struct Minimized(AtomicBool);
 
+struct Opacity(Mutex<f32>);
+
+impl Default for Opacity {
+    fn default() -> Self {
+        Opacity(Mutex::new(1.0))
+    }
+}
+
 pub const SSD_HEIGHT: i32 = 36;
 pub const RESIZE_BORDER: i32 = 10;
 
@@ -387,6 +398,26 @@ impl CosmicSurface {
         }
     }
 
+    pub fn opacity(&self) -> f32 {
+        *self
+            .0
+            .user_data()
+            .get_or_insert_threadsafe(Opacity::default)
+            .0
+            .lock()
+            .unwrap()
+    }
+
+    pub fn set_opacity(&self, opacity: f32) {
+        *self
+            .0
+            .user_data()
+            .get_or_insert_threadsafe(Opacity::default)
+            .0
+            .lock()
+            .unwrap() = opacity.clamp(0.0, 1.0);
+    }
+
     pub fn set_suspended(&self, suspended: bool) {
         match self.0.underlying_surface() {
             WindowSurface::Wayland(window) => window.with_pending_state(|state| {
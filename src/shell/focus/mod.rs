@@ -1,5 +1,8 @@
 use crate::{
-    shell::{element::CosmicMapped, Shell},
+    shell::{
+        element::{CosmicMapped, CosmicSurface},
+        Shell,
+    },
     state::Common,
     utils::prelude::*,
     wayland::handlers::xdg_shell::PopupGrabData,
@@ -7,7 +10,7 @@ use crate::{
 use indexmap::IndexSet;
 use smithay::{
     desktop::{layer_map_for_output, PopupUngrabStrategy},
-    input::{pointer::MotionEvent, Seat},
+    input::{keyboard::Layout, pointer::MotionEvent, Seat},
     output::Output,
     reexports::wayland_server::Resource,
     utils::{IsAlive, Serial, SERIAL_COUNTER},
@@ -92,6 +95,41 @@ impl ActiveFocus {
     }
 }
 
+/// Keyboard layout group, that was active when the window lost focus
+struct WindowLayout(Mutex<Layout>);
+
+impl WindowLayout {
+    fn set(window: &CosmicSurface, layout: Layout) {
+        if !window
+            .user_data()
+            .insert_if_missing_threadsafe(|| WindowLayout(Mutex::new(layout)))
+        {
+            *window
+                .user_data()
+                .get::<WindowLayout>()
+                .unwrap()
+                .0
+                .lock()
+                .unwrap() = layout;
+        }
+    }
+
+    fn get(window: &CosmicSurface) -> Option<Layout> {
+        window
+            .user_data()
+            .get::<WindowLayout>()
+            .map(|l| *l.0.lock().unwrap())
+    }
+}
+
+fn focused_window(target: &KeyboardFocusTarget) -> Option<CosmicSurface> {
+    match target {
+        KeyboardFocusTarget::Element(mapped) => Some(mapped.active_window()),
+        KeyboardFocusTarget::Fullscreen(surface) => Some(surface.clone()),
+        _ => None,
+    }
+}
+
 impl Shell {
     pub fn set_focus(
         state: &mut State,
@@ -125,12 +163,28 @@ impl Shell {
 
         // update keyboard focus
         if let Some(keyboard) = seat.get_keyboard() {
+            let layout_per_window = state.common.config.cosmic_conf.keyboard_layout_per_window;
+            if layout_per_window {
+                if let Some(window) = keyboard.current_focus().as_ref().and_then(focused_window) {
+                    let layout = keyboard.with_xkb_state(state, |context| context.active_layout());
+                    WindowLayout::set(&window, layout);
+                }
+            }
+
             ActiveFocus::set(seat, target.cloned());
             keyboard.set_focus(
                 state,
                 target.cloned(),
                 serial.unwrap_or_else(|| SERIAL_COUNTER.next_serial()),
             );
+
+            // windows without a stored layout start with the first one
+            if layout_per_window {
+                if let Some(window) = target.and_then(focused_window) {
+                    let layout = WindowLayout::get(&window).unwrap_or(Layout(0));
+                    keyboard.with_xkb_state(state, |mut context| context.set_layout(layout));
+                }
+            }
         }
 
         state.common.shell.write().unwrap().update_active();
@@ -205,6 +259,7 @@ impl Shell {
             let workspace = self.workspaces.active_mut(&output);
             for focused in focused_windows.iter() {
                 raise_with_children(&mut workspace.floating_layer, focused);
+                workspace.scrolling_layer.focus(focused);
             }
             for window in workspace.mapped() {
                 window.set_activated(focused_windows.contains(&window));
@@ -0,0 +1,504 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use calloop::{
+    timer::{TimeoutAction, Timer},
+    RegistrationToken,
+};
+use cosmic_comp_config::night_light::NightLightSchedule;
+use smithay::output::Output;
+use time::{
+    util::local_offset::{set_soundness, Soundness},
+    Date, OffsetDateTime, UtcOffset,
+};
+use tracing::warn;
+
+use crate::state::State;
+
+/// Gamma ramp size offered by backends, that apply the ramp while rendering
+pub const FALLBACK_GAMMA_SIZE: u32 = 256;
+
+/// Gamma lookup table, with the red, green and blue channels one after another
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GammaRamp(Vec<u16>);
+
+impl GammaRamp {
+    pub fn from_raw(ramp: Vec<u16>) -> GammaRamp {
+        debug_assert_eq!(ramp.len() % 3, 0);
+        GammaRamp(ramp)
+    }
+
+    /// Linear ramp with the white point of a black body at `temperature` Kelvin
+    pub fn from_temperature(temperature: u32, size: u32) -> GammaRamp {
+        let white = whitepoint(temperature);
+        let size = size as usize;
+        GammaRamp(
+            white
+                .iter()
+                .flat_map(|channel| {
+                    (0..size).map(move |i| {
+                        let value = i as f64 / (size - 1).max(1) as f64 * channel;
+                        (value * u16::MAX as f64).round() as u16
+                    })
+                })
+                .collect(),
+        )
+    }
+
+    /// Number of entries per channel
+    pub fn size(&self) -> usize {
+        self.0.len() / 3
+    }
+
+    pub fn red(&self) -> &[u16] {
+        &self.0[..self.size()]
+    }
+
+    pub fn green(&self) -> &[u16] {
+        &self.0[self.size()..self.size() * 2]
+    }
+
+    pub fn blue(&self) -> &[u16] {
+        &self.0[self.size() * 2..]
+    }
+
+    /// Approximates every channel by `mult * x^exp`, for renderers without lookup tables.
+    ///
+    /// Returns `(mult, exp)`, this is exact for the ramps of the usual night light clients.
+    pub fn curve(&self) -> ([f32; 3], [f32; 3]) {
+        let mut mult = [1.0; 3];
+        let mut exp = [1.0; 3];
+
+        let size = self.size();
+        if size < 3 {
+            return (mult, exp);
+        }
+
+        let mid = size / 2;
+        let x = mid as f32 / (size - 1) as f32;
+        for (i, channel) in [self.red(), self.green(), self.blue()]
+            .into_iter()
+            .enumerate()
+        {
+            let max = channel[size - 1] as f32 / u16::MAX as f32;
+            let y = channel[mid] as f32 / u16::MAX as f32;
+            mult[i] = max;
+            if max > 0.0 && y > 0.0 && y < max {
+                exp[i] = ((y / max).ln() / x.ln()).clamp(0.1, 10.0);
+            }
+        }
+
+        (mult, exp)
+    }
+}
+
+/// Approximated color of a black body at `temperature` Kelvin, normalized to the brightest channel
+fn whitepoint(temperature: u32) -> [f64; 3] {
+    let t = temperature.clamp(1000, 40000) as f64 / 100.0;
+    let red = if t <= 66.0 {
+        255.0
+    } else {
+        329.698727446 * (t - 60.0).powf(-0.1332047592)
+    };
+    let green = if t <= 66.0 {
+        99.4708025861 * t.ln() - 161.1195681661
+    } else {
+        288.1221695283 * (t - 60.0).powf(-0.0755148492)
+    };
+    let blue = if t >= 66.0 {
+        255.0
+    } else if t <= 19.0 {
+        0.0
+    } else {
+        138.5177312231 * (t - 10.0).ln() - 305.0447927307
+    };
+
+    let max = red.max(green).max(blue);
+    [red, green, blue].map(|channel| (channel / max).clamp(0.0, 1.0))
+}
+
+/// Gamma ramp applied to an output, stored in its user data
+#[derive(Debug, Default)]
+pub struct OutputGamma {
+    ramp: Mutex<Option<Arc<GammaRamp>>>,
+}
+
+impl OutputGamma {
+    pub fn get(output: &Output) -> &OutputGamma {
+        output
+            .user_data()
+            .insert_if_missing_threadsafe(OutputGamma::default);
+        output.user_data().get::<OutputGamma>().unwrap()
+    }
+
+    pub fn ramp(&self) -> Option<Arc<GammaRamp>> {
+        self.ramp.lock().unwrap().clone()
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct NightLight {
+    active: bool,
+    timer: Option<RegistrationToken>,
+}
+
+/// The monotonic timers don't advance during system suspend, so recheck the schedule regularly
+const NIGHT_LIGHT_RECHECK: Duration = Duration::from_secs(10 * 60);
+
+/// Whether the night light is active at `now` and the time until that changes
+fn night_light_state(
+    schedule: NightLightSchedule,
+    now: OffsetDateTime,
+) -> (bool, Option<Duration>) {
+    match schedule {
+        NightLightSchedule::Fixed { start, end } => {
+            let start = Duration::from_secs(start.0 as u64 * 3600 + start.1 as u64 * 60);
+            let end = Duration::from_secs(end.0 as u64 * 3600 + end.1 as u64 * 60);
+            if start == end {
+                return (false, None);
+            }
+
+            let day = Duration::from_secs(24 * 3600);
+            let (h, m, s) = now.time().as_hms();
+            let now = Duration::from_secs(h as u64 * 3600 + m as u64 * 60 + s as u64);
+            let until = |time: Duration| {
+                if time > now {
+                    time - now
+                } else {
+                    time + day - now
+                }
+            };
+
+            let active = if start < end {
+                start <= now && now < end
+            } else {
+                now >= start || now < end
+            };
+            (active, Some(if active { until(end) } else { until(start) }))
+        }
+        NightLightSchedule::SunsetToSunrise {
+            latitude,
+            longitude,
+        } => {
+            let Some((sunrise, sunset)) = sun_times(now.date(), latitude, longitude) else {
+                // polar day or night
+                let polar_night = matches!(now.month() as u8, 10..=12 | 1..=3) == (latitude > 0.0);
+                return (polar_night, Some(NIGHT_LIGHT_RECHECK));
+            };
+
+            if now < sunrise {
+                (true, Some((sunrise - now).unsigned_abs()))
+            } else if now < sunset {
+                (false, Some((sunset - now).unsigned_abs()))
+            } else {
+                let next_sunrise = now
+                    .date()
+                    .next_day()
+                    .and_then(|date| sun_times(date, latitude, longitude))
+                    .map(|(sunrise, _)| (sunrise - now).unsigned_abs())
+                    .unwrap_or(NIGHT_LIGHT_RECHECK);
+                (true, Some(next_sunrise))
+            }
+        }
+    }
+}
+
+/// Sunrise and sunset at `date` and the given location, using the sunrise equation.
+///
+/// Returns `None`, if the sun doesn't rise or set on that day.
+fn sun_times(
+    date: Date,
+    latitude: f64,
+    longitude: f64,
+) -> Option<(OffsetDateTime, OffsetDateTime)> {
+    const J2000: f64 = 2451545.0;
+    const UNIX_EPOCH: f64 = 2440587.5;
+
+    let day = date.to_julian_day() as f64 - J2000 + 0.0008;
+    let mean_solar_time = day - longitude / 360.0;
+    let anomaly = (357.5291 + 0.98560028 * mean_solar_time).rem_euclid(360.0);
+    let m = anomaly.to_radians();
+    let center = 1.9148 * m.sin() + 0.02 * (2.0 * m).sin() + 0.0003 * (3.0 * m).sin();
+    let ecliptic_longitude = (anomaly + center + 180.0 + 102.9372)
+        .rem_euclid(360.0)
+        .to_radians();
+    let transit =
+        J2000 + mean_solar_time + 0.0053 * m.sin() - 0.0069 * (2.0 * ecliptic_longitude).sin();
+
+    let declination = (ecliptic_longitude.sin() * 23.4397f64.to_radians().sin()).asin();
+    let latitude = latitude.to_radians();
+    let hour_angle = ((-0.833f64).to_radians().sin() - latitude.sin() * declination.sin())
+        / (latitude.cos() * declination.cos());
+    if !(-1.0..=1.0).contains(&hour_angle) {
+        return None;
+    }
+    let hour_angle = hour_angle.acos().to_degrees() / 360.0;
+
+    let to_time = |julian: f64| {
+        OffsetDateTime::from_unix_timestamp(((julian - UNIX_EPOCH) * 86400.0) as i64).ok()
+    };
+    Some((
+        to_time(transit - hour_angle)?,
+        to_time(transit + hour_angle)?,
+    ))
+}
+
+impl State {
+    /// Re-evaluates the night light schedule and updates the gamma ramps of all outputs
+    pub fn refresh_night_light(&mut self) {
+        if let Some(token) = self.common.night_light.timer.take() {
+            self.common.event_loop_handle.remove(token);
+        }
+
+        let now = OffsetDateTime::now_utc();
+        // the offset changes with daylight saving time, while the compositor is running
+        // (other threads are running by now, but the compositor never modifies its environment)
+        unsafe {
+            set_soundness(Soundness::Unsound);
+        }
+        match UtcOffset::local_offset_at(now) {
+            Ok(offset) => self.common.local_offset = offset,
+            Err(err) => warn!(?err, "Failed to determine the local time offset."),
+        }
+        unsafe {
+            set_soundness(Soundness::Sound);
+        }
+
+        let config = self.common.config.cosmic_conf.night_light;
+        let now = now.to_offset(self.common.local_offset);
+        let (active, next_change) = if config.enabled {
+            night_light_state(config.schedule, now)
+        } else {
+            (false, None)
+        };
+
+        self.common.night_light.active = active;
+        if let Some(next_change) = next_change {
+            match self.common.event_loop_handle.insert_source(
+                Timer::from_duration(next_change.min(NIGHT_LIGHT_RECHECK)),
+                |_, _, state| {
+                    state.common.night_light.timer = None;
+                    state.refresh_night_light();
+                    TimeoutAction::Drop
+                },
+            ) {
+                Ok(token) => self.common.night_light.timer = Some(token),
+                Err(err) => warn!(?err, "Failed to start night light timer."),
+            }
+        }
+
+        self.refresh_gamma();
+    }
+
+    /// Updates the gamma ramps of all outputs
+    pub fn refresh_gamma(&mut self) {
+        let outputs = self
+            .common
+            .shell
+            .read()
+            .unwrap()
+            .outputs()
+            .cloned()
+            .collect::<Vec<_>>();
+        for output in outputs {
+            self.refresh_output_gamma(&output);
+        }
+    }
+
+    /// Applies the gamma ramp set by a client or the night light to `output`
+    pub fn refresh_output_gamma(&mut self, output: &Output) {
+        let Some(size) = self.backend.gamma_size(output) else {
+            return;
+        };
+
+        let ramp = if let Some(ramp) = self.common.gamma_control_state.gamma(output) {
+            Some(GammaRamp::from_raw(ramp.to_vec()))
+        } else if self.common.night_light.active {
+            let temperature = self.common.config.cosmic_conf.night_light.temperature;
+            Some(GammaRamp::from_temperature(temperature, size))
+        } else {
+            None
+        };
+
+        {
+            let mut current = OutputGamma::get(output).ramp.lock().unwrap();
+            if current.as_deref() == ramp.as_ref() {
+                return;
+            }
+            *current = ramp.clone().map(Arc::new);
+        }
+
+        self.backend.set_gamma(output, ramp.as_ref());
+        self.backend.schedule_render(output);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{night_light_state, sun_times, GammaRamp};
+    use cosmic_comp_config::night_light::NightLightSchedule;
+    use std::time::Duration;
+    use time::{
+        macros::{date, datetime},
+        OffsetDateTime,
+    };
+
+    fn assert_close(value: f32, expected: f32) {
+        assert!(
+            (value - expected).abs() < 0.01,
+            "{} isn't close to {}",
+            value,
+            expected
+        );
+    }
+
+    fn assert_time(time: OffsetDateTime, expected: OffsetDateTime) {
+        assert!(
+            (time - expected).abs() < time::Duration::minutes(5),
+            "{} isn't close to {}",
+            time,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_curve() {
+        let size = 256;
+        let ramp = (0..3)
+            .flat_map(|channel| {
+                (0..size).map(move |i| {
+                    let x = i as f64 / (size - 1) as f64;
+                    let max = [1.0, 0.8, 0.5][channel];
+                    (max * x.powf(2.2) * u16::MAX as f64).round() as u16
+                })
+            })
+            .collect();
+        let (mult, exp) = GammaRamp::from_raw(ramp).curve();
+        for (channel, max) in [1.0, 0.8, 0.5].into_iter().enumerate() {
+            assert_close(mult[channel], max);
+            assert_close(exp[channel], 2.2);
+        }
+
+        // linear ramps only scale the channels
+        let ramp = GammaRamp::from_temperature(4000, size as u32);
+        let (mult, exp) = ramp.curve();
+        assert_eq!(mult[0], 1.0);
+        assert!(mult[1] < 1.0 && mult[2] < mult[1]);
+        for exp in exp {
+            assert_close(exp, 1.0);
+        }
+
+        // too small to fit anything
+        let (mult, exp) = GammaRamp::from_raw(vec![0, u16::MAX, 0, 0, 0, 0]).curve();
+        assert_eq!((mult, exp), ([1.0; 3], [1.0; 3]));
+    }
+
+    #[test]
+    fn test_from_temperature() {
+        let ramp = GammaRamp::from_temperature(6600, 4);
+        assert_eq!(ramp.size(), 4);
+        for channel in [ramp.red(), ramp.green(), ramp.blue()] {
+            assert_eq!(channel[0], 0);
+            assert!(channel[3] > u16::MAX - 300);
+        }
+    }
+
+    #[test]
+    fn test_fixed_schedule() {
+        let schedule = NightLightSchedule::Fixed {
+            start: (21, 0),
+            end: (7, 0),
+        };
+        let hours = |h: u64| Duration::from_secs(h * 3600);
+        assert_eq!(
+            night_light_state(schedule, datetime!(2024-06-21 22:00 UTC)),
+            (true, Some(hours(9)))
+        );
+        assert_eq!(
+            night_light_state(schedule, datetime!(2024-06-21 12:00 UTC)),
+            (false, Some(hours(9)))
+        );
+        assert_eq!(
+            night_light_state(schedule, datetime!(2024-06-21 06:30 UTC)),
+            (true, Some(Duration::from_secs(30 * 60)))
+        );
+        assert_eq!(
+            night_light_state(schedule, datetime!(2024-06-21 07:00 UTC)),
+            (false, Some(hours(14)))
+        );
+
+        let schedule = NightLightSchedule::Fixed {
+            start: (8, 0),
+            end: (10, 30),
+        };
+        assert_eq!(
+            night_light_state(schedule, datetime!(2024-06-21 09:00 UTC)),
+            (true, Some(Duration::from_secs(90 * 60)))
+        );
+        assert_eq!(
+            night_light_state(schedule, datetime!(2024-06-21 11:00 UTC)),
+            (false, Some(hours(21)))
+        );
+
+        let schedule = NightLightSchedule::Fixed {
+            start: (8, 0),
+            end: (8, 0),
+        };
+        assert_eq!(
+            night_light_state(schedule, datetime!(2024-06-21 08:00 UTC)),
+            (false, None)
+        );
+    }
+
+    #[test]
+    fn test_sun_times() {
+        // Berlin, at the summer solstice
+        let (sunrise, sunset) = sun_times(date!(2024 - 06 - 21), 52.52, 13.405).unwrap();
+        assert_time(sunrise, datetime!(2024-06-21 02:43 UTC));
+        assert_time(sunset, datetime!(2024-06-21 19:33 UTC));
+
+        // the equator, at the equinox
+        let (sunrise, sunset) = sun_times(date!(2024 - 03 - 20), 0.0, 0.0).unwrap();
+        let day = sunset - sunrise;
+        assert!(day > time::Duration::hours(12) && day < time::Duration::minutes(12 * 60 + 15));
+
+        // polar day and night
+        assert!(sun_times(date!(2024 - 06 - 21), 80.0, 0.0).is_none());
+        assert!(sun_times(date!(2024 - 12 - 21), 80.0, 0.0).is_none());
+    }
+
+    #[test]
+    fn test_sunset_to_sunrise_schedule() {
+        let schedule = NightLightSchedule::SunsetToSunrise {
+            latitude: 52.52,
+            longitude: 13.405,
+        };
+        let (active, next) = night_light_state(schedule, datetime!(2024-06-21 12:00 UTC));
+        assert!(!active);
+        let next = next.unwrap().as_secs() as i64;
+        assert!((next - (7 * 3600 + 33 * 60)).abs() < 5 * 60);
+
+        let (active, next) = night_light_state(schedule, datetime!(2024-06-21 23:00 UTC));
+        assert!(active);
+        let next = next.unwrap().as_secs() as i64;
+        assert!((next - (3 * 3600 + 43 * 60)).abs() < 5 * 60);
+
+        let (active, _) = night_light_state(schedule, datetime!(2024-06-21 01:00 UTC));
+        assert!(active);
+
+        let polar = |latitude: f64, now: OffsetDateTime| {
+            let schedule = NightLightSchedule::SunsetToSunrise {
+                latitude,
+                longitude: 0.0,
+            };
+            night_light_state(schedule, now).0
+        };
+        assert!(!polar(80.0, datetime!(2024-06-21 00:00 UTC)));
+        assert!(polar(80.0, datetime!(2024-12-21 12:00 UTC)));
+        assert!(polar(-80.0, datetime!(2024-06-21 12:00 UTC)));
+    }
+}
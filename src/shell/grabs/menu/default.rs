@@ -15,6 +15,19 @@ use crate::{
 
 use super::{Item, ResizeEdge};
 
+fn set_opacity(state: &mut State, mapped: &CosmicMapped, opacity: f32) {
+    let window = mapped.active_window();
+    window.set_opacity(opacity);
+
+    let shell = state.common.shell.read().unwrap();
+    if let Some(output) = window
+        .wl_surface()
+        .and_then(|surface| shell.visible_output_for_surface(&surface))
+    {
+        state.backend.schedule_render(output);
+    }
+}
+
 fn toggle_stacking(state: &mut State, mapped: &CosmicMapped) {
     let mut shell = state.common.shell.write().unwrap();
     let seat = shell.seats.last_active().clone();
@@ -128,10 +141,7 @@ pub fn tab_items(
                     {
                         workspace.unmaximize_request(&mapped);
                     }
-                    let focus_stack = workspace.focus_stack.get(&seat);
-                    workspace
-                        .tiling_layer
-                        .map(mapped, Some(focus_stack.iter()), None);
+                    workspace.map_tiled(mapped, Some(&seat), None);
                 } else {
                     workspace.floating_layer.map(mapped, None)
                 }
@@ -175,6 +185,7 @@ pub fn window_items(
     let stack_clone = window.clone();
     let sticky_clone = window.clone();
     let close_clone = window.clone();
+    let opacity = window.active_window().opacity();
 
     vec![
         (!is_stacked).then_some(
@@ -225,6 +236,7 @@ pub fn window_items(
                 let tile_clone = tile_clone.clone();
                 let _ = handle.insert_idle(move |state| {
                     let mut shell = state.common.shell.write().unwrap();
+                    shell.session.mark_dirty();
                     let seat = shell.seats.last_active().clone();
                     if let Some(ws) = shell.space_for_mut(&tile_clone) {
                         ws.toggle_floating_window(&seat, &tile_clone);
@@ -423,6 +435,20 @@ pub fn window_items(
             .shortcut(config.shortcut_for_action(&Action::MoveToNextWorkspace))
             .disabled(is_sticky),
         ),
+        Some(Item::new_submenu(
+            fl!("window-menu-opacity"),
+            [1.0, 0.9, 0.75, 0.5]
+                .into_iter()
+                .map(|value: f32| {
+                    let mapped = window.clone();
+                    Item::new(format!("{}%", (value * 100.0).round()), move |handle| {
+                        let mapped = mapped.clone();
+                        let _ = handle.insert_idle(move |state| set_opacity(state, &mapped, value));
+                    })
+                    .toggled((opacity - value).abs() < 0.01)
+                })
+                .collect(),
+        )),
         Some(Item::Separator),
         Some(
             Item::new(fl!("window-menu-sticky"), move |handle| {
@@ -63,6 +63,7 @@ pub struct MoveGrabState {
     start: Instant,
     previous: ManagedLayer,
     snapping_zone: Option<SnappingZone>,
+    snapping_gaps: (i32, i32),
     stacking_indicator: Option<(StackHover, Point<i32, Logical>)>,
     location: Point<f64, Logical>,
     cursor_output: Output,
@@ -146,7 +147,7 @@ impl MoveGrabState {
             layers.non_exclusive_zone()
         };
 
-        let gaps = (theme.gaps.0 as i32, theme.gaps.1 as i32);
+        let gaps = self.snapping_gaps;
 
         let snapping_indicator = match &self.snapping_zone {
             Some(t) if &self.cursor_output == output => {
@@ -419,6 +420,8 @@ impl MoveGrab {
             // Check for overlapping with zones
             if grab_state.previous == ManagedLayer::Floating {
                 let output_geometry = current_output.geometry().to_local(&current_output);
+                grab_state.snapping_gaps =
+                    shell.active_space(&current_output).floating_layer.gaps();
                 grab_state.snapping_zone = [
                     SnappingZone::Maximize,
                     SnappingZone::Top,
@@ -696,6 +699,7 @@ impl MoveGrab {
             start: Instant::now(),
             stacking_indicator: None,
             snapping_zone: None,
+            snapping_gaps: (0, 0),
             previous: previous_layer,
             location: start_data.location(),
             cursor_output: cursor_output.clone(),
@@ -757,6 +761,7 @@ impl Drop for MoveGrab {
                     let window_location =
                         (grab_state.location.to_i32_round() + grab_state.window_offset).as_global();
                     let mut shell = state.common.shell.write().unwrap();
+                    shell.session.mark_dirty();
 
                     let workspace_handle = shell.active_space(&output).handle;
                     for old_output in window_outputs.iter().filter(|o| *o != &output) {
@@ -783,6 +788,13 @@ impl Drop for MoveGrab {
 
                             Some((window, location.to_global(&output)))
                         }
+                        ManagedLayer::Tiling if shell.active_space(&output).scrolling_enabled => {
+                            let workspace = shell.active_space_mut(&output);
+                            workspace.map_tiled(grab_state.window.clone(), Some(&seat), None);
+                            let location =
+                                workspace.element_geometry(&grab_state.window).unwrap().loc;
+                            Some((grab_state.window, location.to_global(&output)))
+                        }
                         ManagedLayer::Tiling if shell.active_space(&output).tiling_enabled => {
                             let (window, location) = shell
                                 .active_space_mut(&output)
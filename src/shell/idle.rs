@@ -0,0 +1,218 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+
+use calloop::{
+    timer::{TimeoutAction, Timer},
+    LoopHandle, RegistrationToken,
+};
+use smithay::{backend::renderer::element::Id, output::Output};
+use tracing::warn;
+
+use crate::{config::Config, state::State};
+
+/// Power state of an output, stored in its user data
+#[derive(Debug)]
+pub struct OutputPower {
+    dimmed: AtomicBool,
+    on: AtomicBool,
+    /// Id of the element dimming the output
+    pub dim_id: Id,
+}
+
+impl Default for OutputPower {
+    fn default() -> Self {
+        OutputPower {
+            dimmed: AtomicBool::new(false),
+            on: AtomicBool::new(true),
+            dim_id: Id::new(),
+        }
+    }
+}
+
+impl OutputPower {
+    pub fn get(output: &Output) -> &OutputPower {
+        output
+            .user_data()
+            .insert_if_missing_threadsafe(OutputPower::default);
+        output.user_data().get::<OutputPower>().unwrap()
+    }
+
+    pub fn is_dimmed(&self) -> bool {
+        self.dimmed.load(Ordering::SeqCst)
+    }
+
+    pub fn is_on(&self) -> bool {
+        self.on.load(Ordering::SeqCst)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum IdleStage {
+    Active,
+    Dimmed,
+    Off,
+}
+
+/// Dims and turns off all outputs after the configured time without input
+#[derive(Debug)]
+pub struct IdlePolicy {
+    stage: IdleStage,
+    last_activity: Instant,
+    /// Whether a visible surface inhibits idling
+    inhibited: bool,
+    timer: Option<RegistrationToken>,
+    /// Outputs turned off by the policy, that are turned on again by input
+    powered_off: Vec<Output>,
+}
+
+impl Default for IdlePolicy {
+    fn default() -> Self {
+        IdlePolicy {
+            stage: IdleStage::Active,
+            last_activity: Instant::now(),
+            inhibited: false,
+            timer: None,
+            powered_off: Vec::new(),
+        }
+    }
+}
+
+impl IdlePolicy {
+    /// Resets the idle time and wakes up the outputs, if they were dimmed or turned off
+    pub fn notify_activity(&mut self, evlh: &LoopHandle<'static, State>) {
+        self.last_activity = Instant::now();
+        if self.stage != IdleStage::Active {
+            self.stage = IdleStage::Active;
+            evlh.insert_idle(|state| state.apply_idle_stage());
+        }
+    }
+
+    pub fn set_inhibited(&mut self, inhibited: bool) {
+        if self.inhibited && !inhibited {
+            // start counting once the inhibitor is gone
+            self.last_activity = Instant::now();
+        }
+        self.inhibited = inhibited;
+    }
+
+    /// Restarts the timer for the next stage, e.g. after the config changed
+    pub fn rearm(&mut self, config: &Config, evlh: &LoopHandle<'static, State>) {
+        if let Some(token) = self.timer.take() {
+            evlh.remove(token);
+        }
+
+        let idle = config.cosmic_conf.idle;
+        let Some(timeout) = [
+            (IdleStage::Dimmed, idle.dim_timeout),
+            (IdleStage::Off, idle.screen_off_timeout),
+        ]
+        .into_iter()
+        .filter(|(stage, _)| *stage > self.stage)
+        .filter_map(|(_, timeout)| timeout)
+        .min() else {
+            return;
+        };
+
+        let remaining =
+            Duration::from_secs(timeout as u64).saturating_sub(self.last_activity.elapsed());
+        match evlh.insert_source(Timer::from_duration(remaining), |_, _, state| {
+            state.common.idle_policy.timer = None;
+            state.idle_timeout();
+            TimeoutAction::Drop
+        }) {
+            Ok(token) => self.timer = Some(token),
+            Err(err) => warn!(?err, "Failed to start idle timer."),
+        }
+    }
+
+    /// Last stage, whose timeout has passed
+    fn reached_stage(&self, config: &Config) -> IdleStage {
+        let idle = config.cosmic_conf.idle;
+        let elapsed = self.last_activity.elapsed();
+        let reached = |timeout: Option<u32>| {
+            timeout.is_some_and(|t| elapsed >= Duration::from_secs(t as u64))
+        };
+
+        if reached(idle.screen_off_timeout) {
+            IdleStage::Off
+        } else if reached(idle.dim_timeout) {
+            IdleStage::Dimmed
+        } else {
+            IdleStage::Active
+        }
+    }
+}
+
+impl State {
+    fn idle_timeout(&mut self) {
+        let policy = &mut self.common.idle_policy;
+        if policy.inhibited {
+            policy.last_activity = Instant::now();
+        } else {
+            let stage = policy.reached_stage(&self.common.config);
+            if stage > policy.stage {
+                policy.stage = stage;
+                self.apply_idle_stage();
+                return;
+            }
+        }
+
+        self.common
+            .idle_policy
+            .rearm(&self.common.config, &self.common.event_loop_handle);
+    }
+
+    /// Dims, turns off or wakes up all outputs according to the idle policy
+    pub fn apply_idle_stage(&mut self) {
+        let stage = self.common.idle_policy.stage;
+        let outputs = self
+            .common
+            .shell
+            .read()
+            .unwrap()
+            .outputs()
+            .cloned()
+            .collect::<Vec<_>>();
+
+        for output in outputs {
+            let power = OutputPower::get(&output);
+            let dimmed = stage >= IdleStage::Dimmed;
+            if power.dimmed.swap(dimmed, Ordering::SeqCst) != dimmed {
+                self.backend.schedule_render(&output);
+            }
+            if stage == IdleStage::Off && power.is_on() {
+                self.set_output_power(&output, false);
+                self.common.idle_policy.powered_off.push(output);
+            }
+        }
+
+        if stage == IdleStage::Active {
+            for output in std::mem::take(&mut self.common.idle_policy.powered_off) {
+                self.set_output_power(&output, true);
+            }
+        }
+
+        self.common
+            .idle_policy
+            .rearm(&self.common.config, &self.common.event_loop_handle);
+    }
+
+    /// Turns `output` on or off, without changing its configuration
+    pub fn set_output_power(&mut self, output: &Output, on: bool) {
+        if OutputPower::get(output).on.swap(on, Ordering::SeqCst) == on {
+            return;
+        }
+
+        self.backend.set_output_power(output, on);
+        self.common
+            .output_power_state
+            .output_power_changed(output, on);
+        if on {
+            self.backend.schedule_render(output);
+        }
+    }
+}
@@ -407,6 +407,7 @@ impl ResizeSurfaceGrab {
         if resize_state.is_none() {
             return;
         }
+        shell.session.mark_dirty();
 
         let (output, floating_layer) = if let Some((output, set)) = shell
             .workspaces
@@ -6,6 +6,7 @@ use std::{
     time::{Duration, Instant},
 };
 
+use cosmic_comp_config::gaps::GapSettings;
 use cosmic_settings_config::shortcuts::action::ResizeDirection;
 use keyframe::{ease, functions::EaseInOutCubic};
 use smithay::{
@@ -28,7 +29,10 @@ use crate::{
     shell::{
         element::{
             resize_indicator::ResizeIndicator,
-            stack::{CosmicStackRenderElement, MoveResult as StackMoveResult, TAB_HEIGHT},
+            stack::{
+                CosmicStackRenderElement, MoveMode as StackMoveMode, MoveResult as StackMoveResult,
+                TAB_HEIGHT,
+            },
             window::CosmicWindowRenderElement,
             CosmicMapped, CosmicMappedRenderElement, CosmicWindow, MaximizedState,
         },
@@ -58,6 +62,7 @@ pub struct FloatingLayout {
     hovered_stack: Option<(CosmicMapped, Rectangle<i32, Local>)>,
     dirty: AtomicBool,
     pub theme: cosmic::Theme,
+    pub gap_settings: GapSettings,
 }
 
 #[derive(Debug)]
@@ -855,14 +860,7 @@ impl FloatingLayout {
             .space
             .output_geometry(self.space.outputs().next().unwrap())
             .unwrap();
-        let (min_size, max_size) = (mapped.min_size(), mapped.max_size());
-        let min_width = min_size.map(|s| s.w).unwrap_or(360);
-        let min_height = min_size.map(|s| s.h).unwrap_or(240);
-        let max_width = max_size.map(|s| s.w).unwrap_or(i32::max_value());
-        let max_height = max_size.map(|s| s.h).unwrap_or(i32::max_value());
-
-        geo.size.w = min_width.max(geo.size.w).min(max_width);
-        geo.size.h = min_height.max(geo.size.h).min(max_height);
+        geo.size = constrain_size(mapped, geo.size);
         geo = geo.intersection(bounding_box).unwrap();
 
         *mapped.resize_state.lock().unwrap() = Some(ResizeState::Resizing(ResizeData {
@@ -882,6 +880,60 @@ impl FloatingLayout {
         true
     }
 
+    /// Resizes and/or moves the focused window to the given size and position relative to the output
+    pub fn set_geometry(
+        &mut self,
+        focused: &KeyboardFocusTarget,
+        size: Option<Size<i32, Logical>>,
+        position: Option<Point<i32, Local>>,
+    ) -> bool {
+        let Some(toplevel) = focused.toplevel() else {
+            return false;
+        };
+        let Some(mapped) = self
+            .space
+            .elements()
+            .find(|m| m.has_surface(&toplevel, WindowSurfaceType::TOPLEVEL))
+            .cloned()
+        else {
+            return false;
+        };
+        if mapped.is_maximized(true) {
+            return false;
+        }
+
+        let Some(original_geo) = self.space.element_geometry(&mapped) else {
+            return false; // we don't have that window
+        };
+        let output = self.space.outputs().next().unwrap().clone();
+        let bounding_box = self.space.output_geometry(&output).unwrap();
+
+        let mut geo = original_geo.as_local();
+        if let Some(size) = size {
+            geo.size = constrain_size(&mapped, size).as_local();
+        }
+        if let Some(position) = position {
+            geo.loc = position;
+        }
+        let geo = geo
+            .as_logical()
+            .intersection(bounding_box)
+            .unwrap_or(original_geo)
+            .as_local();
+
+        mapped.moved_since_mapped.store(true, Ordering::SeqCst);
+        if geo.loc != original_geo.loc.as_local() {
+            self.space
+                .map_element(mapped.clone(), geo.loc.as_logical(), false);
+        }
+        if geo.size != original_geo.size.as_local() {
+            mapped.set_geometry(geo.to_global(&output));
+            mapped.configure();
+        }
+
+        true
+    }
+
     pub fn toggle_stacking(
         &mut self,
         mapped: &CosmicMapped,
@@ -974,7 +1026,7 @@ impl FloatingLayout {
         theme: &cosmic::Theme,
         element: &CosmicMapped,
     ) -> MoveResult {
-        match element.handle_move(direction) {
+        match element.handle_move(direction, StackMoveMode::Default) {
             StackMoveResult::Handled => MoveResult::Done,
             StackMoveResult::MoveOut(surface, loop_handle) => {
                 let mapped: CosmicMapped =
@@ -1269,6 +1321,7 @@ impl FloatingLayout {
         mut resize_indicator: Option<(ResizeMode, ResizeIndicator)>,
         indicator_thickness: u8,
         alpha: f32,
+        inactive_alpha: f32,
         theme: &cosmic::theme::CosmicTheme,
     ) -> SplitRenderElements<CosmicMappedRenderElement<R>>
     where
@@ -1299,6 +1352,11 @@ impl FloatingLayout {
                 .get(elem)
                 .map(|anim| (*anim.previous_geometry(), alpha * anim.alpha()))
                 .unwrap_or_else(|| (self.space.element_geometry(elem).unwrap().as_local(), alpha));
+            let alpha = if focused == Some(elem) {
+                alpha
+            } else {
+                alpha * inactive_alpha
+            };
 
             let render_location = geometry.loc - elem.geometry().loc.as_local();
             let SplitRenderElements {
@@ -1430,8 +1488,30 @@ impl FloatingLayout {
         elements
     }
 
-    fn gaps(&self) -> (i32, i32) {
+    pub fn gaps(&self) -> (i32, i32) {
         let g = self.theme.cosmic().gaps;
-        (g.0 as i32, g.1 as i32)
+        (
+            self.gap_settings.outer.unwrap_or(g.0) as i32,
+            self.gap_settings.inner.unwrap_or(g.1) as i32,
+        )
+    }
+
+    pub fn active_hint(&self) -> u8 {
+        self.gap_settings
+            .active_hint
+            .unwrap_or(self.theme.cosmic().active_hint) as u8
     }
 }
+
+fn constrain_size(mapped: &CosmicMapped, size: Size<i32, Logical>) -> Size<i32, Logical> {
+    let (min_size, max_size) = (mapped.min_size(), mapped.max_size());
+    let min_width = min_size.map(|s| s.w).unwrap_or(360);
+    let min_height = min_size.map(|s| s.h).unwrap_or(240);
+    let max_width = max_size.map(|s| s.w).unwrap_or(i32::max_value());
+    let max_height = max_size.map(|s| s.h).unwrap_or(i32::max_value());
+
+    Size::from((
+        min_width.max(size.w).min(max_width),
+        min_height.max(size.h).min(max_height),
+    ))
+}
@@ -0,0 +1,94 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use smithay::utils::IsAlive;
+
+use crate::shell::{layout::floating::FloatingLayout, CosmicMapped, CosmicSurface};
+
+/// Windows sent to the scratchpad.
+///
+/// Hidden windows are only tracked here, shown windows are mapped into the
+/// `sticky_layer` of the output they are shown on and stay part of the scratchpad,
+/// until they are made non-sticky.
+#[derive(Debug, Default)]
+pub struct ScratchpadLayer {
+    hidden: Vec<CosmicMapped>,
+    shown: Vec<CosmicMapped>,
+}
+
+impl ScratchpadLayer {
+    /// Hides `window`, it is expected to be unmapped already
+    pub fn hide(&mut self, window: CosmicMapped) {
+        self.shown.retain(|w| w != &window);
+        window.set_minimized(true);
+        self.hidden.push(window);
+    }
+
+    /// Marks `window` as shown, it is expected to be mapped into the `sticky_layer`
+    pub fn show(&mut self, window: CosmicMapped) {
+        self.hidden.retain(|w| w != &window);
+        window.set_minimized(false);
+        self.shown.push(window);
+    }
+
+    /// Stops tracking `window`
+    pub fn remove(&mut self, window: &CosmicMapped) {
+        self.hidden.retain(|w| w != window);
+        self.shown.retain(|w| w != window);
+    }
+
+    /// Takes the first hidden window matching `app_id` out of the scratchpad
+    pub fn take_hidden(&mut self, app_id: Option<&str>) -> Option<CosmicMapped> {
+        let pos = self
+            .hidden
+            .iter()
+            .position(|window| matches_app_id(window, app_id))?;
+        let window = self.hidden.remove(pos);
+        window.set_minimized(false);
+        Some(window)
+    }
+
+    /// Removes `surface` from a hidden window, returns it if it was found
+    pub fn remove_surface<S>(&mut self, surface: &S) -> Option<CosmicSurface>
+    where
+        CosmicSurface: PartialEq<S>,
+    {
+        let pos = self
+            .hidden
+            .iter()
+            .position(|w| w.windows().any(|(s, _)| &s == surface))?;
+        if self.hidden[pos].is_stack() {
+            let stack = self.hidden[pos].stack_ref_mut().unwrap();
+            let idx = stack.surfaces().position(|s| &s == surface);
+            idx.and_then(|idx| stack.remove_idx(idx))
+        } else {
+            Some(self.hidden.remove(pos).active_window())
+        }
+    }
+
+    pub fn hidden(&self) -> impl Iterator<Item = &CosmicMapped> {
+        self.hidden.iter()
+    }
+
+    pub fn find_shown(&self, app_id: Option<&str>) -> Option<&CosmicMapped> {
+        self.shown
+            .iter()
+            .find(|window| matches_app_id(window, app_id))
+    }
+
+    pub fn merge(&mut self, other: ScratchpadLayer) {
+        self.hidden.extend(other.hidden);
+        self.shown.extend(other.shown);
+    }
+
+    pub fn refresh(&mut self, sticky_layer: &FloatingLayout) {
+        self.hidden.retain(|w| w.alive());
+        self.shown
+            .retain(|w| sticky_layer.mapped().any(|mapped| mapped == w));
+    }
+}
+
+fn matches_app_id(window: &CosmicMapped, app_id: Option<&str>) -> bool {
+    app_id.map_or(true, |app_id| {
+        window.windows().any(|(w, _)| w.app_id() == app_id)
+    })
+}
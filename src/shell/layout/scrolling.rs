@@ -0,0 +1,648 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::time::Instant;
+
+use cosmic_comp_config::gaps::GapSettings;
+use cosmic_settings_config::shortcuts::action::{FocusDirection, ResizeDirection};
+use smithay::{
+    backend::renderer::{element::RenderElement, ImportAll, ImportMem, Renderer},
+    desktop::{layer_map_for_output, space::SpaceElement},
+    input::Seat,
+    output::Output,
+    utils::{IsAlive, Point, Rectangle},
+};
+
+use crate::{
+    backend::render::{
+        animations::spring::{Spring, SpringParams},
+        element::AsGlowRenderer,
+        IndicatorShader, Key, SplitRenderElements, Usage,
+    },
+    shell::{
+        element::{
+            resize_indicator::ResizeIndicator,
+            stack::{
+                CosmicStackRenderElement, MoveMode as StackMoveMode, MoveResult as StackMoveResult,
+            },
+            window::CosmicWindowRenderElement,
+            CosmicMapped, CosmicMappedRenderElement, CosmicWindow,
+        },
+        focus::target::{KeyboardFocusTarget, PointerFocusTarget},
+        grabs::ResizeEdge,
+        layout::Orientation,
+        session::{identities, TilingSession},
+        Direction, FocusResult, MoveResult, ResizeMode,
+    },
+    state::State,
+    utils::prelude::*,
+};
+
+/// Width of newly created columns as a fraction of the usable output width
+const DEFAULT_COLUMN_WIDTH: f64 = 0.5;
+const MIN_COLUMN_WIDTH: f64 = 0.1;
+
+#[derive(Debug, Clone)]
+struct Column {
+    windows: Vec<CosmicMapped>,
+    /// Fraction of the usable output width
+    width: f64,
+}
+
+impl Column {
+    fn new(window: CosmicMapped) -> Column {
+        Column {
+            windows: vec![window],
+            width: DEFAULT_COLUMN_WIDTH,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ViewAnimation {
+    start: Instant,
+    from: f64,
+    spring: Spring,
+}
+
+/// Layout placing windows in an infinitely scrolling horizontal strip of columns.
+///
+/// Only a part of the strip is visible at any time, the viewport follows the focused window.
+#[derive(Debug)]
+pub struct ScrollingLayout {
+    output: Output,
+    columns: Vec<Column>,
+    /// Offset of the viewport into the strip, windows are configured for this offset
+    view_offset: i32,
+    view_animation: Option<ViewAnimation>,
+    pub theme: cosmic::Theme,
+    pub gap_settings: GapSettings,
+}
+
+impl ScrollingLayout {
+    pub fn new(theme: cosmic::Theme, output: &Output) -> ScrollingLayout {
+        ScrollingLayout {
+            output: output.clone(),
+            columns: Vec::new(),
+            view_offset: 0,
+            view_animation: None,
+            theme,
+            gap_settings: GapSettings::default(),
+        }
+    }
+
+    pub fn set_output(&mut self, output: &Output) {
+        for column in &self.columns {
+            for mapped in &column.windows {
+                mapped.output_leave(&self.output);
+                mapped.output_enter(output, mapped.bbox());
+            }
+        }
+        self.output = output.clone();
+        self.clamp_view_offset();
+        self.recalculate();
+    }
+
+    /// Maps a new window into its own column.
+    ///
+    /// Without a `direction` the column is inserted next to the last focused window,
+    /// otherwise the window is treated as entering the strip while moving in that direction.
+    pub fn map<'a>(
+        &mut self,
+        window: CosmicMapped,
+        focus_stack: Option<impl Iterator<Item = &'a CosmicMapped> + 'a>,
+        direction: Option<Direction>,
+    ) {
+        window.output_enter(&self.output, window.bbox());
+        window.set_bounds(self.output.geometry().size.as_logical());
+
+        let focused = focus_stack.and_then(|mut stack| stack.find_map(|m| self.position(m)));
+        let idx = match (direction, focused) {
+            (Some(Direction::Right), _) => 0,
+            (Some(Direction::Left), _) | (_, None) => self.columns.len(),
+            (_, Some((col, _))) => col + 1,
+        };
+        self.columns.insert(idx, Column::new(window));
+
+        self.recalculate();
+        self.scroll_to_column(idx);
+    }
+
+    pub fn unmap(&mut self, window: &CosmicMapped) -> bool {
+        let Some((col, row)) = self.position(window) else {
+            return false;
+        };
+
+        self.columns[col].windows.remove(row);
+        if self.columns[col].windows.is_empty() {
+            self.columns.remove(col);
+        }
+
+        self.clamp_view_offset();
+        self.recalculate();
+        true
+    }
+
+    pub fn mapped(&self) -> impl Iterator<Item = (&CosmicMapped, Rectangle<i32, Local>)> {
+        self.geometries().into_iter()
+    }
+
+    pub fn element_geometry(&self, elem: &CosmicMapped) -> Option<Rectangle<i32, Local>> {
+        self.mapped()
+            .find(|(mapped, _)| *mapped == elem)
+            .map(|(_, geo)| geo)
+    }
+
+    pub fn element_under(&self, location: Point<f64, Local>) -> Option<KeyboardFocusTarget> {
+        let location_i32 = location.to_i32_round();
+        self.mapped()
+            .filter(|(mapped, _)| !mapped.is_maximized(false))
+            .filter(|(mapped, geo)| {
+                mapped
+                    .bbox()
+                    .contains((location_i32 - geo.loc).as_logical())
+            })
+            .find(|(mapped, geo)| {
+                mapped.is_in_input_region(
+                    &((location - geo.loc.to_f64()).as_logical() + mapped.geometry().loc.to_f64()),
+                )
+            })
+            .map(|(mapped, _)| mapped.clone().into())
+    }
+
+    pub fn surface_under(
+        &self,
+        location: Point<f64, Local>,
+    ) -> Option<(PointerFocusTarget, Point<f64, Local>)> {
+        let location_i32 = location.to_i32_round();
+        self.mapped()
+            .filter(|(mapped, _)| !mapped.is_maximized(false))
+            .filter(|(mapped, geo)| {
+                mapped
+                    .bbox()
+                    .contains((location_i32 - geo.loc).as_logical())
+            })
+            .find_map(|(mapped, geo)| {
+                mapped
+                    .focus_under(
+                        (location - geo.loc.to_f64()).as_logical() + mapped.geometry().loc.to_f64(),
+                    )
+                    .map(|(target, surface_offset)| {
+                        (
+                            target,
+                            geo.loc.to_f64() - mapped.geometry().loc.as_local().to_f64()
+                                + surface_offset.as_local(),
+                        )
+                    })
+            })
+    }
+
+    /// Scrolls the viewport to make the column of `mapped` fully visible
+    pub fn focus(&mut self, mapped: &CosmicMapped) {
+        if let Some((col, _)) = self.position(mapped) {
+            self.scroll_to_column(col);
+        }
+    }
+
+    pub fn next_focus(&self, direction: FocusDirection, focused: &CosmicMapped) -> FocusResult {
+        let Some((col, row)) = self.position(focused) else {
+            return FocusResult::None;
+        };
+
+        let column = match direction {
+            FocusDirection::Left => col.checked_sub(1),
+            FocusDirection::Right => Some(col + 1),
+            FocusDirection::Up | FocusDirection::Down => Some(col),
+            _ => None,
+        }
+        .and_then(|idx| self.columns.get(idx));
+        let next = column.and_then(|column| match direction {
+            FocusDirection::Up => row.checked_sub(1).and_then(|row| column.windows.get(row)),
+            FocusDirection::Down => column.windows.get(row + 1),
+            _ => column.windows.get(row.min(column.windows.len() - 1)),
+        });
+
+        next.map(|mapped| FocusResult::Some(KeyboardFocusTarget::Element(mapped.clone())))
+            .unwrap_or(FocusResult::None)
+    }
+
+    /// Moves the focused window through the strip.
+    ///
+    /// Windows alone in their column move the whole column, otherwise horizontal movement
+    /// expels the window into a new column next to its current one.
+    pub fn move_current_element(&mut self, direction: Direction, seat: &Seat<State>) -> MoveResult {
+        let Some(KeyboardFocusTarget::Element(focused)) =
+            seat.get_keyboard().unwrap().current_focus()
+        else {
+            return MoveResult::None;
+        };
+        let Some((col, row)) = self.position(&focused) else {
+            return MoveResult::None;
+        };
+
+        // stacks may handle movement internally
+        match focused.handle_move(direction, StackMoveMode::Default) {
+            StackMoveResult::Handled => return MoveResult::Done,
+            StackMoveResult::MoveOut(surface, loop_handle) => {
+                let mapped: CosmicMapped =
+                    CosmicWindow::new(surface, loop_handle, self.theme.clone()).into();
+                mapped.output_enter(&self.output, mapped.bbox());
+                match direction {
+                    Direction::Left => self.columns.insert(col, Column::new(mapped.clone())),
+                    Direction::Right => self.columns.insert(col + 1, Column::new(mapped.clone())),
+                    Direction::Up => self.columns[col].windows.insert(row, mapped.clone()),
+                    Direction::Down => self.columns[col].windows.insert(row + 1, mapped.clone()),
+                }
+                self.recalculate();
+                self.focus(&mapped);
+                return MoveResult::ShiftFocus(mapped.into());
+            }
+            StackMoveResult::Default => {}
+        }
+
+        match direction {
+            Direction::Left | Direction::Right => {
+                if self.columns[col].windows.len() > 1 {
+                    let window = self.columns[col].windows.remove(row);
+                    let idx = if direction == Direction::Left {
+                        col
+                    } else {
+                        col + 1
+                    };
+                    self.columns.insert(idx, Column::new(window));
+                } else {
+                    let other = if direction == Direction::Left {
+                        col.checked_sub(1)
+                    } else {
+                        Some(col + 1).filter(|idx| *idx < self.columns.len())
+                    };
+                    let Some(other) = other else {
+                        return MoveResult::MoveFurther(focused.into());
+                    };
+                    self.columns.swap(col, other);
+                }
+            }
+            Direction::Up | Direction::Down => {
+                let other = if direction == Direction::Up {
+                    row.checked_sub(1)
+                } else {
+                    Some(row + 1).filter(|idx| *idx < self.columns[col].windows.len())
+                };
+                let Some(other) = other else {
+                    return MoveResult::MoveFurther(focused.into());
+                };
+                self.columns[col].windows.swap(row, other);
+            }
+        }
+
+        self.recalculate();
+        self.focus(&focused);
+        MoveResult::Done
+    }
+
+    /// Changes the width of the column of the focused window
+    pub fn resize(
+        &mut self,
+        focused: &KeyboardFocusTarget,
+        direction: ResizeDirection,
+        edges: ResizeEdge,
+        amount: i32,
+    ) -> bool {
+        let KeyboardFocusTarget::Element(mapped) = focused else {
+            return false;
+        };
+        let Some((col, _)) = self.position(mapped) else {
+            return false;
+        };
+        if !edges.intersects(ResizeEdge::LEFT | ResizeEdge::RIGHT) {
+            return true;
+        }
+
+        let delta = amount as f64 / self.usable_area().size.w.max(1) as f64;
+        let column = &mut self.columns[col];
+        column.width = match direction {
+            ResizeDirection::Outwards => column.width + delta,
+            ResizeDirection::Inwards => column.width - delta,
+        }
+        .clamp(MIN_COLUMN_WIDTH, 1.0);
+
+        self.recalculate();
+        self.scroll_to_column(col);
+        true
+    }
+
+    pub fn recalculate(&mut self) {
+        for (mapped, geo) in self.geometries() {
+            if !(mapped.is_fullscreen(true) || mapped.is_maximized(true)) {
+                mapped.set_tiled(true);
+                mapped.set_geometry(geo.to_global(&self.output));
+                mapped.configure();
+            }
+        }
+    }
+
+    /// Describes the columns for session persistence
+    pub fn session_state(&self) -> Option<TilingSession> {
+        if self.columns.is_empty() {
+            return None;
+        }
+
+        let usable_width = self.usable_area().size.w;
+        Some(TilingSession::Group {
+            orientation: Orientation::Vertical,
+            sizes: self
+                .columns
+                .iter()
+                .map(|column| (column.width * usable_width as f64).round() as i32)
+                .collect(),
+            children: self
+                .columns
+                .iter()
+                .map(|column| TilingSession::Group {
+                    orientation: Orientation::Horizontal,
+                    sizes: vec![1; column.windows.len()],
+                    children: column
+                        .windows
+                        .iter()
+                        .map(|mapped| TilingSession::Window(identities(mapped)))
+                        .collect(),
+                })
+                .collect(),
+        })
+    }
+
+    pub fn refresh(&mut self) {
+        let len = self.columns.iter().map(|c| c.windows.len()).sum::<usize>();
+        for column in &mut self.columns {
+            column.windows.retain(|w| w.alive());
+        }
+        self.columns.retain(|c| !c.windows.is_empty());
+        if len != self.columns.iter().map(|c| c.windows.len()).sum::<usize>() {
+            self.clamp_view_offset();
+            self.recalculate();
+        }
+
+        for column in &self.columns {
+            for mapped in &column.windows {
+                mapped.refresh();
+            }
+        }
+    }
+
+    pub fn animations_going(&self) -> bool {
+        self.view_animation.is_some()
+    }
+
+    pub fn update_animation_state(&mut self) {
+        if self
+            .view_animation
+            .is_some_and(|anim| Instant::now().duration_since(anim.start) >= anim.spring.duration())
+        {
+            self.view_animation = None;
+        }
+    }
+
+    #[profiling::function]
+    pub fn render<R>(
+        &self,
+        renderer: &mut R,
+        focused: Option<&CosmicMapped>,
+        mut resize_indicator: Option<(ResizeMode, ResizeIndicator)>,
+        indicator_thickness: u8,
+        alpha: f32,
+        inactive_alpha: f32,
+        theme: &cosmic::theme::CosmicTheme,
+    ) -> SplitRenderElements<CosmicMappedRenderElement<R>>
+    where
+        R: Renderer + ImportAll + ImportMem + AsGlowRenderer,
+        <R as Renderer>::TextureId: Send + Clone + 'static,
+        CosmicMappedRenderElement<R>: RenderElement<R>,
+        CosmicWindowRenderElement<R>: RenderElement<R>,
+        CosmicStackRenderElement<R>: RenderElement<R>,
+    {
+        let output_scale = self.output.current_scale().fractional_scale();
+        let output_geometry =
+            Rectangle::from_loc_and_size((0, 0), self.output.geometry().size.as_local());
+        // windows are configured for the target offset, so we only need to shift them while scrolling
+        let scroll = (self.view_offset as f64 - self.current_view_offset()).round() as i32;
+
+        let mut elements = SplitRenderElements::default();
+
+        for (elem, mut geometry) in self.mapped() {
+            if elem.is_maximized(false) {
+                continue;
+            }
+            geometry.loc.x += scroll;
+            if !geometry.overlaps(output_geometry) {
+                continue;
+            }
+
+            let render_location = geometry.loc - elem.geometry().loc.as_local();
+            let SplitRenderElements {
+                w_elements,
+                p_elements,
+            } = elem.split_render_elements(
+                renderer,
+                render_location
+                    .as_logical()
+                    .to_physical_precise_round(output_scale),
+                output_scale.into(),
+                if focused == Some(elem) {
+                    alpha
+                } else {
+                    alpha * inactive_alpha
+                },
+            );
+
+            if focused == Some(elem) {
+                if let Some((mode, resize)) = resize_indicator.as_mut() {
+                    let mut resize_geometry = geometry.clone();
+                    resize_geometry.loc -= (18, 18).into();
+                    resize_geometry.size += (36, 36).into();
+
+                    resize.resize(resize_geometry.size.as_logical());
+                    resize.output_enter(&self.output, Rectangle::default() /* unused */);
+                    elements.w_elements.extend(
+                        resize
+                            .render_elements::<CosmicWindowRenderElement<R>>(
+                                renderer,
+                                resize_geometry
+                                    .loc
+                                    .as_logical()
+                                    .to_physical_precise_round(output_scale),
+                                output_scale.into(),
+                                alpha * mode.alpha().unwrap_or(1.0),
+                            )
+                            .into_iter()
+                            .map(CosmicMappedRenderElement::Window),
+                    );
+                }
+
+                if indicator_thickness > 0 {
+                    let active_window_hint = crate::theme::active_window_hint(theme);
+                    let element = IndicatorShader::focus_element(
+                        renderer,
+                        Key::Window(Usage::FocusIndicator, elem.key()),
+                        geometry,
+                        indicator_thickness,
+                        output_scale,
+                        alpha,
+                        [
+                            active_window_hint.red,
+                            active_window_hint.green,
+                            active_window_hint.blue,
+                        ],
+                    );
+                    elements.w_elements.push(element.into());
+                }
+            }
+
+            elements.w_elements.extend(w_elements);
+            elements.p_elements.extend(p_elements);
+        }
+
+        elements
+    }
+
+    fn position(&self, mapped: &CosmicMapped) -> Option<(usize, usize)> {
+        self.columns.iter().enumerate().find_map(|(col, column)| {
+            column
+                .windows
+                .iter()
+                .position(|w| w == mapped)
+                .map(|row| (col, row))
+        })
+    }
+
+    fn gaps(&self) -> (i32, i32) {
+        let single_window = self.columns.len() == 1 && self.columns[0].windows.len() == 1;
+        if single_window && self.gap_settings.smart_gaps.unwrap_or(false) {
+            return (0, 0);
+        }
+
+        let g = self.theme.cosmic().gaps;
+        (
+            self.gap_settings.outer.unwrap_or(g.0) as i32,
+            self.gap_settings.inner.unwrap_or(g.1) as i32,
+        )
+    }
+
+    /// Area of the output available to windows at a view offset of zero
+    fn usable_area(&self) -> Rectangle<i32, Local> {
+        let (outer, _) = self.gaps();
+        let mut geo = layer_map_for_output(&self.output)
+            .non_exclusive_zone()
+            .as_local();
+        geo.loc.x += outer;
+        geo.loc.y += outer;
+        geo.size.w -= outer * 2;
+        geo.size.h -= outer * 2;
+        geo
+    }
+
+    /// Start and width of every column in strip coordinates
+    fn column_extents(&self) -> Vec<(i32, i32)> {
+        let (_, inner) = self.gaps();
+        let usable_width = self.usable_area().size.w;
+
+        let mut x = 0;
+        self.columns
+            .iter()
+            .map(|column| {
+                let width =
+                    (((usable_width + inner) as f64 * column.width).round() as i32 - inner).max(1);
+                let extent = (x, width);
+                x += width + inner;
+                extent
+            })
+            .collect()
+    }
+
+    fn geometries(&self) -> Vec<(&CosmicMapped, Rectangle<i32, Local>)> {
+        let (_, inner) = self.gaps();
+        let area = self.usable_area();
+
+        self.columns
+            .iter()
+            .zip(self.column_extents())
+            .flat_map(|(column, (x, width))| {
+                let count = column.windows.len() as i32;
+                let height = (area.size.h - inner * (count - 1)) / count;
+                column.windows.iter().enumerate().map(move |(row, mapped)| {
+                    let y = area.loc.y + (height + inner) * row as i32;
+                    let height = if row as i32 == count - 1 {
+                        area.loc.y + area.size.h - y
+                    } else {
+                        height
+                    };
+                    (
+                        mapped,
+                        Rectangle::from_loc_and_size(
+                            (area.loc.x + x - self.view_offset, y),
+                            (width, height),
+                        ),
+                    )
+                })
+            })
+            .collect()
+    }
+
+    fn current_view_offset(&self) -> f64 {
+        match self.view_animation {
+            Some(anim) => {
+                let progress = anim
+                    .spring
+                    .value_at(Instant::now().duration_since(anim.start));
+                anim.from + (self.view_offset as f64 - anim.from) * progress
+            }
+            None => self.view_offset as f64,
+        }
+    }
+
+    fn set_view_offset(&mut self, offset: i32) {
+        if offset == self.view_offset {
+            return;
+        }
+
+        let from = self.current_view_offset();
+        self.view_offset = offset;
+        self.view_animation = Some(ViewAnimation {
+            start: Instant::now(),
+            from,
+            spring: Spring {
+                from: 0.0,
+                to: 1.0,
+                initial_velocity: 0.0,
+                params: SpringParams::new(1.0, 1000.0, 0.0001),
+            },
+        });
+        self.recalculate();
+    }
+
+    fn scroll_to_column(&mut self, idx: usize) {
+        let Some((x, width)) = self.column_extents().get(idx).copied() else {
+            return;
+        };
+        let usable_width = self.usable_area().size.w;
+
+        let offset = if x < self.view_offset || width >= usable_width {
+            x
+        } else if x + width > self.view_offset + usable_width {
+            x + width - usable_width
+        } else {
+            self.view_offset
+        };
+        self.set_view_offset(offset);
+    }
+
+    /// Avoids scrolling past the end of the strip, e.g. after windows got removed
+    fn clamp_view_offset(&mut self) {
+        let usable_width = self.usable_area().size.w;
+        let strip_width = self
+            .column_extents()
+            .last()
+            .map(|(x, width)| x + width)
+            .unwrap_or(0);
+        let offset = self.view_offset.min(strip_width - usable_width).max(0);
+        self.set_view_offset(offset);
+    }
+}
@@ -222,6 +222,7 @@ impl ResizeForkGrab {
 
         if let Some(output) = self.output.upgrade() {
             let mut shell = data.common.shell.write().unwrap();
+            shell.session.mark_dirty();
             let tiling_layer = &mut shell.active_space_mut(&output).tiling_layer;
             let gaps = tiling_layer.gaps();
 
@@ -9,7 +9,7 @@ use crate::{
         element::{
             resize_indicator::ResizeIndicator,
             stack::{
-                CosmicStackRenderElement, MoveResult as StackMoveResult,
+                CosmicStackRenderElement, MoveMode as StackMoveMode, MoveResult as StackMoveResult,
                 TAB_HEIGHT as STACK_TAB_HEIGHT,
             },
             swap_indicator::SwapIndicator,
@@ -22,6 +22,7 @@ use crate::{
         },
         grabs::ResizeEdge,
         layout::Orientation,
+        session::{identities, TilingSession, WindowIdentity},
         CosmicSurface, Direction, FocusResult, MoveResult, OutputNotMapped, OverviewMode,
         ResizeMode, Trigger,
     },
@@ -38,6 +39,10 @@ use crate::{
     },
 };
 
+use cosmic_comp_config::{
+    gaps::GapSettings,
+    workspace::{LayoutStrategy, MasterStackConfig},
+};
 use cosmic_settings_config::shortcuts::action::{FocusDirection, ResizeDirection};
 use id_tree::{InsertBehavior, MoveBehavior, Node, NodeId, NodeIdError, RemoveBehavior, Tree};
 use keyframe::{
@@ -134,6 +139,103 @@ pub struct TilingLayout {
     swapping_stack_surface_id: Id,
     last_overview_hover: Option<(Option<Instant>, TargetZone)>,
     pub theme: cosmic::Theme,
+    pub gap_settings: GapSettings,
+    strategy: Option<LayoutStrategy>,
+    default_strategy: LayoutStrategy,
+    master_stack: MasterStackConfig,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Gaps {
+    outer: i32,
+    inner: i32,
+    smart: bool,
+}
+
+/// Target shape of a tree, used to apply a `LayoutStrategy`
+#[derive(Debug)]
+enum Arrangement {
+    Window(NodeId),
+    Group(Orientation, Vec<(Arrangement, f64)>),
+}
+
+impl Arrangement {
+    /// Group of weighted `children`, collapsed into its only child or `None` if empty
+    fn group(
+        orientation: Orientation,
+        mut children: Vec<(Arrangement, f64)>,
+    ) -> Option<Arrangement> {
+        match children.len() {
+            0 => None,
+            1 => children.pop().map(|(child, _)| child),
+            _ => Some(Arrangement::Group(orientation, children)),
+        }
+    }
+
+    /// Shape of `windows` in `geo` for `strategy`, `None` for strategies that don't rebuild the tree
+    fn new(
+        strategy: LayoutStrategy,
+        master_stack: MasterStackConfig,
+        windows: &[NodeId],
+        geo: Rectangle<i32, Local>,
+    ) -> Option<Arrangement> {
+        let equal = |windows: &[NodeId]| {
+            windows
+                .iter()
+                .map(|id| (Arrangement::Window(id.clone()), 1.0))
+                .collect::<Vec<_>>()
+        };
+        match strategy {
+            LayoutStrategy::Manual | LayoutStrategy::Monocle => None,
+            LayoutStrategy::Columns => Arrangement::group(Orientation::Vertical, equal(windows)),
+            LayoutStrategy::MasterStack => {
+                let masters = (master_stack.masters as usize).max(1);
+                if windows.len() <= masters {
+                    Arrangement::group(Orientation::Horizontal, equal(windows))
+                } else {
+                    let ratio = master_stack.ratio.clamp(0.1, 0.9) as f64;
+                    Arrangement::group(
+                        Orientation::Vertical,
+                        vec![
+                            (
+                                Arrangement::group(
+                                    Orientation::Horizontal,
+                                    equal(&windows[..masters]),
+                                )?,
+                                ratio,
+                            ),
+                            (
+                                Arrangement::group(
+                                    Orientation::Horizontal,
+                                    equal(&windows[masters..]),
+                                )?,
+                                1.0 - ratio,
+                            ),
+                        ],
+                    )
+                }
+            }
+            LayoutStrategy::Dwindle => {
+                fn dwindle(windows: &[NodeId], orientation: Orientation) -> Option<Arrangement> {
+                    let (first, rest) = windows.split_first()?;
+                    let next = match orientation {
+                        Orientation::Horizontal => Orientation::Vertical,
+                        Orientation::Vertical => Orientation::Horizontal,
+                    };
+                    let mut children = vec![(Arrangement::Window(first.clone()), 1.0)];
+                    children.extend(dwindle(rest, next).map(|rest| (rest, 1.0)));
+                    Arrangement::group(orientation, children)
+                }
+
+                let orientation = if geo.size.w > geo.size.h {
+                    Orientation::Vertical
+                } else {
+                    Orientation::Horizontal
+                };
+                dwindle(windows, orientation)
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -357,6 +459,10 @@ impl TilingLayout {
             swapping_stack_surface_id: Id::new(),
             last_overview_hover: None,
             theme,
+            gap_settings: GapSettings::default(),
+            strategy: None,
+            default_strategy: LayoutStrategy::default(),
+            master_stack: MasterStackConfig::default(),
         }
     }
 
@@ -411,14 +517,23 @@ impl TilingLayout {
             ANIMATION_DURATION
         };
 
+        let window: CosmicMapped = window.into();
         TilingLayout::map_to_tree(
             &mut tree,
-            window,
+            window.clone(),
             &self.output,
             last_active,
             direction,
             minimize_rect,
         );
+        let window_id = window.tiling_node_id.lock().unwrap().clone();
+        TilingLayout::arrange(
+            &self.output,
+            &mut tree,
+            self.strategy(),
+            self.master_stack,
+            window_id.as_ref().zip(direction),
+        );
         let blocker = TilingLayout::update_positions(&self.output, &mut tree, gaps);
         self.queue.push_tree(tree, duration, blocker);
     }
@@ -1388,6 +1503,13 @@ impl TilingLayout {
                 let mut tree = self.queue.trees.back().unwrap().0.copy_clone();
 
                 TilingLayout::unmap_internal(&mut tree, &node_id);
+                TilingLayout::arrange(
+                    &self.output,
+                    &mut tree,
+                    self.strategy(),
+                    self.master_stack,
+                    None,
+                );
 
                 let duration = if minimizing {
                     MINIMIZE_ANIMATION_DURATION
@@ -1467,7 +1589,12 @@ impl TilingLayout {
         }
     }
 
-    pub fn move_current_node(&mut self, direction: Direction, seat: &Seat<State>) -> MoveResult {
+    pub fn move_current_node(
+        &mut self,
+        direction: Direction,
+        seat: &Seat<State>,
+        mode: StackMoveMode,
+    ) -> MoveResult {
         let gaps = self.gaps();
 
         let mut tree = self.queue.trees.back().unwrap().0.copy_clone();
@@ -1481,7 +1608,7 @@ impl TilingLayout {
 
         // stacks may handle movement internally
         if let FocusedNodeData::Window(window) = data.clone() {
-            match window.handle_move(direction) {
+            match window.handle_move(direction, mode) {
                 StackMoveResult::Handled => return MoveResult::Done,
                 StackMoveResult::MoveOut(surface, loop_handle) => {
                     let mapped: CosmicMapped =
@@ -1516,6 +1643,10 @@ impl TilingLayout {
                 StackMoveResult::Default => {} // continue normally
             }
         }
+        if mode != StackMoveMode::Default {
+            // tab movement only applies to stacks
+            return MoveResult::None;
+        }
 
         let mut child_id = node_id.clone();
         // Without a parent to start with, just return
@@ -1790,6 +1921,63 @@ impl TilingLayout {
         }
     }
 
+    /// Merges the focused window or stack into the window next to it in `direction`,
+    /// turning that window into a stack, if necessary.
+    pub fn merge_current_node(&mut self, direction: Direction, seat: &Seat<State>) -> MoveResult {
+        let gaps = self.gaps();
+
+        let mut tree = self.queue.trees.back().unwrap().0.copy_clone();
+
+        let Some(target) = seat.get_keyboard().unwrap().current_focus() else {
+            return MoveResult::None;
+        };
+        let Some((node_id, FocusedNodeData::Window(window))) =
+            TilingLayout::currently_focused_node(&tree, target)
+        else {
+            return MoveResult::None;
+        };
+        let Some(other_id) = TilingLayout::adjacent_window(&tree, &node_id, direction) else {
+            return MoveResult::None;
+        };
+
+        let active = window.active_window();
+        let surfaces = window.windows().map(|(s, _)| s).collect::<Vec<_>>();
+        TilingLayout::unmap_internal(&mut tree, &node_id);
+
+        let mapped = match tree.get_mut(&other_id).unwrap().data_mut() {
+            Data::Mapped { mapped, .. } => {
+                if mapped.is_window() {
+                    mapped.convert_to_stack((&self.output, mapped.bbox()), self.theme.clone());
+                }
+                let stack = mapped.stack_ref_mut().unwrap();
+                for (i, surface) in surfaces.into_iter().enumerate() {
+                    stack.add_window(
+                        surface,
+                        match direction {
+                            Direction::Right | Direction::Down => Some(i),
+                            Direction::Left | Direction::Up => None,
+                        },
+                    );
+                }
+                stack.set_active(&active);
+                mapped.clone()
+            }
+            _ => unreachable!(),
+        };
+
+        TilingLayout::arrange(
+            &self.output,
+            &mut tree,
+            self.strategy(),
+            self.master_stack,
+            None,
+        );
+        let blocker = TilingLayout::update_positions(&self.output, &mut tree, gaps);
+        self.queue.push_tree(tree, ANIMATION_DURATION, blocker);
+
+        MoveResult::ShiftFocus(mapped.into())
+    }
+
     pub fn next_focus<'a>(
         &self,
         direction: FocusDirection,
@@ -2220,15 +2408,13 @@ impl TilingLayout {
     pub fn toggle_stacking_focused(
         &mut self,
         seat: &Seat<State>,
-        mut focus_stack: FocusStackMut,
+        focus_stack: FocusStackMut,
     ) -> Option<KeyboardFocusTarget> {
-        let gaps = self.gaps();
-
         let Some(target) = seat.get_keyboard().unwrap().current_focus() else {
             return None;
         };
 
-        let mut tree = self.queue.trees.back().unwrap().0.copy_clone();
+        let tree = self.queue.trees.back().unwrap().0.copy_clone();
         if let Some((last_active, last_active_data)) =
             TilingLayout::currently_focused_node(&tree, target)
         {
@@ -2237,72 +2423,259 @@ impl TilingLayout {
                     return self.toggle_stacking(&mapped, focus_stack);
                 }
                 FocusedNodeData::Group(_, _) => {
-                    let mut handle = None;
-                    let surfaces = tree
-                        .traverse_pre_order(&last_active)
-                        .unwrap()
-                        .flat_map(|node| match node.data() {
-                            Data::Mapped { mapped, .. } => {
-                                if handle.is_none() {
-                                    handle = Some(mapped.loop_handle());
-                                }
-                                Some(mapped.windows().map(|(s, _)| s))
-                            }
-                            _ => None,
-                        })
-                        .flatten()
-                        .collect::<Vec<_>>();
+                    return self.stack_group(tree, &last_active, focus_stack);
+                }
+            }
+        }
 
-                    if surfaces.is_empty() {
-                        return None;
-                    }
-                    let handle = handle.unwrap();
-                    let stack = CosmicStack::new(surfaces.into_iter(), handle, self.theme.clone());
+        None
+    }
 
-                    for child in tree
-                        .children_ids(&last_active)
-                        .unwrap()
-                        .cloned()
-                        .collect::<Vec<_>>()
-                        .into_iter()
-                    {
-                        tree.remove_node(child, RemoveBehavior::DropChildren)
-                            .unwrap();
+    /// Replaces the group `group_id` with a single stack containing all of its windows
+    fn stack_group(
+        &mut self,
+        mut tree: Tree<Data>,
+        group_id: &NodeId,
+        mut focus_stack: FocusStackMut,
+    ) -> Option<KeyboardFocusTarget> {
+        let gaps = self.gaps();
+
+        let mut handle = None;
+        let surfaces = tree
+            .traverse_pre_order(group_id)
+            .unwrap()
+            .flat_map(|node| match node.data() {
+                Data::Mapped { mapped, .. } => {
+                    if handle.is_none() {
+                        handle = Some(mapped.loop_handle());
                     }
-                    let data = tree.get_mut(&last_active).unwrap().data_mut();
+                    Some(mapped.windows().map(|(s, _)| s))
+                }
+                _ => None,
+            })
+            .flatten()
+            .collect::<Vec<_>>();
 
-                    let geo = *data.geometry();
-                    stack.output_enter(&self.output, stack.bbox());
-                    stack.set_activate(true);
-                    stack.active().send_configure();
-                    stack.refresh();
+        if surfaces.is_empty() {
+            return None;
+        }
+        let handle = handle.unwrap();
+        let stack = CosmicStack::new(surfaces.into_iter(), handle, self.theme.clone());
 
-                    let mapped = CosmicMapped::from(stack);
-                    *mapped.last_geometry.lock().unwrap() = Some(geo);
-                    *mapped.tiling_node_id.lock().unwrap() = Some(last_active);
-                    focus_stack.append(&mapped);
-                    *data = Data::Mapped {
-                        mapped: mapped.clone(),
-                        last_geometry: geo,
-                        minimize_rect: None,
-                    };
+        for child in tree
+            .children_ids(group_id)
+            .unwrap()
+            .cloned()
+            .collect::<Vec<_>>()
+            .into_iter()
+        {
+            tree.remove_node(child, RemoveBehavior::DropChildren)
+                .unwrap();
+        }
+        let data = tree.get_mut(group_id).unwrap().data_mut();
+
+        let geo = *data.geometry();
+        stack.output_enter(&self.output, stack.bbox());
+        stack.set_activate(true);
+        stack.active().send_configure();
+        stack.refresh();
+
+        let mapped = CosmicMapped::from(stack);
+        *mapped.last_geometry.lock().unwrap() = Some(geo);
+        *mapped.tiling_node_id.lock().unwrap() = Some(group_id.clone());
+        focus_stack.append(&mapped);
+        *data = Data::Mapped {
+            mapped: mapped.clone(),
+            last_geometry: geo,
+            minimize_rect: None,
+        };
 
-                    let blocker = TilingLayout::update_positions(&self.output, &mut tree, gaps);
-                    self.queue.push_tree(tree, ANIMATION_DURATION, blocker);
+        let blocker = TilingLayout::update_positions(&self.output, &mut tree, gaps);
+        self.queue.push_tree(tree, ANIMATION_DURATION, blocker);
 
-                    return Some(KeyboardFocusTarget::Element(mapped));
-                }
+        Some(KeyboardFocusTarget::Element(mapped))
+    }
+
+    pub fn recalculate(&mut self) {
+        let gaps = self.gaps();
+
+        let mut tree = self.queue.trees.back().unwrap().0.copy_clone();
+        let blocker = TilingLayout::update_positions(&self.output, &mut tree, gaps);
+        self.queue.push_tree(tree, ANIMATION_DURATION, blocker);
+    }
+
+    pub fn strategy(&self) -> LayoutStrategy {
+        self.strategy.unwrap_or(self.default_strategy)
+    }
+
+    /// Overrides the default layout strategy and rearranges all windows accordingly
+    pub fn set_strategy(
+        &mut self,
+        strategy: LayoutStrategy,
+        focus_stack: FocusStackMut,
+    ) -> Option<KeyboardFocusTarget> {
+        self.strategy = Some(strategy);
+
+        let mut tree = self.queue.trees.back().unwrap().0.copy_clone();
+        if strategy == LayoutStrategy::Monocle {
+            let root_id = tree.root_node_id()?.clone();
+            let has_placeholder = tree
+                .traverse_pre_order(&root_id)
+                .unwrap()
+                .any(|node| node.data().is_placeholder());
+            if has_placeholder {
+                return None;
             }
+            let lone_window = match tree.get(&root_id).unwrap().data() {
+                Data::Mapped { mapped, .. } if mapped.is_window() => Some(mapped.clone()),
+                _ => None,
+            };
+            return if tree.get(&root_id).unwrap().data().is_group() {
+                self.stack_group(tree, &root_id, focus_stack)
+            } else if let Some(mapped) = lone_window {
+                // like new windows joining the stack, a lone window becomes one
+                self.toggle_stacking(&mapped, focus_stack)
+            } else {
+                None
+            };
         }
 
+        if TilingLayout::arrange(&self.output, &mut tree, strategy, self.master_stack, None) {
+            let blocker = TilingLayout::update_positions(&self.output, &mut tree, self.gaps());
+            self.queue.push_tree(tree, ANIMATION_DURATION, blocker);
+        }
         None
     }
 
-    pub fn recalculate(&mut self) {
-        let gaps = self.gaps();
+    pub fn set_default_strategy(
+        &mut self,
+        strategy: LayoutStrategy,
+        master_stack: MasterStackConfig,
+    ) {
+        let previous = (self.strategy(), self.master_stack);
+        self.default_strategy = strategy;
+        self.master_stack = master_stack;
+
+        if previous != (self.strategy(), self.master_stack) {
+            let mut tree = self.queue.trees.back().unwrap().0.copy_clone();
+            if TilingLayout::arrange(&self.output, &mut tree, self.strategy(), master_stack, None) {
+                let blocker = TilingLayout::update_positions(&self.output, &mut tree, self.gaps());
+                self.queue.push_tree(tree, ANIMATION_DURATION, blocker);
+            }
+        }
+    }
+
+    /// Describes the current tree for session persistence
+    pub fn session_state(&self) -> Option<TilingSession> {
+        fn node_state(tree: &Tree<Data>, id: &NodeId) -> Option<TilingSession> {
+            match tree.get(id).unwrap().data() {
+                Data::Group {
+                    orientation, sizes, ..
+                } => {
+                    let (children, sizes) = tree
+                        .children_ids(id)
+                        .unwrap()
+                        .zip(sizes.iter())
+                        .filter_map(|(child, size)| node_state(tree, child).map(|s| (s, *size)))
+                        .unzip();
+                    Some(TilingSession::Group {
+                        orientation: *orientation,
+                        sizes,
+                        children,
+                    })
+                }
+                Data::Mapped { mapped, .. } => Some(TilingSession::Window(identities(mapped))),
+                Data::Placeholder { .. } => None,
+            }
+        }
+
+        let tree = &self.queue.trees.back().unwrap().0;
+        tree.root_node_id().and_then(|root| node_state(tree, root))
+    }
+
+    /// Rearranges the tree to match a previous session.
+    ///
+    /// `windows` maps the identities of the session to windows that reappeared,
+    /// windows not part of the session are kept after the restored ones.
+    pub fn restore(
+        &mut self,
+        session: &TilingSession,
+        windows: &[(WindowIdentity, CosmicSurface)],
+    ) {
+        if self.strategy() != LayoutStrategy::Manual {
+            return;
+        }
 
         let mut tree = self.queue.trees.back().unwrap().0.copy_clone();
-        let blocker = TilingLayout::update_positions(&self.output, &mut tree, gaps);
+        let Some(root_id) = tree.root_node_id().cloned() else {
+            return;
+        };
+        let mut nodes = Vec::new();
+        for id in tree.traverse_pre_order_ids(&root_id).unwrap() {
+            match tree.get(&id).unwrap().data() {
+                Data::Mapped { mapped, .. } => nodes.push((mapped.clone(), id)),
+                // don't interfere with ongoing move operations
+                Data::Placeholder { .. } => return,
+                Data::Group { .. } => {}
+            }
+        }
+        let mut windows = windows.iter().collect::<Vec<_>>();
+
+        fn arrangement(
+            session: &TilingSession,
+            windows: &mut Vec<&(WindowIdentity, CosmicSurface)>,
+            nodes: &mut Vec<(CosmicMapped, NodeId)>,
+        ) -> Option<Arrangement> {
+            match session {
+                TilingSession::Group {
+                    orientation,
+                    sizes,
+                    children,
+                } => {
+                    let children = children
+                        .iter()
+                        .zip(sizes.iter())
+                        .filter_map(|(child, size)| {
+                            arrangement(child, windows, nodes).map(|a| (a, *size as f64))
+                        })
+                        .collect::<Vec<_>>();
+                    Arrangement::group(*orientation, children)
+                }
+                TilingSession::Window(identities) => {
+                    let pos = windows
+                        .iter()
+                        .position(|(identity, _)| identities.contains(identity))?;
+                    let (_, surface) = windows.remove(pos);
+                    let idx = nodes
+                        .iter()
+                        .position(|(mapped, _)| mapped.windows().any(|(w, _)| &w == surface))?;
+                    let (_, node_id) = nodes.remove(idx);
+                    Some(Arrangement::Window(node_id))
+                }
+            }
+        }
+
+        let Some(restored) = arrangement(session, &mut windows, &mut nodes) else {
+            return;
+        };
+        let mut children = vec![(restored, 1.0)];
+        children.extend(
+            nodes
+                .into_iter()
+                .map(|(_, id)| (Arrangement::Window(id), 1.0)),
+        );
+        let Some(arrangement) = Arrangement::group(Orientation::Vertical, children) else {
+            return;
+        };
+
+        let geo = layer_map_for_output(&self.output)
+            .non_exclusive_zone()
+            .as_local();
+        if !TilingLayout::apply_arrangement(&mut tree, &root_id, arrangement, geo) {
+            return;
+        }
+
+        let blocker = TilingLayout::update_positions(&self.output, &mut tree, self.gaps());
         self.queue.push_tree(tree, ANIMATION_DURATION, blocker);
     }
 
@@ -2493,21 +2866,7 @@ impl TilingLayout {
         let gaps = self.gaps();
 
         let mut tree = self.queue.trees.back().unwrap().0.copy_clone();
-        let Some(root_id) = tree.root_node_id() else {
-            return false;
-        };
-        let Some(mut node_id) = (match TilingLayout::currently_focused_node(&tree, focused.clone())
-        {
-            Some((_id, FocusedNodeData::Window(mapped))) =>
-            // we need to make sure the id belongs to this tree..
-            {
-                tree.traverse_pre_order_ids(root_id)
-                    .unwrap()
-                    .find(|id| tree.get(id).unwrap().data().is_mapped(Some(&mapped)))
-            }
-            Some((id, FocusedNodeData::Group(_, _))) => Some(id), // in this case the workspace handle was already matched, so the id is to be trusted
-            _ => None,
-        }) else {
+        let Some(mut node_id) = TilingLayout::focused_node_id(&tree, focused) else {
             return false;
         };
 
@@ -2581,6 +2940,106 @@ impl TilingLayout {
         true
     }
 
+    /// Makes the focused node take up `ratio` of its parent group,
+    /// scaling its siblings to fill the remaining space.
+    pub fn resize_to_ratio(&mut self, focused: &KeyboardFocusTarget, ratio: f64) -> bool {
+        let gaps = self.gaps();
+
+        let mut tree = self.queue.trees.back().unwrap().0.copy_clone();
+        let Some(node_id) = TilingLayout::focused_node_id(&tree, focused) else {
+            return false;
+        };
+        let Some(group_id) = tree.get(&node_id).unwrap().parent().cloned() else {
+            return false;
+        };
+        let node_idx = tree
+            .children_ids(&group_id)
+            .unwrap()
+            .position(|id| id == &node_id)
+            .unwrap();
+
+        match tree.get_mut(&group_id).unwrap().data_mut() {
+            Data::Group {
+                sizes, orientation, ..
+            } => {
+                let min_size = match orientation {
+                    Orientation::Vertical => 360,
+                    Orientation::Horizontal => 240,
+                };
+                let total = sizes.iter().sum::<i32>();
+                let others = total - sizes[node_idx];
+                let max_size = total - min_size * (sizes.len() as i32 - 1);
+                if others <= 0 || max_size < min_size {
+                    return true;
+                }
+
+                let new_size = ((total as f64 * ratio).round() as i32).clamp(min_size, max_size);
+                let remaining = total - new_size;
+                let mut distributed = 0;
+                let last_other = if node_idx == sizes.len() - 1 {
+                    node_idx - 1
+                } else {
+                    sizes.len() - 1
+                };
+                for (idx, size) in sizes.iter_mut().enumerate() {
+                    if idx == node_idx {
+                        *size = new_size;
+                    } else if idx == last_other {
+                        *size = remaining - distributed;
+                    } else {
+                        *size = (*size as i64 * remaining as i64 / others as i64) as i32;
+                        distributed += *size;
+                    }
+                }
+            }
+            _ => unreachable!(),
+        }
+
+        let blocker = TilingLayout::update_positions(&self.output, &mut tree, gaps);
+        self.queue.push_tree(tree, ANIMATION_DURATION, blocker);
+
+        true
+    }
+
+    /// Evenly distributes the space of every group in the focused subtree.
+    ///
+    /// If a window is focused, its parent group and all groups below are balanced.
+    pub fn balance(&mut self, focused: &KeyboardFocusTarget) -> bool {
+        let gaps = self.gaps();
+
+        let mut tree = self.queue.trees.back().unwrap().0.copy_clone();
+        let Some(node_id) = TilingLayout::focused_node_id(&tree, focused) else {
+            return false;
+        };
+        let subtree = if tree.get(&node_id).unwrap().data().is_group() {
+            node_id
+        } else if let Some(parent) = tree.get(&node_id).unwrap().parent().cloned() {
+            parent
+        } else {
+            return true;
+        };
+
+        let groups = tree
+            .traverse_pre_order_ids(&subtree)
+            .unwrap()
+            .filter(|id| tree.get(id).unwrap().data().is_group())
+            .collect::<Vec<_>>();
+        for group_id in groups {
+            if let Data::Group { sizes, .. } = tree.get_mut(&group_id).unwrap().data_mut() {
+                let total = sizes.iter().sum::<i32>();
+                let len = sizes.len() as i32;
+                for (idx, size) in sizes.iter_mut().enumerate() {
+                    *size = total / len + if (idx as i32) < total % len { 1 } else { 0 };
+                }
+            }
+        }
+
+        let blocker = TilingLayout::update_positions(&self.output, &mut tree, gaps);
+        self.queue.push_tree(tree, ANIMATION_DURATION, blocker);
+
+        true
+    }
+
     pub fn stacking_indicator(&self) -> Option<Rectangle<i32, Local>> {
         if let Some(TargetZone::WindowStack(_, geo)) =
             self.last_overview_hover.as_ref().map(|(_, zone)| zone)
@@ -2785,6 +3244,22 @@ impl TilingLayout {
             )
     }
 
+    /// Like `currently_focused_node`, but makes sure a focused window actually belongs to `tree`
+    fn focused_node_id(tree: &Tree<Data>, focused: &KeyboardFocusTarget) -> Option<NodeId> {
+        let root_id = tree.root_node_id()?;
+        match TilingLayout::currently_focused_node(tree, focused.clone()) {
+            Some((_id, FocusedNodeData::Window(mapped))) =>
+            // we need to make sure the id belongs to this tree..
+            {
+                tree.traverse_pre_order_ids(root_id)
+                    .unwrap()
+                    .find(|id| tree.get(id).unwrap().data().is_mapped(Some(&mapped)))
+            }
+            Some((id, FocusedNodeData::Group(_, _))) => Some(id), // in this case the workspace handle was already matched, so the id is to be trusted
+            _ => None,
+        }
+    }
+
     fn currently_focused_node(
         tree: &Tree<Data>,
         mut target: KeyboardFocusTarget,
@@ -2877,6 +3352,193 @@ impl TilingLayout {
         Ok(group_id)
     }
 
+    /// Rebuilds all groups of `tree` according to `strategy`, keeping the order of the windows.
+    ///
+    /// A window `mapped` from a direction is placed on the side it entered from.
+    /// Returns `false` if the tree was left untouched.
+    fn arrange(
+        output: &Output,
+        tree: &mut Tree<Data>,
+        strategy: LayoutStrategy,
+        master_stack: MasterStackConfig,
+        mapped: Option<(&NodeId, Direction)>,
+    ) -> bool {
+        let Some(root_id) = tree.root_node_id() else {
+            return false;
+        };
+        if tree
+            .traverse_pre_order(root_id)
+            .unwrap()
+            .any(|node| node.data().is_placeholder())
+        {
+            // don't interfere with ongoing move operations
+            return false;
+        }
+
+        let geo = layer_map_for_output(output).non_exclusive_zone().as_local();
+        TilingLayout::arrange_tree(tree, strategy, master_stack, mapped, geo)
+    }
+
+    /// Same as `TilingLayout::arrange` for an area of `geo`, treating placeholders as windows
+    fn arrange_tree(
+        tree: &mut Tree<Data>,
+        strategy: LayoutStrategy,
+        master_stack: MasterStackConfig,
+        mapped: Option<(&NodeId, Direction)>,
+        geo: Rectangle<i32, Local>,
+    ) -> bool {
+        let Some(root_id) = tree.root_node_id().cloned() else {
+            return false;
+        };
+        let mut windows = tree
+            .traverse_pre_order_ids(&root_id)
+            .unwrap()
+            .filter(|id| !tree.get(id).unwrap().data().is_group())
+            .collect::<Vec<_>>();
+
+        if let Some((node_id, direction)) = mapped {
+            if let Some(pos) = windows.iter().position(|id| id == node_id) {
+                let id = windows.remove(pos);
+                match direction {
+                    // moving right or down enters the workspace from the left or top
+                    Direction::Right | Direction::Down => windows.insert(0, id),
+                    Direction::Left | Direction::Up => windows.push(id),
+                }
+            }
+        }
+
+        let Some(arrangement) = Arrangement::new(strategy, master_stack, &windows, geo) else {
+            return false;
+        };
+        TilingLayout::apply_arrangement(tree, &root_id, arrangement, geo)
+    }
+
+    /// Replaces the tree below `root_id` with `arrangement`, which has to contain all of its windows.
+    ///
+    /// Returns `false` if the tree was left untouched.
+    fn apply_arrangement(
+        tree: &mut Tree<Data>,
+        root_id: &NodeId,
+        arrangement: Arrangement,
+        geo: Rectangle<i32, Local>,
+    ) -> bool {
+        let root_is_group = tree.get(root_id).unwrap().data().is_group();
+        match arrangement {
+            Arrangement::Window(node_id) if &node_id == root_id => return false,
+            // the old root ends up as the last child of the window
+            Arrangement::Window(node_id) => tree.move_node(&node_id, MoveBehavior::ToRoot).unwrap(),
+            // the old root ends up as a child of the new one
+            arrangement => TilingLayout::insert_arrangement(tree, None, arrangement, geo),
+        }
+        if root_is_group {
+            // all windows have been moved out, so only old groups are dropped
+            tree.remove_node(root_id.clone(), RemoveBehavior::DropChildren)
+                .unwrap();
+        }
+
+        true
+    }
+
+    fn insert_arrangement(
+        tree: &mut Tree<Data>,
+        parent: Option<&NodeId>,
+        arrangement: Arrangement,
+        geo: Rectangle<i32, Local>,
+    ) {
+        match arrangement {
+            Arrangement::Window(node_id) => {
+                let parent = parent.expect("Window without group?");
+                tree.move_node(&node_id, MoveBehavior::ToParent(parent))
+                    .unwrap();
+                tree.make_last_sibling(&node_id).unwrap();
+            }
+            Arrangement::Group(orientation, children) => {
+                let length = match orientation {
+                    Orientation::Vertical => geo.size.w,
+                    Orientation::Horizontal => geo.size.h,
+                };
+                let total: f64 = children.iter().map(|(_, weight)| weight).sum();
+                let mut sizes = children
+                    .iter()
+                    .map(|(_, weight)| (length as f64 * weight / total).round() as i32)
+                    .collect::<Vec<_>>();
+                let used: i32 = sizes.iter().sum();
+                *sizes.last_mut().unwrap() += length - used;
+
+                let group_id = tree
+                    .insert(
+                        Node::new(Data::Group {
+                            orientation,
+                            sizes: sizes.clone(),
+                            last_geometry: geo,
+                            alive: Arc::new(()),
+                            pill_indicator: None,
+                        }),
+                        match parent {
+                            Some(parent) => InsertBehavior::UnderNode(parent),
+                            None => InsertBehavior::AsRoot,
+                        },
+                    )
+                    .unwrap();
+                if parent.is_some() {
+                    tree.make_last_sibling(&group_id).unwrap();
+                }
+
+                let mut offset = 0;
+                for ((child, _), size) in children.into_iter().zip(sizes) {
+                    let child_geo = match orientation {
+                        Orientation::Vertical => Rectangle::from_loc_and_size(
+                            (geo.loc.x + offset, geo.loc.y),
+                            (size, geo.size.h),
+                        ),
+                        Orientation::Horizontal => Rectangle::from_loc_and_size(
+                            (geo.loc.x, geo.loc.y + offset),
+                            (geo.size.w, size),
+                        ),
+                    };
+                    offset += size;
+                    TilingLayout::insert_arrangement(tree, Some(&group_id), child, child_geo);
+                }
+            }
+        }
+    }
+
+    /// Finds the window closest to `node` in `direction`
+    fn adjacent_window(tree: &Tree<Data>, node: &NodeId, direction: Direction) -> Option<NodeId> {
+        let geo = *tree.get(node).ok()?.data().geometry();
+        let root = tree.root_node_id()?;
+
+        tree.traverse_pre_order_ids(root)
+            .unwrap()
+            .filter(|id| id != node && tree.get(id).unwrap().data().is_mapped(None))
+            .filter_map(|id| {
+                let other = *tree.get(&id).unwrap().data().geometry();
+                let (distance, overlaps) = match direction {
+                    Direction::Left | Direction::Right => (
+                        if direction == Direction::Left {
+                            geo.loc.x - (other.loc.x + other.size.w)
+                        } else {
+                            other.loc.x - (geo.loc.x + geo.size.w)
+                        },
+                        other.loc.y < geo.loc.y + geo.size.h
+                            && geo.loc.y < other.loc.y + other.size.h,
+                    ),
+                    Direction::Up | Direction::Down => (
+                        if direction == Direction::Up {
+                            geo.loc.y - (other.loc.y + other.size.h)
+                        } else {
+                            other.loc.y - (geo.loc.y + geo.size.h)
+                        },
+                        other.loc.x < geo.loc.x + geo.size.w
+                            && geo.loc.x < other.loc.x + other.size.w,
+                    ),
+                };
+                (overlaps && distance >= 0).then_some((id, distance))
+            })
+            .min_by_key(|(_, distance)| *distance)
+            .map(|(id, _)| id)
+    }
+
     fn has_adjacent_node(tree: &Tree<Data>, node: &NodeId, direction: Direction) -> bool {
         let mut search_node = node;
         match tree.ancestor_ids(node) {
@@ -2939,12 +3601,22 @@ impl TilingLayout {
     fn update_positions(
         output: &Output,
         tree: &mut Tree<Data>,
-        gaps: (i32, i32),
+        gaps: Gaps,
     ) -> Option<TilingBlocker> {
         if let Some(root_id) = tree.root_node_id() {
             let mut configures = Vec::new();
 
-            let (outer, inner) = gaps;
+            let single_window = tree
+                .traverse_pre_order(root_id)
+                .unwrap()
+                .filter(|node| !node.data().is_group())
+                .count()
+                == 1;
+            let (outer, inner) = if gaps.smart && single_window {
+                (0, 0)
+            } else {
+                (gaps.outer, gaps.inner)
+            };
             let mut geo = layer_map_for_output(&output)
                 .non_exclusive_zone()
                 .as_local();
@@ -3840,6 +4512,7 @@ impl TilingLayout {
         overview: (OverviewMode, Option<(SwapIndicator, Option<&Tree<Data>>)>),
         resize_indicator: Option<(ResizeMode, ResizeIndicator)>,
         indicator_thickness: u8,
+        inactive_alpha: f32,
         theme: &cosmic::theme::CosmicTheme,
     ) -> Result<SplitRenderElements<CosmicMappedRenderElement<R>>, OutputNotMapped>
     where
@@ -3971,6 +4644,7 @@ impl TilingLayout {
             },
             overview,
             resize_indicator,
+            inactive_alpha,
             swap_desc.clone(),
             &self.swapping_stack_surface_id,
             &self.placeholder_id,
@@ -3985,9 +4659,13 @@ impl TilingLayout {
         Ok(elements)
     }
 
-    fn gaps(&self) -> (i32, i32) {
+    fn gaps(&self) -> Gaps {
         let g = self.theme.cosmic().gaps;
-        (g.0 as i32, g.1 as i32)
+        Gaps {
+            outer: self.gap_settings.outer.unwrap_or(g.0) as i32,
+            inner: self.gap_settings.inner.unwrap_or(g.1) as i32,
+            smart: self.gap_settings.smart_gaps.unwrap_or(false),
+        }
     }
 }
 
@@ -4836,6 +5514,7 @@ fn render_new_tree<R>(
     indicator_thickness: u8,
     overview: (OverviewMode, Option<(SwapIndicator, Option<&Tree<Data>>)>),
     mut resize_indicator: Option<(ResizeMode, ResizeIndicator)>,
+    inactive_alpha: f32,
     swap_desc: Option<NodeDesc>,
     swapping_stack_surface_id: &Id,
     placeholder_id: &Id,
@@ -5229,6 +5908,13 @@ where
 
             if let Data::Mapped { mapped, .. } = data {
                 let elem_geometry = mapped.geometry().to_physical_precise_round(output_scale);
+                // windows inside a focused group count as focused as well
+                let is_focused = focused.as_ref().is_some_and(|focused| {
+                    focused == &node_id
+                        || target_tree
+                            .ancestor_ids(&node_id)
+                            .is_ok_and(|mut ancestors| ancestors.any(|id| id == focused))
+                });
                 let SplitRenderElements {
                     mut w_elements,
                     p_elements,
@@ -5238,7 +5924,11 @@ where
                     geo.loc.as_logical().to_physical_precise_round(output_scale)
                         - elem_geometry.loc,
                     Scale::from(output_scale),
-                    alpha,
+                    if is_focused {
+                        alpha
+                    } else {
+                        alpha * inactive_alpha
+                    },
                 );
                 if swap_desc
                     .as_ref()
@@ -5391,3 +6081,249 @@ fn scale_to_center<C>(
         )
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{Arrangement, Data, Direction, Orientation, TilingLayout};
+    use crate::utils::geometry::Local;
+    use cosmic_comp_config::workspace::{LayoutStrategy, MasterStackConfig};
+    use id_tree::{InsertBehavior, Node, NodeId, Tree};
+    use smithay::utils::Rectangle;
+
+    const STRATEGIES: [LayoutStrategy; 3] = [
+        LayoutStrategy::Columns,
+        LayoutStrategy::MasterStack,
+        LayoutStrategy::Dwindle,
+    ];
+
+    fn geo() -> Rectangle<i32, Local> {
+        Rectangle::from_loc_and_size((0, 0), (1920, 1080))
+    }
+
+    /// Tree of `n` windows, each one mapped next to the previous root like `map_to_tree` does
+    fn tree(n: usize) -> (Tree<Data>, Vec<NodeId>) {
+        let mut tree = Tree::new();
+        let mut windows = Vec::new();
+        for _ in 0..n {
+            let root_id = tree.root_node_id().cloned();
+            let window = Node::new(Data::Placeholder {
+                last_geometry: Rectangle::from_loc_and_size((0, 0), (100, 100)),
+                initial_placeholder: false,
+            });
+            let id = tree.insert(window, InsertBehavior::AsRoot).unwrap();
+            if let Some(root_id) = root_id {
+                TilingLayout::new_group(&mut tree, &root_id, &id, Orientation::Vertical).unwrap();
+            }
+            windows.push(id);
+        }
+        (tree, windows)
+    }
+
+    /// Describes the tree like `V(0,H(1,2))`, using the index of every window in `windows`
+    fn shape(tree: &Tree<Data>, windows: &[NodeId]) -> String {
+        fn node_shape(tree: &Tree<Data>, windows: &[NodeId], id: &NodeId) -> String {
+            let node = tree.get(id).unwrap();
+            match node.data() {
+                Data::Group { orientation, .. } => format!(
+                    "{}({})",
+                    match orientation {
+                        Orientation::Horizontal => "H",
+                        Orientation::Vertical => "V",
+                    },
+                    node.children()
+                        .iter()
+                        .map(|child| node_shape(tree, windows, child))
+                        .collect::<Vec<_>>()
+                        .join(",")
+                ),
+                _ => windows.iter().position(|w| w == id).unwrap().to_string(),
+            }
+        }
+
+        tree.root_node_id()
+            .map(|root| node_shape(tree, windows, root))
+            .unwrap_or_default()
+    }
+
+    /// Every group needs at least two children and a size for each of them
+    fn assert_valid(tree: &Tree<Data>) {
+        let Some(root) = tree.root_node_id() else {
+            return;
+        };
+        for id in tree.traverse_pre_order_ids(root).unwrap() {
+            let node = tree.get(&id).unwrap();
+            if node.data().is_group() {
+                assert!(node.children().len() >= 2, "group with a single child");
+                assert_eq!(node.data().len(), node.children().len());
+            } else {
+                assert!(node.children().is_empty());
+            }
+        }
+    }
+
+    fn arranged(n: usize, strategy: LayoutStrategy, master_stack: MasterStackConfig) -> String {
+        let (mut tree, windows) = tree(n);
+        TilingLayout::arrange_tree(&mut tree, strategy, master_stack, None, geo());
+        assert_valid(&tree);
+        shape(&tree, &windows)
+    }
+
+    #[test]
+    fn test_group_collapse() {
+        let (_, windows) = tree(2);
+        let window = |i: usize| (Arrangement::Window(windows[i].clone()), 1.0);
+
+        assert!(Arrangement::group(Orientation::Vertical, Vec::new()).is_none());
+        assert!(matches!(
+            Arrangement::group(Orientation::Vertical, vec![window(0)]),
+            Some(Arrangement::Window(id)) if id == windows[0]
+        ));
+        assert!(matches!(
+            Arrangement::group(Orientation::Vertical, vec![window(0), window(1)]),
+            Some(Arrangement::Group(Orientation::Vertical, children)) if children.len() == 2
+        ));
+    }
+
+    #[test]
+    fn test_manual_and_monocle() {
+        let (_, windows) = tree(3);
+        for strategy in [LayoutStrategy::Manual, LayoutStrategy::Monocle] {
+            assert!(
+                Arrangement::new(strategy, MasterStackConfig::default(), &windows, geo()).is_none()
+            );
+        }
+    }
+
+    #[test]
+    fn test_columns() {
+        let config = MasterStackConfig::default();
+        assert_eq!(arranged(1, LayoutStrategy::Columns, config), "0");
+        assert_eq!(arranged(2, LayoutStrategy::Columns, config), "V(0,1)");
+        assert_eq!(arranged(4, LayoutStrategy::Columns, config), "V(0,1,2,3)");
+    }
+
+    #[test]
+    fn test_master_stack() {
+        let config = MasterStackConfig::default();
+        assert_eq!(arranged(1, LayoutStrategy::MasterStack, config), "0");
+        assert_eq!(arranged(2, LayoutStrategy::MasterStack, config), "V(0,1)");
+        assert_eq!(
+            arranged(4, LayoutStrategy::MasterStack, config),
+            "V(0,H(1,2,3))"
+        );
+
+        let config = MasterStackConfig {
+            masters: 2,
+            ratio: 0.6,
+        };
+        assert_eq!(arranged(1, LayoutStrategy::MasterStack, config), "0");
+        assert_eq!(arranged(2, LayoutStrategy::MasterStack, config), "H(0,1)");
+        assert_eq!(
+            arranged(3, LayoutStrategy::MasterStack, config),
+            "V(H(0,1),2)"
+        );
+
+        let (mut tree, _) = tree(3);
+        TilingLayout::arrange_tree(&mut tree, LayoutStrategy::MasterStack, config, None, geo());
+        let root = tree.root_node_id().unwrap();
+        match tree.get(root).unwrap().data() {
+            Data::Group { sizes, .. } => assert_eq!(sizes, &[1152, 768]),
+            _ => panic!("master and stack aren't grouped"),
+        }
+    }
+
+    #[test]
+    fn test_dwindle() {
+        let config = MasterStackConfig::default();
+        assert_eq!(arranged(1, LayoutStrategy::Dwindle, config), "0");
+        assert_eq!(arranged(2, LayoutStrategy::Dwindle, config), "V(0,1)");
+        assert_eq!(arranged(3, LayoutStrategy::Dwindle, config), "V(0,H(1,2))");
+        assert_eq!(
+            arranged(4, LayoutStrategy::Dwindle, config),
+            "V(0,H(1,V(2,3)))"
+        );
+    }
+
+    #[test]
+    fn test_mapped_from_direction() {
+        let config = MasterStackConfig::default();
+        for (mapped, direction, expected) in [
+            (2, Direction::Right, "V(2,0,1)"),
+            (2, Direction::Down, "V(2,0,1)"),
+            (0, Direction::Left, "V(1,2,0)"),
+            (0, Direction::Up, "V(1,2,0)"),
+        ] {
+            let (mut tree, windows) = tree(3);
+            TilingLayout::arrange_tree(
+                &mut tree,
+                LayoutStrategy::Columns,
+                config,
+                Some((&windows[mapped], direction)),
+                geo(),
+            );
+            assert_valid(&tree);
+            assert_eq!(shape(&tree, &windows), expected);
+        }
+    }
+
+    #[test]
+    fn test_unmap_arranged() {
+        let configs = [
+            MasterStackConfig::default(),
+            MasterStackConfig {
+                masters: 2,
+                ratio: 0.5,
+            },
+        ];
+        for strategy in STRATEGIES {
+            for config in configs {
+                for n in 1..=5 {
+                    for closed in 0..n {
+                        let (mut tree, windows) = tree(n);
+                        TilingLayout::arrange_tree(&mut tree, strategy, config, None, geo());
+
+                        TilingLayout::unmap_internal(&mut tree, &windows[closed]);
+                        assert_valid(&tree);
+                        TilingLayout::arrange_tree(&mut tree, strategy, config, None, geo());
+                        assert_valid(&tree);
+
+                        let remaining = tree
+                            .traverse_pre_order(tree.root_node_id().unwrap())
+                            .unwrap()
+                            .filter(|node| !node.data().is_group())
+                            .count();
+                        assert_eq!(remaining, n - 1);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_unmap_all() {
+        for strategy in STRATEGIES {
+            let (mut tree, windows) = tree(5);
+            TilingLayout::arrange_tree(
+                &mut tree,
+                strategy,
+                MasterStackConfig::default(),
+                None,
+                geo(),
+            );
+            // close the master window over and over again
+            for window in &windows {
+                TilingLayout::unmap_internal(&mut tree, window);
+                assert_valid(&tree);
+                TilingLayout::arrange_tree(
+                    &mut tree,
+                    strategy,
+                    MasterStackConfig::default(),
+                    None,
+                    geo(),
+                );
+                assert_valid(&tree);
+            }
+            assert!(tree.root_node_id().is_none());
+        }
+    }
+}
@@ -1,4 +1,7 @@
-use calloop::LoopHandle;
+use calloop::{
+    timer::{TimeoutAction, Timer},
+    LoopHandle,
+};
 use grabs::SeatMoveGrabState;
 use indexmap::IndexMap;
 use std::{
@@ -6,12 +9,17 @@ use std::{
     sync::atomic::Ordering,
     time::{Duration, Instant},
 };
+use tracing::{error, warn};
 use wayland_backend::server::ClientId;
 
 use cosmic_comp_config::{
-    workspace::{WorkspaceLayout, WorkspaceMode},
+    gaps::GapsConfig,
+    workspace::{
+        LayoutStrategy, MasterStackConfig, PersistentWorkspace, WorkspaceLayout, WorkspaceMode,
+    },
     TileBehavior,
 };
+use cosmic_config::ConfigSet;
 use cosmic_protocols::workspace::v1::server::zcosmic_workspace_handle_v1::{
     State as WState, TilingState,
 };
@@ -69,8 +77,8 @@ use crate::{
                 toplevel_leave_workspace, ToplevelInfoState,
             },
             workspace::{
-                WorkspaceCapabilities, WorkspaceGroupHandle, WorkspaceHandle, WorkspaceState,
-                WorkspaceUpdateGuard,
+                GroupCapabilities, WorkspaceCapabilities, WorkspaceGroupHandle, WorkspaceHandle,
+                WorkspaceState, WorkspaceUpdateGuard,
             },
         },
     },
@@ -78,9 +86,13 @@ use crate::{
 
 pub mod element;
 pub mod focus;
+pub mod gamma;
 pub mod grabs;
+pub mod idle;
 pub mod layout;
 mod seats;
+pub mod session;
+pub mod window_rules;
 mod workspace;
 pub use self::element::{CosmicMapped, CosmicMappedRenderElement, CosmicSurface};
 pub use self::seats::*;
@@ -88,7 +100,9 @@ pub use self::workspace::*;
 
 use self::{
     element::{
+        lock_fallback::LockFallback,
         resize_indicator::{resize_indicator, ResizeIndicator},
+        stack::{MoveMode as StackMoveMode, MoveResult as StackMoveResult},
         swap_indicator::{swap_indicator, SwapIndicator},
         CosmicWindow, MaximizedState,
     },
@@ -99,11 +113,20 @@ use self::{
     },
     layout::{
         floating::{FloatingLayout, ResizeState},
+        scratchpad::ScratchpadLayer,
         tiling::{NodeDesc, ResizeForkGrab, TilingLayout},
     },
+    session::{Persist, SessionRestore},
+    window_rules::WindowRules,
 };
 
 const ANIMATION_DURATION: Duration = Duration::from_millis(200);
+const WORKSPACE_CAPABILITIES: [WorkspaceCapabilities; 4] = [
+    WorkspaceCapabilities::Activate,
+    WorkspaceCapabilities::Deactivate,
+    WorkspaceCapabilities::Remove,
+    WorkspaceCapabilities::Rename,
+];
 const GESTURE_MAX_LENGTH: f64 = 150.0;
 const GESTURE_POSITION_THRESHOLD: f64 = 0.5;
 const GESTURE_VELOCITY_THRESHOLD: f64 = 0.02;
@@ -243,6 +266,9 @@ pub struct Shell {
 
     theme: cosmic::Theme,
     pub active_hint: bool,
+    pub dim_inactive: f32,
+    pub window_rules: WindowRules,
+    pub session: SessionRestore,
     overview_mode: OverviewMode,
     swap_indicator: Option<SwapIndicator>,
     resize_mode: ResizeMode,
@@ -264,6 +290,8 @@ pub struct Shell {
 pub struct SessionLock {
     pub ext_session_lock: ExtSessionLockV1,
     pub surfaces: HashMap<Output, LockSurface>,
+    /// Screens shown after the lock client died, until another client takes over the lock
+    pub fallback: HashMap<Output, LockFallback>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -312,9 +340,12 @@ pub struct WorkspaceSet {
     pub group: WorkspaceGroupHandle,
     idx: usize,
     tiling_enabled: bool,
+    layout_strategy: LayoutStrategy,
+    master_stack: MasterStackConfig,
     output: Output,
     theme: cosmic::Theme,
     pub sticky_layer: FloatingLayout,
+    pub scratchpad_layer: ScratchpadLayer,
     pub minimized_windows: Vec<MinimizedWindow>,
     pub workspaces: Vec<Workspace>,
 }
@@ -325,6 +356,8 @@ fn create_workspace(
     group_handle: &WorkspaceGroupHandle,
     active: bool,
     tiling: bool,
+    layout_strategy: LayoutStrategy,
+    master_stack: MasterStackConfig,
     theme: cosmic::Theme,
 ) -> Workspace {
     let workspace_handle = state
@@ -340,11 +373,12 @@ fn create_workspace(
     if active {
         state.add_workspace_state(&workspace_handle, WState::Active);
     }
-    state.set_workspace_capabilities(
-        &workspace_handle,
-        [WorkspaceCapabilities::Activate].into_iter(),
-    );
-    Workspace::new(workspace_handle, output.clone(), tiling, theme.clone())
+    state.set_workspace_capabilities(&workspace_handle, WORKSPACE_CAPABILITIES.into_iter());
+    let mut workspace = Workspace::new(workspace_handle, output.clone(), tiling, theme.clone());
+    workspace
+        .tiling_layer
+        .set_default_strategy(layout_strategy, master_stack);
+    workspace
 }
 
 fn move_workspace_to_group(
@@ -363,10 +397,8 @@ fn move_workspace_to_group(
             },
         )
         .unwrap();
-    workspace_state.set_workspace_capabilities(
-        &workspace.handle,
-        [WorkspaceCapabilities::Activate].into_iter(),
-    );
+    workspace_state
+        .set_workspace_capabilities(&workspace.handle, WORKSPACE_CAPABILITIES.into_iter());
     for window in workspace.mapped() {
         for (surface, _) in window.windows() {
             toplevel_leave_workspace(&surface, &old_workspace_handle);
@@ -414,9 +446,15 @@ impl WorkspaceSet {
         output: &Output,
         idx: usize,
         tiling_enabled: bool,
+        layout_strategy: LayoutStrategy,
+        master_stack: MasterStackConfig,
         theme: cosmic::Theme,
     ) -> WorkspaceSet {
         let group_handle = state.create_workspace_group();
+        state.set_group_capabilities(
+            &group_handle,
+            [GroupCapabilities::CreateWorkspace].into_iter(),
+        );
         let workspaces = {
             let workspace = create_workspace(
                 state,
@@ -424,13 +462,12 @@ impl WorkspaceSet {
                 &group_handle,
                 true,
                 tiling_enabled,
+                layout_strategy,
+                master_stack,
                 theme.clone(),
             );
-            workspace_set_idx(state, 1, idx, &workspace.handle);
-            state.set_workspace_capabilities(
-                &workspace.handle,
-                [WorkspaceCapabilities::Activate].into_iter(),
-            );
+            workspace_set_idx(state, 1, idx, &workspace);
+            state.set_workspace_capabilities(&workspace.handle, WORKSPACE_CAPABILITIES.into_iter());
             vec![workspace]
         };
         let sticky_layer = FloatingLayout::new(theme.clone(), output);
@@ -441,8 +478,11 @@ impl WorkspaceSet {
             group: group_handle,
             idx,
             tiling_enabled,
+            layout_strategy,
+            master_stack,
             theme,
             sticky_layer,
+            scratchpad_layer: ScratchpadLayer::default(),
             minimized_windows: Vec::new(),
             workspaces,
             output: output.clone(),
@@ -546,6 +586,7 @@ impl WorkspaceSet {
             self.workspaces[self.active].refresh(xdg_activation_state);
         }
         self.sticky_layer.refresh();
+        self.scratchpad_layer.refresh(&self.sticky_layer);
     }
 
     fn add_empty_workspace(&mut self, state: &mut WorkspaceUpdateGuard<State>) {
@@ -555,24 +596,36 @@ impl WorkspaceSet {
             &self.group,
             false,
             self.tiling_enabled,
+            self.layout_strategy,
+            self.master_stack,
             self.theme.clone(),
         );
         workspace_set_idx(
             state,
             self.workspaces.len() as u8 + 1,
             self.idx,
-            &workspace.handle,
+            &workspace,
             // this method is only used by code paths related to dynamic workspaces, so this should be fine
         );
         self.workspaces.push(workspace);
     }
 
+    fn set_layout_strategy(&mut self, strategy: LayoutStrategy, master_stack: MasterStackConfig) {
+        self.layout_strategy = strategy;
+        self.master_stack = master_stack;
+        for workspace in &mut self.workspaces {
+            workspace
+                .tiling_layer
+                .set_default_strategy(strategy, master_stack);
+        }
+    }
+
     fn ensure_last_empty(&mut self, state: &mut WorkspaceUpdateGuard<State>) {
         // add empty at the end, if necessary
         if self
             .workspaces
             .last()
-            .map(|last| !last.is_empty())
+            .map(|last| !last.is_empty() || last.pinned)
             .unwrap_or(true)
         {
             self.add_empty_workspace(state);
@@ -582,7 +635,11 @@ impl WorkspaceSet {
         let len = self.workspaces.len();
         let mut keep = vec![true; len];
         for (i, workspace) in self.workspaces.iter().enumerate() {
-            if workspace.is_empty() && i != self.active && i != len - 1 {
+            if workspace.is_empty()
+                && !workspace.keep_if_empty()
+                && i != self.active
+                && i != len - 1
+            {
                 state.remove_workspace(workspace.handle);
                 keep[i] = false;
             }
@@ -598,15 +655,48 @@ impl WorkspaceSet {
 
         if keep.iter().any(|val| *val == false) {
             for (i, workspace) in self.workspaces.iter().enumerate() {
-                workspace_set_idx(state, i as u8 + 1, self.idx, &workspace.handle);
+                workspace_set_idx(state, i as u8 + 1, self.idx, workspace);
             }
         }
     }
 
+    fn insert_workspace(
+        &mut self,
+        idx: usize,
+        workspace: Workspace,
+        state: &mut WorkspaceUpdateGuard<'_, State>,
+    ) {
+        let idx = idx.min(self.workspaces.len());
+        self.workspaces.insert(idx, workspace);
+        if self.active >= idx {
+            self.active += 1;
+        }
+        if let Some((previous, delta)) = self.previously_active {
+            if previous >= idx {
+                self.previously_active = Some((previous + 1, delta));
+            }
+        }
+        self.update_idx(state, self.idx);
+    }
+
+    fn remove_workspace(&mut self, idx: usize, state: &mut WorkspaceUpdateGuard<'_, State>) {
+        let workspace = self.workspaces.remove(idx);
+        state.remove_workspace(workspace.handle);
+        if self.active > idx || self.active == self.workspaces.len() {
+            self.active -= 1;
+        }
+        self.previously_active = match self.previously_active.take() {
+            Some((previous, _)) if previous == idx => None,
+            Some((previous, delta)) if previous > idx => Some((previous - 1, delta)),
+            previously_active => previously_active,
+        };
+        self.update_idx(state, self.idx);
+    }
+
     fn update_idx(&mut self, state: &mut WorkspaceUpdateGuard<'_, State>, idx: usize) {
         self.idx = idx;
         for (i, workspace) in self.workspaces.iter().enumerate() {
-            workspace_set_idx(state, i as u8 + 1, idx, &workspace.handle);
+            workspace_set_idx(state, i as u8 + 1, idx, workspace);
         }
     }
 }
@@ -619,6 +709,10 @@ pub struct Workspaces {
     mode: WorkspaceMode,
     autotile: bool,
     autotile_behavior: TileBehavior,
+    gaps: GapsConfig,
+    layout_strategy: LayoutStrategy,
+    master_stack: MasterStackConfig,
+    persistent: Vec<PersistentWorkspace>,
     theme: cosmic::Theme,
 }
 
@@ -631,6 +725,10 @@ impl Workspaces {
             mode: config.cosmic_conf.workspaces.workspace_mode,
             autotile: config.cosmic_conf.autotile,
             autotile_behavior: config.cosmic_conf.autotile_behavior,
+            gaps: config.cosmic_conf.gaps.clone(),
+            layout_strategy: config.cosmic_conf.layout_strategy,
+            master_stack: config.cosmic_conf.master_stack,
+            persistent: config.cosmic_conf.workspaces.persistent.clone(),
             theme,
         }
     }
@@ -650,6 +748,7 @@ impl Workspaces {
             .take()
             .map(|mut set| {
                 set.set_output(output);
+                set.set_layout_strategy(self.layout_strategy, self.master_stack);
                 set
             })
             .unwrap_or_else(|| {
@@ -658,6 +757,8 @@ impl Workspaces {
                     &output,
                     self.sets.len(),
                     self.autotile,
+                    self.layout_strategy,
+                    self.master_stack,
                     self.theme.clone(),
                 )
             });
@@ -686,12 +787,75 @@ impl Workspaces {
             for (i, workspace) in set.workspaces.iter_mut().enumerate() {
                 workspace.set_output(output);
                 workspace.refresh(xdg_activation_state);
-                workspace_set_idx(workspace_state, i as u8 + 1, set.idx, &workspace.handle);
+                workspace_set_idx(workspace_state, i as u8 + 1, set.idx, workspace);
                 if i == set.active {
                     workspace_state.add_workspace_state(&workspace.handle, WState::Active);
                 }
             }
         }
+        self.apply_persistent(output, workspace_state);
+    }
+
+    /// Applies names and pins of the configured persistent workspaces to `output`
+    fn apply_persistent(
+        &mut self,
+        output: &Output,
+        workspace_state: &mut WorkspaceUpdateGuard<'_, State>,
+    ) {
+        let Some(set) = self.sets.get_mut(output) else {
+            return;
+        };
+        let name = output.name();
+        let persistent = self
+            .persistent
+            .iter()
+            .filter(|p| p.output == name)
+            .collect::<Vec<_>>();
+
+        if let Some(max) = persistent.iter().map(|p| p.index).max() {
+            while set.workspaces.len() <= max {
+                set.add_empty_workspace(workspace_state);
+            }
+        }
+        for (i, workspace) in set.workspaces.iter_mut().enumerate() {
+            let entry = persistent.iter().find(|p| p.index == i);
+            workspace.name = entry.and_then(|p| p.name.clone());
+            workspace.pinned = entry.is_some_and(|p| p.pinned);
+            workspace_set_idx(workspace_state, i as u8 + 1, set.idx, workspace);
+        }
+    }
+
+    /// Records names and pins of all workspaces.
+    ///
+    /// Returns the new list of persistent workspaces, if it changed.
+    pub fn update_persistent(&mut self) -> Option<Vec<PersistentWorkspace>> {
+        let mut persistent = self
+            .persistent
+            .iter()
+            .filter(|p| !self.sets.keys().any(|o| o.name() == p.output))
+            .cloned()
+            .collect::<Vec<_>>();
+        for (output, set) in self.sets.iter() {
+            persistent.extend(
+                set.workspaces
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, w)| w.pinned || w.name.is_some())
+                    .map(|(index, w)| PersistentWorkspace {
+                        output: output.name(),
+                        index,
+                        name: w.name.clone(),
+                        pinned: w.pinned,
+                    }),
+            );
+        }
+
+        if persistent != self.persistent {
+            self.persistent = persistent.clone();
+            Some(persistent)
+        } else {
+            None
+        }
     }
 
     pub fn remove_output<'a>(
@@ -750,6 +914,13 @@ impl Workspaces {
                     }
                 }
                 new_set.minimized_windows.extend(set.minimized_windows);
+                for window in set.scratchpad_layer.hidden() {
+                    for (surface, _) in window.windows() {
+                        toplevel_leave_output(&surface, output);
+                        toplevel_enter_output(&surface, &new_output);
+                    }
+                }
+                new_set.scratchpad_layer.merge(set.scratchpad_layer);
 
                 if self.mode == WorkspaceMode::OutputBound {
                     workspace_state.remove_workspace_group(set.group);
@@ -802,6 +973,19 @@ impl Workspaces {
         let old_mode = self.mode;
         self.mode = config.cosmic_conf.workspaces.workspace_mode;
         self.layout = config.cosmic_conf.workspaces.workspace_layout;
+        self.gaps = config.cosmic_conf.gaps.clone();
+        self.layout_strategy = config.cosmic_conf.layout_strategy;
+        self.master_stack = config.cosmic_conf.master_stack;
+        for set in self.sets.values_mut() {
+            set.set_layout_strategy(self.layout_strategy, self.master_stack);
+        }
+        self.update_gap_settings();
+        if self.persistent != config.cosmic_conf.workspaces.persistent {
+            self.persistent = config.cosmic_conf.workspaces.persistent.clone();
+            for output in self.sets.keys().cloned().collect::<Vec<_>>() {
+                self.apply_persistent(&output, workspace_state);
+            }
+        }
 
         if self.sets.len() <= 1 {
             return;
@@ -847,6 +1031,8 @@ impl Workspaces {
                                     &set.group,
                                     false,
                                     config.cosmic_conf.autotile,
+                                    self.layout_strategy,
+                                    self.master_stack,
                                     self.theme.clone(),
                                 ),
                             );
@@ -898,7 +1084,7 @@ impl Workspaces {
                     .sets
                     .values()
                     .flat_map(|set| set.workspaces.last())
-                    .any(|w| w.mapped().next().is_some())
+                    .any(|w| w.mapped().next().is_some() || w.pinned)
                 {
                     for set in self.sets.values_mut() {
                         set.add_empty_workspace(workspace_state);
@@ -910,7 +1096,10 @@ impl Workspaces {
                 let mut active = self.sets[0].active;
                 let mut keep = vec![true; len];
                 for i in 0..len {
-                    let has_windows = self.sets.values().any(|s| !s.workspaces[i].is_empty());
+                    let has_windows = self
+                        .sets
+                        .values()
+                        .any(|s| !s.workspaces[i].is_empty() || s.workspaces[i].keep_if_empty());
 
                     if !has_windows && i != active && i != len - 1 {
                         for workspace in self.sets.values().map(|s| &s.workspaces[i]) {
@@ -932,12 +1121,7 @@ impl Workspaces {
                 if keep.iter().any(|val| *val == false) {
                     for set in self.sets.values_mut() {
                         for (i, workspace) in set.workspaces.iter().enumerate() {
-                            workspace_set_idx(
-                                workspace_state,
-                                i as u8 + 1,
-                                set.idx,
-                                &workspace.handle,
-                            );
+                            workspace_set_idx(workspace_state, i as u8 + 1, set.idx, workspace);
                         }
                     }
                 }
@@ -949,11 +1133,23 @@ impl Workspaces {
             }
         }
 
+        self.update_gap_settings();
         for set in self.sets.values_mut() {
             set.refresh(xdg_activation_state)
         }
     }
 
+    /// Applies gap settings, which may depend on output and position, to all workspaces
+    fn update_gap_settings(&mut self) {
+        for (output, set) in self.sets.iter_mut() {
+            let name = output.name();
+            set.sticky_layer.gap_settings = self.gaps.settings(&name, None);
+            for (i, workspace) in set.workspaces.iter_mut().enumerate() {
+                workspace.set_gap_settings(self.gaps.settings(&name, Some(i as u32 + 1)));
+            }
+        }
+    }
+
     pub fn get(&self, num: usize, output: &Output) -> Option<&Workspace> {
         self.sets
             .get(output)
@@ -1044,6 +1240,7 @@ impl Workspaces {
 
             for w in &mut s.workspaces {
                 w.tiling_layer.theme = theme.clone();
+                w.scrolling_layer.theme = theme.clone();
                 w.floating_layer.theme = theme.clone();
 
                 w.mapped().for_each(|m| {
@@ -1124,17 +1321,20 @@ pub struct InvalidWorkspaceIndex;
 impl Common {
     pub fn add_output(&mut self, output: &Output) {
         let mut shell = self.shell.write().unwrap();
-        shell.workspaces.add_output(
-            output,
-            &mut self.workspace_state.update(),
-            &self.xdg_activation_state,
-        );
+        let mut workspace_state = self.workspace_state.update();
+        shell
+            .workspaces
+            .add_output(output, &mut workspace_state, &self.xdg_activation_state);
+        shell.restore_workspaces(output, &mut workspace_state);
+        std::mem::drop(workspace_state);
 
         std::mem::drop(shell);
         self.refresh(); // fixes indicies of any moved workspaces
     }
 
     pub fn remove_output(&mut self, output: &Output) {
+        self.output_power_state.output_removed(output);
+        self.gamma_control_state.output_removed(output);
         let mut shell = self.shell.write().unwrap();
         let shell_ref = &mut *shell;
         shell_ref.workspaces.remove_output(
@@ -1168,7 +1368,9 @@ impl Common {
 
     pub fn update_config(&mut self) {
         let mut shell = self.shell.write().unwrap();
+        shell.session.mark_dirty();
         shell.active_hint = self.config.cosmic_conf.active_hint;
+        shell.dim_inactive = self.config.cosmic_conf.dim_inactive.clamp(0.0, 1.0);
 
         let mut workspace_state = self.workspace_state.update();
         shell.workspaces.update_config(
@@ -1191,6 +1393,68 @@ impl Common {
         self.toplevel_info_state.refresh(&self.workspace_state);
         refresh_foreign_toplevels(&self.shell.read().unwrap());
         self.refresh_idle_inhibit();
+        self.persist_session();
+    }
+
+    /// Writes names and pins of all workspaces to the config
+    pub fn persist_workspaces(&mut self) {
+        let Some(persistent) = self.shell.write().unwrap().workspaces.update_persistent() else {
+            return;
+        };
+
+        self.config.cosmic_conf.workspaces.persistent = persistent;
+        let config = self.config.cosmic_helper.clone();
+        let workspaces = self.config.cosmic_conf.workspaces.clone();
+        std::thread::spawn(move || {
+            if let Err(err) = config.set("workspaces", workspaces) {
+                error!(?err, "Failed to update workspaces key");
+            }
+        });
+    }
+
+    fn persist_session(&mut self) {
+        let mut shell = self.shell.write().unwrap();
+        match shell.session.should_persist() {
+            Persist::Now => {}
+            Persist::After(delay) => {
+                // make sure the last change is persisted, even if no further refresh happens.
+                // The snapshot is taken by the refresh following the dispatch of the timer.
+                match self.event_loop_handle.insert_source(
+                    Timer::from_duration(delay),
+                    |_, _, state| {
+                        let mut shell = state.common.shell.write().unwrap();
+                        shell.session.persist_timer_elapsed();
+                        TimeoutAction::Drop
+                    },
+                ) {
+                    Ok(token) => shell.session.persist_timer = Some(token),
+                    Err(err) => warn!(?err, "Failed to schedule persisting the session."),
+                }
+                return;
+            }
+            Persist::Skip => return,
+        }
+
+        let session = shell.session_state();
+        if &session != self.config.dynamic_conf.session() {
+            *self.config.dynamic_conf.session_mut() = session;
+        }
+    }
+
+    /// Persists the current session regardless of throttling, e.g. on shutdown
+    pub fn flush_session(&mut self) {
+        let mut shell = self.shell.write().unwrap();
+        if let Some(token) = shell.session.persist_timer.take() {
+            self.event_loop_handle.remove(token);
+        }
+        if shell.session.is_restoring() {
+            return;
+        }
+
+        let session = shell.session_state();
+        if &session != self.config.dynamic_conf.session() {
+            *self.config.dynamic_conf.session_mut() = session;
+        }
     }
 
     pub fn refresh_idle_inhibit(&mut self) {
@@ -1202,6 +1466,7 @@ impl Common {
             })
         });
         self.idle_notifier_state.set_is_inhibited(is_inhibited);
+        self.idle_policy.set_inhibited(is_inhibited);
     }
 
     pub fn on_commit(&mut self, surface: &WlSurface) {
@@ -1243,6 +1508,9 @@ impl Shell {
 
             theme,
             active_hint: config.cosmic_conf.active_hint,
+            dim_inactive: config.cosmic_conf.dim_inactive.clamp(0.0, 1.0),
+            window_rules: WindowRules::new(&config.cosmic_conf.window_rules),
+            session: SessionRestore::new(config.dynamic_conf.session()),
             overview_mode: OverviewMode::None,
             swap_indicator: None,
             resize_mode: ResizeMode::None,
@@ -1261,6 +1529,7 @@ impl Shell {
         workspace_delta: WorkspaceDelta,
         workspace_state: &mut WorkspaceUpdateGuard<'_, State>,
     ) -> Result<Option<Point<i32, Global>>, InvalidWorkspaceIndex> {
+        self.session.mark_dirty();
         match &mut self.workspaces.mode {
             WorkspaceMode::OutputBound => {
                 if let Some(set) = self.workspaces.sets.get_mut(output) {
@@ -1290,6 +1559,174 @@ impl Shell {
         }
     }
 
+    fn workspace_position(&self, handle: &WorkspaceHandle) -> Option<(Output, usize)> {
+        self.workspaces.iter().find_map(|(output, set)| {
+            set.workspaces
+                .iter()
+                .position(|w| &w.handle == handle)
+                .map(|idx| (output.clone(), idx))
+        })
+    }
+
+    /// Creates a new named and pinned workspace.
+    ///
+    /// If no `idx` is given, the workspace is placed before the trailing empty workspace.
+    pub fn create_workspace(
+        &mut self,
+        group: &WorkspaceGroupHandle,
+        name: String,
+        idx: Option<usize>,
+        workspace_state: &mut WorkspaceUpdateGuard<'_, State>,
+    ) -> Option<WorkspaceHandle> {
+        self.session.mark_dirty();
+        let output = self
+            .workspaces
+            .iter()
+            .find(|(_, set)| &set.group == group)
+            .map(|(output, _)| output.clone())?;
+        let set = &self.workspaces.sets[&output];
+        let idx = match idx {
+            Some(idx) => idx.min(set.workspaces.len()),
+            // before the trailing empty workspace
+            None => match set.workspaces.last() {
+                Some(last) if last.is_empty() && !last.pinned => set.workspaces.len() - 1,
+                _ => set.workspaces.len(),
+            },
+        };
+
+        let mode = self.workspaces.mode;
+        let mut handle = None;
+        for (set_output, set) in self.workspaces.sets.iter_mut() {
+            // in global mode all outputs need to have the same amount of workspaces
+            if set_output != &output && mode != WorkspaceMode::Global {
+                continue;
+            }
+
+            let mut workspace = create_workspace(
+                workspace_state,
+                set_output,
+                &set.group,
+                false,
+                set.tiling_enabled,
+                set.layout_strategy,
+                set.master_stack,
+                set.theme.clone(),
+            );
+            if set_output == &output {
+                workspace.name = (!name.is_empty()).then(|| name.clone());
+                workspace.pinned = true;
+                handle = Some(workspace.handle);
+            }
+            set.insert_workspace(idx, workspace, workspace_state);
+        }
+
+        handle
+    }
+
+    pub fn rename_workspace(
+        &mut self,
+        handle: &WorkspaceHandle,
+        name: String,
+        workspace_state: &mut WorkspaceUpdateGuard<'_, State>,
+    ) {
+        let Some((output, idx)) = self.workspace_position(handle) else {
+            return;
+        };
+        let set = self.workspaces.sets.get_mut(&output).unwrap();
+        let workspace = &mut set.workspaces[idx];
+        workspace.name = (!name.is_empty()).then_some(name);
+        workspace_set_idx(workspace_state, idx as u8 + 1, set.idx, workspace);
+    }
+
+    /// Activates a neighboring workspace, if `handle` is currently active
+    pub fn deactivate_workspace(
+        &mut self,
+        handle: &WorkspaceHandle,
+        workspace_state: &mut WorkspaceUpdateGuard<'_, State>,
+    ) {
+        let Some((output, idx)) = self.workspace_position(handle) else {
+            return;
+        };
+        let set = &self.workspaces.sets[&output];
+        if set.active != idx || set.workspaces.len() <= 1 {
+            return;
+        }
+
+        let target = if idx > 0 { idx - 1 } else { idx + 1 };
+        let _ = self.activate(
+            &output,
+            target,
+            WorkspaceDelta::new_shortcut(),
+            workspace_state,
+        );
+    }
+
+    /// Removes a workspace, moving its windows to a neighboring workspace
+    pub fn remove_workspace(
+        &mut self,
+        handle: &WorkspaceHandle,
+        workspace_state: &mut WorkspaceUpdateGuard<'_, State>,
+        xdg_activation_state: &XdgActivationState,
+    ) {
+        self.session.mark_dirty();
+        let Some((output, idx)) = self.workspace_position(handle) else {
+            return;
+        };
+        if self.workspaces.sets[&output].workspaces.len() <= 1 {
+            return;
+        }
+        let target_idx = if idx > 0 { idx - 1 } else { idx + 1 };
+
+        // in global mode all outputs need to have the same amount of workspaces
+        let outputs = match self.workspaces.mode {
+            WorkspaceMode::Global => self.workspaces.sets.keys().cloned().collect::<Vec<_>>(),
+            WorkspaceMode::OutputBound => vec![output],
+        };
+
+        self.deactivate_workspace(handle, workspace_state);
+        for output in &outputs {
+            let set = self.workspaces.sets.get_mut(output).unwrap();
+            let target = set.workspaces[target_idx].handle;
+            let workspace = &mut set.workspaces[idx];
+            workspace.pinned = false;
+            workspace.restoring = false;
+            workspace.name = None;
+
+            let from = workspace.handle;
+            // moving minimized windows through `move_window` would restore them
+            let minimized = std::mem::take(&mut workspace.minimized_windows);
+            let windows = workspace.mapped().cloned().collect::<Vec<_>>();
+
+            let target_workspace = &mut set.workspaces[target_idx];
+            for window in minimized {
+                for (toplevel, _) in window.window.windows() {
+                    toplevel_leave_workspace(&toplevel, &from);
+                    toplevel_enter_workspace(&toplevel, &target);
+                }
+                target_workspace.minimized_windows.push(window);
+            }
+
+            for mapped in windows {
+                let _ =
+                    self.move_window(None, &mapped, &from, &target, false, None, workspace_state);
+            }
+        }
+
+        let is_empty = outputs.iter().all(|output| {
+            let workspace = &self.workspaces.sets[output].workspaces[idx];
+            workspace.mapped().next().is_none() && workspace.minimized_windows.is_empty()
+        });
+        if is_empty {
+            for output in &outputs {
+                let set = self.workspaces.sets.get_mut(output).unwrap();
+                set.remove_workspace(idx, workspace_state);
+            }
+        }
+
+        self.workspaces
+            .refresh(workspace_state, xdg_activation_state);
+    }
+
     pub fn update_workspace_delta(&mut self, output: &Output, delta: f64) {
         match &mut self.workspaces.mode {
             WorkspaceMode::OutputBound => {
@@ -1508,6 +1945,7 @@ impl Shell {
                 .iter()
                 .map(|w| &w.window)
                 .chain(set.sticky_layer.mapped())
+                .chain(set.scratchpad_layer.hidden())
                 .find(|w| w.windows().any(|(s, _)| &s == surface))
                 .or_else(|| {
                     set.workspaces
@@ -1576,6 +2014,26 @@ impl Shell {
             .map(|(o, _)| o)
     }
 
+    /// Geometry of a mapped element in the global space
+    pub fn global_element_geometry(&self, mapped: &CosmicMapped) -> Option<Rectangle<i32, Global>> {
+        if let Some(set) = self
+            .workspaces
+            .sets
+            .values()
+            .find(|set| set.sticky_layer.mapped().any(|m| m == mapped))
+        {
+            return set
+                .sticky_layer
+                .element_geometry(mapped)
+                .map(|geometry| geometry.to_global(&set.output));
+        }
+
+        let workspace = self.space_for(mapped)?;
+        workspace
+            .element_geometry(mapped)
+            .map(|geometry| geometry.to_global(workspace.output()))
+    }
+
     pub fn builtin_output(&self) -> Option<&Output> {
         self.outputs().find(|output| {
             let name = output.name();
@@ -1779,6 +2237,11 @@ impl Shell {
             _ => {}
         }
 
+        if self.session.refresh() {
+            for workspace in self.workspaces.spaces_mut() {
+                workspace.restoring = false;
+            }
+        }
         self.workspaces
             .refresh(workspace_state, xdg_activation_state);
 
@@ -1844,9 +2307,7 @@ impl Shell {
                     .map(mapped, None)
             }
             ManagedLayer::Tiling if new_workspace.tiling_enabled => {
-                new_workspace
-                    .tiling_layer
-                    .map(mapped, Option::<std::iter::Empty<_>>::None, None)
+                new_workspace.map_tiled(mapped, None, None)
             }
             _ => new_workspace.floating_layer.map(mapped, None),
         };
@@ -1861,6 +2322,7 @@ impl Shell {
         workspace_state: &mut WorkspaceState<State>,
         evlh: &LoopHandle<'static, State>,
     ) -> Option<KeyboardFocusTarget> {
+        self.session.mark_dirty();
         let pos = self
             .pending_windows
             .iter()
@@ -1885,15 +2347,55 @@ impl Shell {
             false
         };
 
+        let is_dialog = layout::is_dialog(&window);
+        let rules = self.window_rules.properties(&window, is_dialog);
+        let restored = self.session.claim(&window);
+        let floating = rules.floating.unwrap_or_else(|| {
+            restored
+                .as_ref()
+                .map(|restored| !restored.is_tiled())
+                .unwrap_or(is_dialog)
+        });
+        if let Some(opacity) = rules.opacity {
+            window.set_opacity(opacity);
+        }
+
+        let should_be_fullscreen = rules.fullscreen.unwrap_or(output.is_some());
+        let restored_output = restored.as_ref().and_then(|restored| {
+            self.outputs()
+                .find(|o| o.name() == restored.output)
+                .cloned()
+        });
+        let mut output = output
+            .or_else(|| {
+                rules
+                    .output
+                    .as_ref()
+                    .and_then(|name| self.outputs().find(|o| &o.name() == name))
+                    .cloned()
+            })
+            .or_else(|| restored_output.clone())
+            .unwrap_or_else(|| seat.active_output());
+
+        let restored_handle = restored
+            .as_ref()
+            .filter(|restored| !restored.is_sticky())
+            .zip(restored_output.as_ref())
+            .and_then(|(restored, output)| self.workspaces.get(restored.workspace, output))
+            .map(|w| w.handle);
         let pending_activation = self.pending_activations.remove(&(&window).into());
         let workspace_handle = match pending_activation {
             Some(ActivationContext::Workspace(handle)) => Some(handle),
-            _ => None,
+            _ => rules
+                .workspace
+                .and_then(|num| {
+                    self.workspaces
+                        .get((num as usize).saturating_sub(1), &output)
+                        .map(|w| w.handle)
+                })
+                .or(restored_handle),
         };
 
-        let should_be_fullscreen = output.is_some();
-        let mut output = output.unwrap_or_else(|| seat.active_output());
-
         // this is beyond stupid, just to make the borrow checker happy
         let workspace = if let Some(handle) = workspace_handle.filter(|handle| {
             self.workspaces
@@ -1948,13 +2450,84 @@ impl Shell {
         let was_activated = workspace_handle.is_some()
             && (workspace_output != seat.active_output() || active_handle != workspace.handle);
         let workspace_handle = workspace.handle;
-        let is_dialog = layout::is_dialog(&window);
-        let floating_exception = layout::has_floating_exception(&window);
+
+        if let Some(restored) = restored.as_ref().filter(|r| !r.stack.is_empty()) {
+            // join the stack this window was part of
+            let sibling = workspace
+                .mapped()
+                .find(|mapped| {
+                    mapped.windows().any(|(w, _)| {
+                        self.session
+                            .claimed_window(&restored.stack)
+                            .any(|claimed| claimed == &w)
+                    })
+                })
+                .cloned();
+            if let Some(sibling) = sibling {
+                let new_focus = if sibling.is_stack() {
+                    None
+                } else if workspace.is_tiled(&sibling) {
+                    workspace
+                        .tiling_layer
+                        .toggle_stacking(&sibling, workspace.focus_stack.get_mut(&seat))
+                } else {
+                    workspace
+                        .floating_layer
+                        .toggle_stacking(&sibling, workspace.focus_stack.get_mut(&seat))
+                };
+                let stack = match &new_focus {
+                    Some(KeyboardFocusTarget::Element(mapped)) => mapped.clone(),
+                    _ => sibling,
+                };
+                if let Some(stack) = stack.stack_ref() {
+                    stack.add_window(window, None);
+                    return None;
+                }
+            }
+        }
 
         let maybe_focused = workspace.focus_stack.get(&seat).iter().next().cloned();
+        if !floating
+            && !should_be_fullscreen
+            && workspace.tiling_enabled
+            && !workspace.scrolling_enabled
+            && workspace.tiling_layer.strategy() == LayoutStrategy::Monocle
+        {
+            let tiled = maybe_focused
+                .clone()
+                .filter(|focused| workspace.is_tiled(focused))
+                .or_else(|| {
+                    workspace
+                        .tiling_layer
+                        .mapped()
+                        .next()
+                        .map(|(m, _)| m.clone())
+                });
+            if let Some(tiled) = tiled {
+                // join the single stack of the workspace
+                let new_focus = if tiled.is_stack() {
+                    None
+                } else {
+                    workspace
+                        .tiling_layer
+                        .toggle_stacking(&tiled, workspace.focus_stack.get_mut(&seat))
+                };
+                let stack = match &new_focus {
+                    Some(KeyboardFocusTarget::Element(mapped)) => mapped.clone(),
+                    _ => tiled,
+                };
+                if let Some(stack) = stack.stack_ref() {
+                    stack.add_window(window, None);
+                    if was_activated {
+                        workspace_state.add_workspace_state(&workspace_handle, WState::Urgent);
+                    }
+                    return new_focus;
+                }
+            }
+        }
         if let Some(focused) = maybe_focused {
-            if (focused.is_stack() && !is_dialog && !should_be_fullscreen)
-                && !(workspace.is_tiled(&focused) && floating_exception)
+            if (focused.is_stack() && !(is_dialog && floating) && !should_be_fullscreen)
+                && !(workspace.is_tiled(&focused) && floating)
             {
                 focused.stack_ref().unwrap().add_window(window, None);
                 if was_activated {
@@ -1975,8 +2548,20 @@ impl Shell {
         }
 
         let workspace_empty = workspace.mapped().next().is_none();
-        if is_dialog || floating_exception || !workspace.tiling_enabled {
-            workspace.floating_layer.map(mapped.clone(), None);
+        if floating || !workspace.tiling_enabled {
+            let restored_geometry = restored.as_ref().and_then(|r| r.geometry());
+            workspace.floating_layer.map_internal(
+                mapped.clone(),
+                rules
+                    .position
+                    .map(Point::from)
+                    .or(restored_geometry.map(|geo| geo.loc)),
+                rules
+                    .size
+                    .map(Size::from)
+                    .or(restored_geometry.map(|geo| geo.size.as_logical())),
+                None,
+            );
         } else {
             for mapped in workspace
                 .mapped()
@@ -1987,10 +2572,16 @@ impl Shell {
             {
                 workspace.unmaximize_request(&mapped);
             }
-            let focus_stack = workspace.focus_stack.get(&seat);
-            workspace
-                .tiling_layer
-                .map(mapped.clone(), Some(focus_stack.iter()), None);
+            workspace.map_tiled(mapped.clone(), Some(&seat), None);
+            if let Some(tree) = restored
+                .as_ref()
+                .filter(|_| {
+                    restored_handle == Some(workspace_handle) && !workspace.scrolling_enabled
+                })
+                .and_then(|restored| self.session.tree(restored))
+            {
+                workspace.tiling_layer.restore(tree, self.session.claimed());
+            }
         }
 
         if !parent_is_sticky && should_be_fullscreen {
@@ -1999,10 +2590,23 @@ impl Shell {
             workspace.fullscreen_request(&mapped.active_window(), None, from, &seat);
         }
 
-        if parent_is_sticky {
+        if parent_is_sticky
+            || rules
+                .sticky
+                .unwrap_or_else(|| restored.as_ref().is_some_and(|r| r.is_sticky()))
+        {
             self.toggle_sticky(&seat, &mapped);
         }
 
+        if rules.maximized == Some(true) && !should_be_fullscreen {
+            self.maximize_request(&mapped, &seat);
+        }
+
+        if restored.as_ref().is_some_and(|r| r.minimized) && !should_be_fullscreen {
+            self.minimize_request(&mapped);
+            return None;
+        }
+
         let new_target = if (workspace_output == seat.active_output()
             && active_handle == workspace_handle)
             || parent_is_sticky
@@ -2062,6 +2666,7 @@ impl Shell {
         }
         for workspace in self.workspaces.spaces_mut() {
             workspace.tiling_layer.recalculate();
+            workspace.scrolling_layer.recalculate();
         }
 
         wants_focus.then(|| layer_surface.into())
@@ -2076,6 +2681,7 @@ impl Shell {
     ) where
         CosmicSurface: PartialEq<S>,
     {
+        self.session.mark_dirty();
         for set in self.workspaces.sets.values_mut() {
             let sticky_res = set.sticky_layer.mapped().find_map(|m| {
                 m.windows()
@@ -2103,6 +2709,8 @@ impl Shell {
                 } else {
                     Some(set.minimized_windows.remove(idx).window.active_window())
                 }
+            } else if let Some(surface) = set.scratchpad_layer.remove_surface(surface) {
+                Some(surface)
             } else if let Some((workspace, mut elem)) = set.workspaces.iter_mut().find_map(|w| {
                 w.element_for_surface(&surface)
                     .cloned()
@@ -2167,6 +2775,7 @@ impl Shell {
         direction: Option<Direction>,
         workspace_state: &mut WorkspaceUpdateGuard<'_, State>,
     ) -> Option<(KeyboardFocusTarget, Point<i32, Global>)> {
+        self.session.mark_dirty();
         let from_output = self.workspaces.space_for_handle(from)?.output.clone();
         let to_output = self.workspaces.space_for_handle(to)?.output.clone();
 
@@ -2204,15 +2813,10 @@ impl Shell {
 
         let any_seat = seat.unwrap_or(self.seats.last_active()).clone();
         let mut to_workspace = self.workspaces.space_for_handle_mut(to).unwrap(); // checked above
-        let focus_stack = seat.map(|seat| to_workspace.focus_stack.get(&seat));
         if window_state.layer == ManagedLayer::Floating || !to_workspace.tiling_enabled {
             to_workspace.floating_layer.map(mapped.clone(), None);
         } else {
-            to_workspace.tiling_layer.map(
-                mapped.clone(),
-                focus_stack.as_ref().map(|x| x.iter()),
-                direction,
-            );
+            to_workspace.map_tiled(mapped.clone(), seat, direction);
         }
 
         let focus_target = if let Some(f) = window_state.was_fullscreen {
@@ -2482,11 +3086,6 @@ impl Shell {
             GrabStartData::Pointer(start_data) => Trigger::Pointer(start_data.button),
             GrabStartData::Touch(start_data) => Trigger::Touch(start_data.slot),
         };
-        let active_hint = if config.cosmic_conf.active_hint {
-            self.theme.cosmic().active_hint as u8
-        } else {
-            0
-        };
         let pointer = seat.get_pointer().unwrap();
         let pos = pointer.current_location().as_global();
 
@@ -2503,6 +3102,11 @@ impl Shell {
         } else {
             seat.active_output()
         };
+        let active_hint = if config.cosmic_conf.active_hint {
+            self.active_space(&cursor_output).active_hint()
+        } else {
+            0
+        };
 
         let (initial_window_location, layer, workspace_handle) = if let Some(workspace) =
             self.space_for_mut(&old_mapped)
@@ -2527,16 +3131,21 @@ impl Shell {
 
             let layer = if mapped == old_mapped {
                 let was_floating = workspace.floating_layer.unmap(&mapped);
-                let was_tiled = workspace.tiling_layer.unmap_as_placeholder(&mapped);
-                assert!(was_floating.is_some() != was_tiled.is_some());
+                let was_tiled = workspace
+                    .tiling_layer
+                    .unmap_as_placeholder(&mapped)
+                    .is_some()
+                    || workspace.scrolling_layer.unmap(&mapped);
+                assert!(was_floating.is_some() != was_tiled);
                 if was_floating.is_some_and(|size| size != elem_geo.size.as_logical()) {
                     new_size = was_floating;
                 }
-                was_tiled.is_some()
+                was_tiled
             } else {
                 workspace
                     .tiling_layer
                     .mapped()
+                    .chain(workspace.scrolling_layer.mapped())
                     .any(|(m, _)| m == &old_mapped)
             }
             .then_some(ManagedLayer::Tiling)
@@ -2622,19 +3231,21 @@ impl Shell {
         mapped.set_activate(true);
         mapped.configure();
 
+        // the scrolling layer has no drop targets to show in the overview
+        let is_scrolling = self.active_space(&cursor_output).scrolling_enabled;
         let grab = MoveGrab::new(
             start_data,
             mapped,
             seat,
             initial_window_location,
             cursor_output,
-            active_hint as u8,
+            active_hint,
             layer,
             release,
             evlh.clone(),
         );
 
-        if grab.is_tiling_grab() {
+        if grab.is_tiling_grab() && !is_scrolling {
             self.set_overview_mode(Some(trigger), evlh.clone());
         }
 
@@ -2701,7 +3312,17 @@ impl Shell {
             return FocusResult::Handled;
         }
 
-        if workspace.is_tiled(&focused) {
+        if workspace
+            .scrolling_layer
+            .mapped()
+            .any(|(m, _)| m == &focused)
+        {
+            if focused.is_maximized(false) {
+                return FocusResult::None;
+            }
+
+            workspace.scrolling_layer.next_focus(direction, &focused)
+        } else if workspace.is_tiled(&focused) {
             if focused.is_maximized(false) {
                 return FocusResult::None;
             }
@@ -2792,6 +3413,7 @@ impl Shell {
 
     #[must_use]
     pub fn move_current_element(&mut self, direction: Direction, seat: &Seat<State>) -> MoveResult {
+        self.session.mark_dirty();
         let output = seat.active_output();
         let workspace = self.active_space(&output);
         let focus_stack = workspace.focus_stack.get(seat);
@@ -2830,7 +3452,68 @@ impl Shell {
             workspace
                 .floating_layer
                 .move_current_element(direction, seat, ManagedLayer::Floating, theme)
-                .or_else(|| workspace.tiling_layer.move_current_node(direction, seat))
+                .or_else(|| {
+                    workspace
+                        .scrolling_layer
+                        .move_current_element(direction, seat)
+                })
+                .or_else(|| {
+                    workspace.tiling_layer.move_current_node(
+                        direction,
+                        seat,
+                        StackMoveMode::Default,
+                    )
+                })
+        }
+    }
+
+    /// Moves the active tab of the focused stack according to `mode`.
+    ///
+    /// Tabs can only be moved out of tiled stacks.
+    #[must_use]
+    pub fn move_current_tab(
+        &mut self,
+        direction: Direction,
+        seat: &Seat<State>,
+        mode: StackMoveMode,
+    ) -> MoveResult {
+        let output = seat.active_output();
+        let workspace = self.active_space_mut(&output);
+        let Some(focused) = workspace.focus_stack.get(seat).last().cloned() else {
+            return MoveResult::None;
+        };
+
+        if workspace.is_tiled(&focused) {
+            workspace
+                .tiling_layer
+                .move_current_node(direction, seat, mode)
+        } else if mode == StackMoveMode::Reorder {
+            match focused.handle_move(direction, mode) {
+                StackMoveResult::Handled => MoveResult::Done,
+                _ => MoveResult::None,
+            }
+        } else {
+            MoveResult::None
+        }
+    }
+
+    /// Merges the focused tiled window into the window next to it in `direction`
+    #[must_use]
+    pub fn merge_current_into_stack(
+        &mut self,
+        direction: Direction,
+        seat: &Seat<State>,
+    ) -> MoveResult {
+        let output = seat.active_output();
+        let workspace = self.active_space_mut(&output);
+        let Some(focused) = workspace.focus_stack.get(seat).last().cloned() else {
+            return MoveResult::None;
+        };
+
+        if workspace.is_tiled(&focused) {
+            workspace.tiling_layer.merge_current_node(direction, seat)
+        } else {
+            MoveResult::None
         }
     }
 
@@ -2941,6 +3624,7 @@ impl Shell {
     }
 
     pub fn minimize_request(&mut self, mapped: &CosmicMapped) {
+        self.session.mark_dirty();
         if let Some(set) = self
             .workspaces
             .sets
@@ -2968,6 +3652,7 @@ impl Shell {
     }
 
     pub fn unminimize_request(&mut self, mapped: &CosmicMapped, seat: &Seat<State>) {
+        self.session.mark_dirty();
         if let Some((set, window)) = self.workspaces.sets.values_mut().find_map(|set| {
             set.minimized_windows
                 .iter()
@@ -3008,6 +3693,7 @@ impl Shell {
     }
 
     pub fn maximize_request(&mut self, mapped: &CosmicMapped, seat: &Seat<State>) {
+        self.session.mark_dirty();
         self.unminimize_request(mapped, seat);
         let (original_layer, floating_layer, original_geometry) = if let Some(set) = self
             .workspaces
@@ -3041,6 +3727,7 @@ impl Shell {
     }
 
     pub fn unmaximize_request(&mut self, mapped: &CosmicMapped) -> Option<Size<i32, Logical>> {
+        self.session.mark_dirty();
         if let Some(set) = self.workspaces.sets.values_mut().find(|set| {
             set.sticky_layer.mapped().any(|m| m == mapped)
                 || set.minimized_windows.iter().any(|m| &m.window == mapped)
@@ -3133,6 +3820,7 @@ impl Shell {
     }
 
     pub fn resize(&mut self, seat: &Seat<State>, direction: ResizeDirection, edge: ResizeEdge) {
+        self.session.mark_dirty();
         let output = seat.active_output();
         let (_, idx) = self.workspaces.active_num(&output);
         let Some(focused) = seat.get_keyboard().unwrap().current_focus() else {
@@ -3162,7 +3850,59 @@ impl Shell {
         }
     }
 
+    /// Makes the focused tiled window take up `ratio` of the split it is in
+    pub fn resize_to_ratio(&mut self, seat: &Seat<State>, ratio: f64) {
+        let output = seat.active_output();
+        let Some(focused) = seat.get_keyboard().unwrap().current_focus() else {
+            return;
+        };
+        let workspace = self.active_space_mut(&output);
+        if workspace.fullscreen.is_none() {
+            workspace.tiling_layer.resize_to_ratio(&focused, ratio);
+        }
+    }
+
+    /// Evenly distributes the space of all splits around the focused tiled window or group
+    pub fn balance_splits(&mut self, seat: &Seat<State>) {
+        let output = seat.active_output();
+        let Some(focused) = seat.get_keyboard().unwrap().current_focus() else {
+            return;
+        };
+        let workspace = self.active_space_mut(&output);
+        if workspace.fullscreen.is_none() {
+            workspace.tiling_layer.balance(&focused);
+        }
+    }
+
+    /// Resizes and/or moves the focused floating window, `position` is relative to its output
+    pub fn set_floating_geometry(
+        &mut self,
+        seat: &Seat<State>,
+        size: Option<Size<i32, Logical>>,
+        position: Option<Point<i32, Local>>,
+    ) {
+        self.session.mark_dirty();
+        let output = seat.active_output();
+        let Some(focused) = seat.get_keyboard().unwrap().current_focus() else {
+            return;
+        };
+
+        if !self
+            .workspaces
+            .sets
+            .get_mut(&output)
+            .unwrap()
+            .sticky_layer
+            .set_geometry(&focused, size, position)
+        {
+            self.active_space_mut(&output)
+                .floating_layer
+                .set_geometry(&focused, size, position);
+        }
+    }
+
     pub fn finish_resize(&mut self, direction: ResizeDirection, edge: ResizeEdge) {
+        self.session.mark_dirty();
         if let Some((old_focused, old_direction, old_edge, _, idx, output)) =
             self.resize_state.take()
         {
@@ -3206,6 +3946,7 @@ impl Shell {
         seat: &Seat<State>,
         window: &CosmicMapped,
     ) -> Option<KeyboardFocusTarget> {
+        self.session.mark_dirty();
         if let Some(set) = self
             .workspaces
             .sets
@@ -3257,7 +3998,134 @@ impl Shell {
         }
     }
 
+    pub fn set_layout_strategy(
+        &mut self,
+        seat: &Seat<State>,
+        strategy: impl FnOnce(LayoutStrategy) -> LayoutStrategy,
+    ) -> Option<KeyboardFocusTarget> {
+        self.session.mark_dirty();
+        let workspace = self.workspaces.active_mut(&seat.active_output());
+        let strategy = strategy(workspace.tiling_layer.strategy());
+        workspace
+            .tiling_layer
+            .set_strategy(strategy, workspace.focus_stack.get_mut(seat))
+    }
+
+    /// Hides `mapped` in the scratchpad of the output it is currently on
+    pub fn move_to_scratchpad(&mut self, mapped: &CosmicMapped) {
+        // clean from focus-stacks
+        for workspace in self.workspaces.spaces_mut() {
+            for seat in self.seats.iter() {
+                let mut stack = workspace.focus_stack.get_mut(seat);
+                stack.remove(mapped);
+            }
+        }
+
+        if let Some(set) = self
+            .workspaces
+            .sets
+            .values_mut()
+            .find(|set| set.sticky_layer.mapped().any(|m| m == mapped))
+        {
+            set.sticky_layer.unmap(mapped);
+            set.scratchpad_layer.hide(mapped.clone());
+        } else if let Some(workspace) = self.space_for_mut(mapped) {
+            if workspace.is_fullscreen(mapped) {
+                let _ = workspace.remove_fullscreen();
+            }
+            if mapped.maximized_state.lock().unwrap().is_some() {
+                workspace.unmaximize_request(mapped);
+            }
+            if workspace.unmap(mapped).is_none() {
+                return;
+            }
+            let output = workspace.output().clone();
+            let handle = workspace.handle;
+            for (window, _) in mapped.windows() {
+                toplevel_leave_workspace(&window, &handle);
+            }
+
+            self.workspaces
+                .sets
+                .get_mut(&output)
+                .unwrap()
+                .scratchpad_layer
+                .hide(mapped.clone());
+        }
+    }
+
+    /// Shows the scratchpad windows matching `app_id` centered on the active output of `seat`
+    /// or hides them again, if they are already shown there.
+    #[must_use]
+    pub fn toggle_scratchpad(
+        &mut self,
+        seat: &Seat<State>,
+        app_id: Option<&str>,
+    ) -> Option<KeyboardFocusTarget> {
+        let output = seat.active_output();
+
+        let shown = self.workspaces.sets.iter().find_map(|(set_output, set)| {
+            set.scratchpad_layer
+                .find_shown(app_id)
+                .map(|mapped| (set_output.clone(), mapped.clone()))
+        });
+        let (previous_output, mapped) = match shown {
+            Some((shown_output, mapped)) => {
+                let set = self.workspaces.sets.get_mut(&shown_output).unwrap();
+                set.sticky_layer.unmap(&mapped);
+                if shown_output == output {
+                    set.scratchpad_layer.hide(mapped.clone());
+                    for workspace in set.workspaces.iter_mut() {
+                        for seat in self.seats.iter() {
+                            workspace.focus_stack.get_mut(seat).remove(&mapped);
+                        }
+                    }
+                    // focus the next window instead of the hidden one
+                    return self
+                        .active_space(&output)
+                        .focus_stack
+                        .get(seat)
+                        .last()
+                        .cloned()
+                        .map(KeyboardFocusTarget::from);
+                }
+                set.scratchpad_layer.remove(&mapped);
+                (shown_output, mapped)
+            }
+            None => self
+                .workspaces
+                .sets
+                .iter_mut()
+                .find_map(|(set_output, set)| {
+                    set.scratchpad_layer
+                        .take_hidden(app_id)
+                        .map(|mapped| (set_output.clone(), mapped))
+                })?,
+        };
+
+        if previous_output != output {
+            for (window, _) in mapped.windows() {
+                toplevel_leave_output(&window, &previous_output);
+                toplevel_enter_output(&window, &output);
+            }
+        }
+
+        let area = layer_map_for_output(&output).non_exclusive_zone();
+        let size = mapped.geometry().size;
+        let position = Point::<i32, Logical>::from((
+            area.loc.x + (area.size.w - size.w).max(0) / 2,
+            area.loc.y + (area.size.h - size.h).max(0) / 2,
+        ));
+        let set = self.workspaces.sets.get_mut(&output).unwrap();
+        set.sticky_layer
+            .map(mapped.clone(), Some(position.as_local()));
+        set.scratchpad_layer.show(mapped.clone());
+
+        Some(KeyboardFocusTarget::from(mapped))
+    }
+
     pub fn toggle_sticky(&mut self, seat: &Seat<State>, mapped: &CosmicMapped) {
+        self.session.mark_dirty();
         // clean from focus-stacks
         for workspace in self.workspaces.spaces_mut() {
             for seat in self.seats.iter() {
@@ -3318,10 +4186,7 @@ impl Shell {
                 .unwrap_or(ManagedLayer::Floating)
             {
                 ManagedLayer::Tiling if workspace.tiling_enabled => {
-                    let focus_stack = workspace.focus_stack.get(seat);
-                    workspace
-                        .tiling_layer
-                        .map(mapped.clone(), Some(focus_stack.iter()), None);
+                    workspace.map_tiled(mapped.clone(), Some(seat), None);
                 }
                 ManagedLayer::Sticky => unreachable!(),
                 _ => workspace.floating_layer.map(mapped.clone(), geometry.loc),
@@ -3428,6 +4293,7 @@ impl Shell {
             set.sticky_layer
                 .mapped()
                 .chain(set.minimized_windows.iter().map(|m| &m.window))
+                .chain(set.scratchpad_layer.hidden())
                 .chain(set.workspaces.iter().flat_map(|w| {
                     w.mapped()
                         .chain(w.minimized_windows.iter().map(|m| &m.window))
@@ -3440,9 +4306,13 @@ fn workspace_set_idx(
     state: &mut WorkspaceUpdateGuard<'_, State>,
     idx: u8,
     output_pos: usize,
-    handle: &WorkspaceHandle,
+    workspace: &Workspace,
 ) {
-    state.set_workspace_name(handle, format!("{}", idx));
+    let handle = &workspace.handle;
+    match workspace.name.as_ref() {
+        Some(name) => state.set_workspace_name(handle, name.clone()),
+        None => state.set_workspace_name(handle, format!("{}", idx)),
+    }
     state.set_workspace_coordinates(handle, [Some(idx as u32), Some(output_pos as u32), None]);
 }
 
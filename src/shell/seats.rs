@@ -3,23 +3,28 @@
 use std::{any::Any, cell::RefCell, collections::HashMap, sync::Mutex, time::Duration};
 
 use crate::{
-    backend::render::cursor::{CursorShape, CursorState},
+    backend::render::cursor::{CursorState, TabletToolCursors},
     config::{xkb_config_to_wl, Config},
-    input::{ModifiersShortcutQueue, SupressedButtons, SupressedKeys},
-    state::State,
+    input::{
+        ActiveKeymap, ModifiersShortcutQueue, SeatBindingMode, SupressedButtons, SupressedKeys,
+    },
+    state::{BackendData, State},
 };
 use smithay::{
     backend::input::{Device, DeviceCapability},
     desktop::utils::bbox_from_surface_tree,
     input::{
         keyboard::{LedState, XkbConfig},
-        pointer::{CursorIcon, CursorImageAttributes, CursorImageStatus},
+        pointer::{CursorImageAttributes, CursorImageStatus},
         Seat, SeatState,
     },
     output::Output,
     reexports::{input::Device as InputDevice, wayland_server::DisplayHandle},
     utils::{Buffer, IsAlive, Monotonic, Point, Rectangle, Time, Transform},
-    wayland::compositor::with_states,
+    wayland::{
+        compositor::with_states,
+        tablet_manager::{TabletDescriptor, TabletSeatTrait},
+    },
 };
 use tracing::warn;
 
@@ -69,6 +74,19 @@ impl Seats {
         self.last_active = Some(seat.clone());
     }
 
+    /// The initially created `seat-0`, which gets all unassigned devices
+    pub fn default_seat(&self) -> &Seat<State> {
+        self.seats.first().expect("No seat?")
+    }
+
+    /// Seat a device is assigned to by the `seats` config
+    pub fn assigned_seat<D: Device + 'static>(&self, config: &Config, device: &D) -> &Seat<State> {
+        <dyn Any>::downcast_ref::<InputDevice>(device)
+            .and_then(|device| config.seat_for_device(device))
+            .and_then(|name| self.iter().find(|seat| seat.name() == name))
+            .unwrap_or_else(|| self.default_seat())
+    }
+
     pub fn for_device<D: Device>(&self, device: &D) -> Option<&Seat<State>> {
         self.iter().find(|seat| {
             let userdata = seat.user_data();
@@ -171,9 +189,12 @@ pub fn create_seat(
     userdata.insert_if_missing(SupressedKeys::default);
     userdata.insert_if_missing(SupressedButtons::default);
     userdata.insert_if_missing(ModifiersShortcutQueue::default);
+    userdata.insert_if_missing(ActiveKeymap::default);
     userdata.insert_if_missing_threadsafe(SeatMoveGrabState::default);
     userdata.insert_if_missing_threadsafe(SeatMenuGrabState::default);
+    userdata.insert_if_missing_threadsafe(SeatBindingMode::default);
     userdata.insert_if_missing_threadsafe(CursorState::default);
+    userdata.insert_if_missing_threadsafe(TabletToolCursors::default);
     userdata.insert_if_missing_threadsafe(|| ActiveOutput(Mutex::new(output.clone())));
     userdata.insert_if_missing_threadsafe(|| Mutex::new(CursorImageStatus::default_named()));
 
@@ -186,7 +207,7 @@ pub fn create_seat(
     //
     // So instead of doing the right thing (and initialize these capabilities as matching
     // devices appear), we have to surrender to reality and just always expose a keyboard and pointer.
-    let conf = config.xkb_config();
+    let conf = config.xkb_config(seat.name());
     if let Err(err) = seat.add_keyboard(
         xkb_config_to_wl(&conf),
         (conf.repeat_delay as i32).abs(),
@@ -209,15 +230,74 @@ pub fn create_seat(
     seat
 }
 
+impl State {
+    /// Creates the seats of the `seats` config, that don't exist yet, and moves
+    /// devices to the seats they are assigned to.
+    ///
+    /// Seats removed from the config are kept, but lose all their devices to `seat-0`.
+    pub fn update_seats(&mut self) {
+        let Some(output) = self.common.shell.read().unwrap().outputs().next().cloned() else {
+            return;
+        };
+
+        let mut names = self
+            .common
+            .config
+            .cosmic_conf
+            .seats
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        names.sort();
+        for name in names {
+            let exists = self
+                .common
+                .shell
+                .read()
+                .unwrap()
+                .seats
+                .iter()
+                .any(|seat| seat.name() == name);
+            if !exists {
+                let seat = create_seat(
+                    &self.common.display_handle,
+                    &mut self.common.seat_state,
+                    &output,
+                    &self.common.config,
+                    name,
+                );
+                self.common.shell.write().unwrap().seats.add_seat(seat);
+            }
+        }
+
+        if let BackendData::Kms(kms) = &self.backend {
+            let shell = self.common.shell.read().unwrap();
+            for device in kms.input_devices.values() {
+                let seat = shell.seats.assigned_seat(&self.common.config, device);
+                if seat.devices().has_device(device) {
+                    continue;
+                }
+                if let Some(previous) = shell.seats.for_device(device) {
+                    previous.detach_device(device);
+                }
+                seat.attach_device(&self.common.display_handle, device);
+            }
+        }
+    }
+}
+
 pub trait SeatExt {
     fn id(&self) -> usize;
 
     fn active_output(&self) -> Output;
     fn set_active_output(&self, output: &Output);
     fn devices(&self) -> &Devices;
+    fn attach_device<D: Device + 'static>(&self, dh: &DisplayHandle, device: &D);
+    fn detach_device<D: Device>(&self, device: &D);
     fn supressed_keys(&self) -> &SupressedKeys;
     fn supressed_buttons(&self) -> &SupressedButtons;
     fn modifiers_shortcut_queue(&self) -> &ModifiersShortcutQueue;
+    fn binding_mode(&self) -> Option<String>;
 
     fn cursor_geometry(
         &self,
@@ -252,6 +332,25 @@ impl SeatExt for Seat<State> {
         self.user_data().get::<Devices>().unwrap()
     }
 
+    fn attach_device<D: Device + 'static>(&self, dh: &DisplayHandle, device: &D) {
+        self.devices().add_device(device);
+        if device.has_capability(DeviceCapability::TabletTool) {
+            self.tablet_seat()
+                .add_tablet::<State>(dh, &TabletDescriptor::from(device));
+        }
+    }
+
+    fn detach_device<D: Device>(&self, device: &D) {
+        self.devices().remove_device(device);
+        if device.has_capability(DeviceCapability::TabletTool) {
+            self.tablet_seat()
+                .remove_tablet(&TabletDescriptor::from(device));
+            if self.tablet_seat().count_tablets() == 0 {
+                self.tablet_seat().clear_tools();
+            }
+        }
+    }
+
     fn supressed_keys(&self) -> &SupressedKeys {
         self.user_data().get::<SupressedKeys>().unwrap()
     }
@@ -264,6 +363,16 @@ impl SeatExt for Seat<State> {
         self.user_data().get::<ModifiersShortcutQueue>().unwrap()
     }
 
+    fn binding_mode(&self) -> Option<String> {
+        self.user_data()
+            .get::<SeatBindingMode>()
+            .unwrap()
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|mode| mode.name.clone())
+    }
+
     fn cursor_geometry(
         &self,
         loc: impl Into<Point<f64, Buffer>>,
@@ -303,16 +412,14 @@ impl SeatExt for Seat<State> {
                 );
                 Some((buffer_geo, (hotspot.x, hotspot.y).into()))
             }
-            CursorImageStatus::Named(CursorIcon::Default) => {
+            CursorImageStatus::Named(icon) => {
                 let seat_userdata = self.user_data();
                 seat_userdata.insert_if_missing_threadsafe(CursorState::default);
                 let state = seat_userdata.get::<CursorState>().unwrap();
                 let frame = state
                     .lock()
                     .unwrap()
-                    .cursors
-                    .get(&CursorShape::Default)
-                    .unwrap()
+                    .cursor(icon)
                     .get_image(1, Into::<Duration>::into(time).as_millis() as u32);
 
                 Some((
@@ -323,10 +430,6 @@ impl SeatExt for Seat<State> {
                     (frame.xhot as i32, frame.yhot as i32).into(),
                 ))
             }
-            CursorImageStatus::Named(_) => {
-                // TODO: Handle for `cursor_shape_v1` protocol
-                None
-            }
             CursorImageStatus::Hidden => None,
         }
     }
@@ -0,0 +1,671 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+use smithay::{
+    output::Output,
+    reexports::calloop::RegistrationToken,
+    utils::{IsAlive, Rectangle},
+};
+
+use crate::{
+    shell::{
+        layout::Orientation, CosmicMapped, CosmicSurface, MinimizedState, Shell, Workspace,
+        WorkspaceDelta, WorkspaceSet,
+    },
+    state::State,
+    utils::prelude::*,
+    wayland::protocols::workspace::WorkspaceUpdateGuard,
+};
+
+/// Time given to the clients of the previous session to reappear
+const RESTORE_TIMEOUT: Duration = Duration::from_secs(60);
+/// Time during which windows may be matched by their app_id alone
+const FALLBACK_TIMEOUT: Duration = Duration::from_secs(5);
+/// Minimum time between two snapshots of the session
+const PERSIST_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Identifies a window across restarts
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct WindowIdentity {
+    pub app_id: String,
+    pub title: String,
+}
+
+impl From<&CosmicSurface> for WindowIdentity {
+    fn from(window: &CosmicSurface) -> Self {
+        WindowIdentity {
+            app_id: window.app_id(),
+            title: window.title(),
+        }
+    }
+}
+
+pub(super) fn identities(mapped: &CosmicMapped) -> Vec<WindowIdentity> {
+    mapped
+        .windows()
+        .map(|(window, _)| WindowIdentity::from(&window))
+        .collect()
+}
+
+/// Arrangement of all windows, persisted across restarts
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionState {
+    /// Workspaces by output connector name
+    pub outputs: HashMap<String, OutputSession>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutputSession {
+    #[serde(default)]
+    pub active: usize,
+    pub workspaces: Vec<WorkspaceSession>,
+    #[serde(default)]
+    pub sticky: Vec<FloatingSession>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkspaceSession {
+    pub tiling_enabled: bool,
+    #[serde(default)]
+    pub scrolling_enabled: bool,
+    #[serde(default)]
+    pub tiling: Option<TilingSession>,
+    #[serde(default)]
+    pub floating: Vec<FloatingSession>,
+    /// Minimized windows, that were tiled before
+    #[serde(default)]
+    pub minimized: Vec<Vec<WindowIdentity>>,
+}
+
+/// Tiling tree with windows identified by [`WindowIdentity`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TilingSession {
+    Group {
+        orientation: Orientation,
+        sizes: Vec<i32>,
+        children: Vec<TilingSession>,
+    },
+    /// A window or all windows of a stack
+    Window(Vec<WindowIdentity>),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FloatingSession {
+    /// The window or all windows of a stack
+    pub windows: Vec<WindowIdentity>,
+    pub position: (i32, i32),
+    pub size: (i32, i32),
+    #[serde(default)]
+    pub minimized: bool,
+}
+
+impl FloatingSession {
+    fn new(mapped: &CosmicMapped, geometry: Rectangle<i32, Local>, minimized: bool) -> Self {
+        FloatingSession {
+            windows: identities(mapped),
+            position: (geometry.loc.x, geometry.loc.y),
+            size: (geometry.size.w, geometry.size.h),
+            minimized,
+        }
+    }
+
+    fn geometry(&self) -> Rectangle<i32, Local> {
+        Rectangle::from_loc_and_size(self.position, self.size)
+    }
+}
+
+impl TilingSession {
+    fn windows(&self) -> Box<dyn Iterator<Item = &Vec<WindowIdentity>> + '_> {
+        match self {
+            TilingSession::Group { children, .. } => {
+                Box::new(children.iter().flat_map(TilingSession::windows))
+            }
+            TilingSession::Window(windows) => Box::new(std::iter::once(windows)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum RestoredLayer {
+    Tiling,
+    Floating(Rectangle<i32, Local>),
+    Sticky(Rectangle<i32, Local>),
+}
+
+/// Placement of a window of the previous session
+#[derive(Debug, Clone)]
+pub struct RestoredWindow {
+    identity: WindowIdentity,
+    pub output: String,
+    pub workspace: usize,
+    pub layer: RestoredLayer,
+    pub minimized: bool,
+    /// Other windows of the same stack
+    pub stack: Vec<WindowIdentity>,
+}
+
+impl RestoredWindow {
+    pub fn geometry(&self) -> Option<Rectangle<i32, Local>> {
+        match self.layer {
+            RestoredLayer::Floating(geo) | RestoredLayer::Sticky(geo) => Some(geo),
+            RestoredLayer::Tiling => None,
+        }
+    }
+
+    pub fn is_tiled(&self) -> bool {
+        matches!(self.layer, RestoredLayer::Tiling)
+    }
+
+    pub fn is_sticky(&self) -> bool {
+        matches!(self.layer, RestoredLayer::Sticky(_))
+    }
+}
+
+/// Result of [`SessionRestore::should_persist`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Persist {
+    /// A new snapshot should be persisted now
+    Now,
+    /// Snapshots are throttled, a new one should be persisted after the given delay
+    After(Duration),
+    /// Nothing changed or nothing may be persisted yet
+    Skip,
+}
+
+/// Places windows of the previous session back, once they reappear
+#[derive(Debug)]
+pub struct SessionRestore {
+    workspaces: HashMap<String, (usize, usize)>,
+    pending: Vec<RestoredWindow>,
+    trees: HashMap<(String, usize), TilingSession>,
+    claimed: Vec<(WindowIdentity, CosmicSurface)>,
+    started: Instant,
+    finished: bool,
+    dirty: bool,
+    last_persisted: Option<Instant>,
+    pub(super) persist_timer: Option<RegistrationToken>,
+}
+
+impl SessionRestore {
+    pub fn new(state: &SessionState) -> SessionRestore {
+        let mut workspaces = HashMap::new();
+        let mut pending = Vec::new();
+        let mut trees = HashMap::new();
+
+        let floating = |pending: &mut Vec<RestoredWindow>,
+                        output: &str,
+                        workspace: usize,
+                        window: &FloatingSession,
+                        sticky: bool| {
+            for (i, identity) in window.windows.iter().enumerate() {
+                let mut stack = window.windows.clone();
+                stack.remove(i);
+                pending.push(RestoredWindow {
+                    identity: identity.clone(),
+                    output: output.to_string(),
+                    workspace,
+                    layer: if sticky {
+                        RestoredLayer::Sticky(window.geometry())
+                    } else {
+                        RestoredLayer::Floating(window.geometry())
+                    },
+                    minimized: window.minimized,
+                    stack,
+                });
+            }
+        };
+        let tiled = |pending: &mut Vec<RestoredWindow>,
+                     output: &str,
+                     workspace: usize,
+                     windows: &Vec<WindowIdentity>,
+                     minimized: bool| {
+            for (i, identity) in windows.iter().enumerate() {
+                let mut stack = windows.clone();
+                stack.remove(i);
+                pending.push(RestoredWindow {
+                    identity: identity.clone(),
+                    output: output.to_string(),
+                    workspace,
+                    layer: RestoredLayer::Tiling,
+                    minimized,
+                    stack,
+                });
+            }
+        };
+
+        for (output, session) in state.outputs.iter() {
+            workspaces.insert(output.clone(), (session.workspaces.len(), session.active));
+            for window in session.sticky.iter() {
+                floating(&mut pending, output, session.active, window, true);
+            }
+            for (idx, workspace) in session.workspaces.iter().enumerate() {
+                if let Some(tree) = workspace.tiling.as_ref() {
+                    for windows in tree.windows() {
+                        tiled(&mut pending, output, idx, windows, false);
+                    }
+                    trees.insert((output.clone(), idx), tree.clone());
+                }
+                for windows in workspace.minimized.iter() {
+                    tiled(&mut pending, output, idx, windows, true);
+                }
+                for window in workspace.floating.iter() {
+                    floating(&mut pending, output, idx, window, false);
+                }
+            }
+        }
+
+        SessionRestore {
+            workspaces,
+            pending,
+            trees,
+            claimed: Vec::new(),
+            started: Instant::now(),
+            finished: false,
+            dirty: false,
+            last_persisted: None,
+            persist_timer: None,
+        }
+    }
+
+    /// Returns `true` while windows of the previous session are still expected
+    pub fn is_restoring(&self) -> bool {
+        !self.finished
+    }
+
+    /// Stops waiting for windows of the previous session, once all of them reappeared
+    /// or after a timeout.
+    ///
+    /// Returns `true` when the restore period ended.
+    pub fn refresh(&mut self) -> bool {
+        self.claimed.retain(|(_, window)| window.alive());
+        if !self.finished
+            && (self.pending.is_empty()
+                || Instant::now().duration_since(self.started) > RESTORE_TIMEOUT)
+        {
+            self.pending.clear();
+            self.trees.clear();
+            self.claimed.clear();
+            self.finished = true;
+            self.dirty = true;
+            return true;
+        }
+        false
+    }
+
+    /// Notes a change of the arrangement, that should be part of the next snapshot
+    pub fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+
+    /// Returns, if and when a new snapshot of the session should be persisted.
+    ///
+    /// The previous session isn't overwritten while its windows are still being restored.
+    pub fn should_persist(&mut self) -> Persist {
+        if !self.dirty || self.is_restoring() || self.persist_timer.is_some() {
+            return Persist::Skip;
+        }
+        if let Some(elapsed) = self
+            .last_persisted
+            .map(|last| last.elapsed())
+            .filter(|elapsed| *elapsed < PERSIST_INTERVAL)
+        {
+            return Persist::After(PERSIST_INTERVAL - elapsed);
+        }
+        self.last_persisted = Some(Instant::now());
+        self.dirty = false;
+        Persist::Now
+    }
+
+    /// Lifts the throttling of snapshots, once the delay returned by [`Self::should_persist`] passed
+    pub fn persist_timer_elapsed(&mut self) {
+        self.persist_timer = None;
+        self.last_persisted = None;
+    }
+
+    /// Number of workspaces and the previously active workspace of `output`
+    pub fn workspaces(&self, output: &Output) -> Option<(usize, usize)> {
+        self.workspaces.get(&output.name()).copied()
+    }
+
+    /// Claims the placement of a window of the previous session.
+    ///
+    /// Windows are matched by app_id and title. As titles tend to change between runs,
+    /// windows appearing shortly after startup fall back to the app_id alone, if only
+    /// one window of that app_id is expected.
+    pub fn claim(&mut self, window: &CosmicSurface) -> Option<RestoredWindow> {
+        let restored = self.take(&WindowIdentity::from(window))?;
+        self.claimed
+            .push((restored.identity.clone(), window.clone()));
+        Some(restored)
+    }
+
+    /// Removes the placement matching `identity` from the pending windows, see [`Self::claim`]
+    fn take(&mut self, identity: &WindowIdentity) -> Option<RestoredWindow> {
+        let pos = self.position(identity)?;
+        Some(self.pending.remove(pos))
+    }
+
+    fn position(&self, identity: &WindowIdentity) -> Option<usize> {
+        if let Some(pos) = self
+            .pending
+            .iter()
+            .position(|pending| &pending.identity == identity)
+        {
+            return Some(pos);
+        }
+        if self.started.elapsed() > FALLBACK_TIMEOUT {
+            return None;
+        }
+
+        let mut candidates = self
+            .pending
+            .iter()
+            .enumerate()
+            .filter(|(_, pending)| pending.identity.app_id == identity.app_id);
+        match (candidates.next(), candidates.next()) {
+            (Some((pos, _)), None) => Some(pos),
+            _ => None,
+        }
+    }
+
+    /// Tiling tree of the workspace a window is restored to
+    pub fn tree(&self, restored: &RestoredWindow) -> Option<&TilingSession> {
+        self.trees
+            .get(&(restored.output.clone(), restored.workspace))
+    }
+
+    /// Windows of the previous session, that already reappeared
+    pub fn claimed(&self) -> &[(WindowIdentity, CosmicSurface)] {
+        &self.claimed
+    }
+
+    /// Finds an already restored window of the given identities
+    pub fn claimed_window<'a>(
+        &'a self,
+        identities: &'a [WindowIdentity],
+    ) -> impl Iterator<Item = &'a CosmicSurface> + 'a {
+        self.claimed
+            .iter()
+            .filter(|(identity, _)| identities.contains(identity))
+            .map(|(_, window)| window)
+    }
+}
+
+impl Workspace {
+    fn session_state(&self) -> WorkspaceSession {
+        WorkspaceSession {
+            tiling_enabled: self.tiling_enabled,
+            scrolling_enabled: self.scrolling_enabled,
+            tiling: if self.scrolling_enabled {
+                self.scrolling_layer.session_state()
+            } else {
+                self.tiling_layer.session_state()
+            },
+            floating: self
+                .floating_layer
+                .mapped()
+                .filter_map(|mapped| {
+                    let geometry = self.floating_layer.element_geometry(mapped)?;
+                    Some(FloatingSession::new(mapped, geometry, false))
+                })
+                .chain(self.minimized_windows.iter().filter_map(|minimized| {
+                    match minimized.previous_state {
+                        MinimizedState::Floating { position } => Some(FloatingSession::new(
+                            &minimized.window,
+                            Rectangle::from_loc_and_size(
+                                position,
+                                minimized.window.geometry().size.as_local(),
+                            ),
+                            true,
+                        )),
+                        _ => None,
+                    }
+                }))
+                .collect(),
+            minimized: self
+                .minimized_windows
+                .iter()
+                .filter(|minimized| {
+                    matches!(minimized.previous_state, MinimizedState::Tiling { .. })
+                })
+                .map(|minimized| identities(&minimized.window))
+                .collect(),
+        }
+    }
+}
+
+impl WorkspaceSet {
+    fn session_state(&self) -> OutputSession {
+        OutputSession {
+            active: self.active,
+            workspaces: self
+                .workspaces
+                .iter()
+                .map(Workspace::session_state)
+                .collect(),
+            sticky: self
+                .sticky_layer
+                .mapped()
+                .filter_map(|mapped| {
+                    let geometry = self.sticky_layer.element_geometry(mapped)?;
+                    Some(FloatingSession::new(mapped, geometry, false))
+                })
+                .chain(self.minimized_windows.iter().filter_map(|minimized| {
+                    match minimized.previous_state {
+                        MinimizedState::Sticky { position } => Some(FloatingSession::new(
+                            &minimized.window,
+                            Rectangle::from_loc_and_size(
+                                position,
+                                minimized.window.geometry().size.as_local(),
+                            ),
+                            true,
+                        )),
+                        _ => None,
+                    }
+                }))
+                .collect(),
+        }
+    }
+}
+
+impl Shell {
+    /// Current arrangement of all windows for persistence
+    pub fn session_state(&self) -> SessionState {
+        SessionState {
+            outputs: self
+                .workspaces
+                .sets
+                .iter()
+                .map(|(output, set)| (output.name(), set.session_state()))
+                .collect(),
+        }
+    }
+
+    /// Recreates the workspaces `output` had in the previous session
+    pub fn restore_workspaces(
+        &mut self,
+        output: &Output,
+        workspace_state: &mut WorkspaceUpdateGuard<'_, State>,
+    ) {
+        if !self.session.is_restoring() {
+            return;
+        }
+        let Some((amount, active)) = self.session.workspaces(output) else {
+            return;
+        };
+        let Some(set) = self.workspaces.sets.get_mut(output) else {
+            return;
+        };
+
+        while set.workspaces.len() < amount {
+            set.add_empty_workspace(workspace_state);
+        }
+        for workspace in set.workspaces.iter_mut().take(amount) {
+            workspace.restoring = true;
+        }
+        let _ = set.activate(active, WorkspaceDelta::new_shortcut(), workspace_state);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        FloatingSession, OutputSession, Persist, SessionRestore, SessionState, TilingSession,
+        WindowIdentity, WorkspaceSession, FALLBACK_TIMEOUT, PERSIST_INTERVAL,
+    };
+    use cosmic_settings_config::shortcuts::action::Orientation;
+    use std::{collections::HashMap, time::Instant};
+
+    fn identity(app_id: &str, title: &str) -> WindowIdentity {
+        WindowIdentity {
+            app_id: app_id.into(),
+            title: title.into(),
+        }
+    }
+
+    fn floating(windows: Vec<WindowIdentity>) -> FloatingSession {
+        FloatingSession {
+            windows,
+            position: (0, 0),
+            size: (100, 100),
+            minimized: false,
+        }
+    }
+
+    fn restore() -> SessionRestore {
+        let workspace = WorkspaceSession {
+            tiling_enabled: true,
+            scrolling_enabled: false,
+            tiling: Some(TilingSession::Group {
+                orientation: Orientation::Vertical,
+                sizes: vec![50, 50],
+                children: vec![
+                    TilingSession::Window(vec![identity("term", "~")]),
+                    TilingSession::Window(vec![identity("term", "~/src")]),
+                ],
+            }),
+            floating: vec![floating(vec![
+                identity("browser", "News"),
+                identity("browser", "Mail"),
+            ])],
+            minimized: vec![vec![identity("editor", "main.rs")]],
+        };
+        let state = SessionState {
+            outputs: HashMap::from([(
+                "DP-1".to_string(),
+                OutputSession {
+                    active: 0,
+                    workspaces: vec![workspace],
+                    sticky: vec![floating(vec![identity("clock", "Clock")])],
+                },
+            )]),
+        };
+        SessionRestore::new(&state)
+    }
+
+    fn take(restore: &mut SessionRestore, identity: &WindowIdentity) -> Option<WindowIdentity> {
+        restore.take(identity).map(|restored| restored.identity)
+    }
+
+    #[test]
+    fn test_pending() {
+        let restore = restore();
+        assert_eq!(restore.pending.len(), 6);
+        assert_eq!(restore.workspaces.get("DP-1"), Some(&(1, 0)));
+
+        let browser = restore
+            .pending
+            .iter()
+            .find(|pending| pending.identity == identity("browser", "News"))
+            .unwrap();
+        assert_eq!(browser.stack, vec![identity("browser", "Mail")]);
+        assert!(!browser.is_tiled());
+
+        let editor = restore
+            .pending
+            .iter()
+            .find(|pending| pending.identity.app_id == "editor")
+            .unwrap();
+        assert!(editor.is_tiled() && editor.minimized);
+        assert!(restore.tree(editor).is_some());
+
+        let clock = restore
+            .pending
+            .iter()
+            .find(|pending| pending.identity.app_id == "clock")
+            .unwrap();
+        assert!(clock.is_sticky());
+    }
+
+    #[test]
+    fn test_exact_match() {
+        let mut restore = restore();
+        assert_eq!(
+            take(&mut restore, &identity("term", "~/src")),
+            Some(identity("term", "~/src"))
+        );
+        assert_eq!(take(&mut restore, &identity("term", "~/src")), None);
+        assert_eq!(restore.pending.len(), 5);
+    }
+
+    #[test]
+    fn test_fallback_match() {
+        let mut restore = restore();
+        // unique app_id
+        assert_eq!(
+            take(&mut restore, &identity("editor", "lib.rs")),
+            Some(identity("editor", "main.rs"))
+        );
+        // once per saved window
+        assert_eq!(take(&mut restore, &identity("editor", "main.rs")), None);
+        // ambiguous app_ids need the exact title
+        assert_eq!(take(&mut restore, &identity("term", "/tmp")), None);
+        assert_eq!(
+            take(&mut restore, &identity("term", "~")),
+            Some(identity("term", "~"))
+        );
+        // no longer ambiguous
+        assert_eq!(
+            take(&mut restore, &identity("term", "/tmp")),
+            Some(identity("term", "~/src"))
+        );
+    }
+
+    #[test]
+    fn test_fallback_timeout() {
+        let mut restore = restore();
+        restore.started = Instant::now() - FALLBACK_TIMEOUT * 2;
+        assert_eq!(take(&mut restore, &identity("clock", "12:00")), None);
+        assert_eq!(
+            take(&mut restore, &identity("clock", "Clock")),
+            Some(identity("clock", "Clock"))
+        );
+    }
+
+    #[test]
+    fn test_should_persist() {
+        let mut restore = restore();
+        assert_eq!(restore.should_persist(), Persist::Skip);
+
+        restore.pending.clear();
+        assert!(restore.refresh());
+        // the end of the restore period is persisted right away
+        assert_eq!(restore.should_persist(), Persist::Now);
+        assert_eq!(restore.should_persist(), Persist::Skip);
+
+        restore.mark_dirty();
+        match restore.should_persist() {
+            Persist::After(delay) => assert!(delay <= PERSIST_INTERVAL),
+            persist => panic!("Expected a delayed snapshot, got {:?}", persist),
+        }
+
+        restore.persist_timer_elapsed();
+        assert_eq!(restore.should_persist(), Persist::Now);
+        assert_eq!(restore.should_persist(), Persist::Skip);
+    }
+}
@@ -0,0 +1,125 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use cosmic_comp_config::window_rules::{WindowRule, X11WindowType};
+use regex::Regex;
+use smithay::xwayland::xwm::WmWindowType;
+use tracing::warn;
+
+use super::CosmicSurface;
+
+#[derive(Debug)]
+struct CompiledRule {
+    app_id: Option<Regex>,
+    title: Option<Regex>,
+    x11_class: Option<Regex>,
+    rule: WindowRule,
+}
+
+/// Window rules from the `window_rules` config key with pre-compiled expressions
+#[derive(Debug, Default)]
+pub struct WindowRules(Vec<CompiledRule>);
+
+/// Combined properties of all rules matching a window
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WindowRuleProperties {
+    pub floating: Option<bool>,
+    pub sticky: Option<bool>,
+    pub workspace: Option<u32>,
+    pub output: Option<String>,
+    pub size: Option<(i32, i32)>,
+    pub position: Option<(i32, i32)>,
+    pub maximized: Option<bool>,
+    pub fullscreen: Option<bool>,
+    pub opacity: Option<f32>,
+}
+
+fn compile(expr: &Option<String>) -> Result<Option<Regex>, regex::Error> {
+    expr.as_deref().map(Regex::new).transpose()
+}
+
+fn window_type(window_type: WmWindowType) -> X11WindowType {
+    match window_type {
+        WmWindowType::DropdownMenu => X11WindowType::DropdownMenu,
+        WmWindowType::Dialog => X11WindowType::Dialog,
+        WmWindowType::Menu => X11WindowType::Menu,
+        WmWindowType::Notification => X11WindowType::Notification,
+        WmWindowType::Normal => X11WindowType::Normal,
+        WmWindowType::PopupMenu => X11WindowType::PopupMenu,
+        WmWindowType::Splash => X11WindowType::Splash,
+        WmWindowType::Toolbar => X11WindowType::Toolbar,
+        WmWindowType::Tooltip => X11WindowType::Tooltip,
+        WmWindowType::Utility => X11WindowType::Utility,
+    }
+}
+
+impl WindowRules {
+    pub fn new(rules: &[WindowRule]) -> WindowRules {
+        WindowRules(
+            rules
+                .iter()
+                .filter_map(|rule| {
+                    let compiled = (|| {
+                        Ok::<_, regex::Error>(CompiledRule {
+                            app_id: compile(&rule.matches.app_id)?,
+                            title: compile(&rule.matches.title)?,
+                            x11_class: compile(&rule.matches.x11_class)?,
+                            rule: rule.clone(),
+                        })
+                    })();
+                    match compiled {
+                        Ok(compiled) => Some(compiled),
+                        Err(err) => {
+                            warn!(?err, ?rule, "Ignoring invalid window rule");
+                            None
+                        }
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    /// Combines the properties of all rules matching `window`, later rules taking precedence.
+    pub fn properties(&self, window: &CosmicSurface, is_dialog: bool) -> WindowRuleProperties {
+        let app_id = window.app_id();
+        let title = window.title();
+        let x11 = window.x11_surface();
+
+        let mut properties = WindowRuleProperties::default();
+        for CompiledRule {
+            app_id: app_id_expr,
+            title: title_expr,
+            x11_class,
+            rule,
+        } in &self.0
+        {
+            if app_id_expr
+                .as_ref()
+                .is_some_and(|expr| !expr.is_match(&app_id))
+                || title_expr
+                    .as_ref()
+                    .is_some_and(|expr| !expr.is_match(&title))
+                || x11_class
+                    .as_ref()
+                    .is_some_and(|expr| !x11.is_some_and(|x| expr.is_match(&x.class())))
+                || rule.matches.x11_window_type.is_some_and(|ty| {
+                    x11.and_then(|x| x.window_type()).map(window_type) != Some(ty)
+                })
+                || rule.matches.is_dialog.is_some_and(|val| val != is_dialog)
+            {
+                continue;
+            }
+
+            properties.floating = rule.floating.or(properties.floating);
+            properties.sticky = rule.sticky.or(properties.sticky);
+            properties.workspace = rule.workspace.or(properties.workspace);
+            properties.output = rule.output.clone().or(properties.output.take());
+            properties.size = rule.size.or(properties.size);
+            properties.position = rule.position.or(properties.position);
+            properties.maximized = rule.maximized.or(properties.maximized);
+            properties.fullscreen = rule.fullscreen.or(properties.fullscreen);
+            properties.opacity = rule.opacity.or(properties.opacity);
+        }
+
+        properties
+    }
+}
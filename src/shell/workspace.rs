@@ -4,7 +4,7 @@ use crate::{
         BackdropShader, SplitRenderElements,
     },
     shell::{
-        layout::{floating::FloatingLayout, tiling::TilingLayout},
+        layout::{floating::FloatingLayout, scrolling::ScrollingLayout, tiling::TilingLayout},
         OverviewMode, ANIMATION_DURATION,
     },
     state::State,
@@ -19,6 +19,7 @@ use crate::{
 };
 
 use cosmic::theme::CosmicTheme;
+use cosmic_comp_config::gaps::GapSettings;
 use cosmic_protocols::workspace::v1::server::zcosmic_workspace_handle_v1::TilingState;
 use id_tree::Tree;
 use indexmap::IndexSet;
@@ -67,7 +68,7 @@ use super::{
     },
     grabs::ResizeEdge,
     layout::tiling::{Data, MinimizedTilingState, NodeDesc},
-    CosmicMappedRenderElement, CosmicSurface, ResizeDirection, ResizeMode,
+    CosmicMappedRenderElement, CosmicSurface, Direction, ResizeDirection, ResizeMode,
 };
 
 const FULLSCREEN_ANIMATION_DURATION: Duration = Duration::from_millis(200);
@@ -76,10 +77,19 @@ const FULLSCREEN_ANIMATION_DURATION: Duration = Duration::from_millis(200);
 pub struct Workspace {
     pub output: Output,
     pub tiling_layer: TilingLayout,
+    pub scrolling_layer: ScrollingLayout,
     pub floating_layer: FloatingLayout,
     pub minimized_windows: Vec<MinimizedWindow>,
     pub tiling_enabled: bool,
+    /// Tiled windows are placed in the `scrolling_layer` instead of the `tiling_layer`
+    pub scrolling_enabled: bool,
     pub fullscreen: Option<FullscreenSurface>,
+    /// Recreated from the previous session, kept while its windows may still reappear
+    pub restoring: bool,
+    /// Name used instead of the number of the workspace
+    pub name: Option<String>,
+    /// Kept around, even if it has no windows
+    pub pinned: bool,
 
     pub handle: WorkspaceHandle,
     pub focus_stack: FocusStacks,
@@ -239,16 +249,22 @@ impl Workspace {
         theme: cosmic::Theme,
     ) -> Workspace {
         let tiling_layer = TilingLayout::new(theme.clone(), &output);
+        let scrolling_layer = ScrollingLayout::new(theme.clone(), &output);
         let floating_layer = FloatingLayout::new(theme, &output);
         let output_name = output.name();
 
         Workspace {
             output,
             tiling_layer,
+            scrolling_layer,
             floating_layer,
             tiling_enabled,
+            scrolling_enabled: false,
             minimized_windows: Vec::new(),
             fullscreen: None,
+            restoring: false,
+            name: None,
+            pinned: false,
             handle,
             focus_stack: FocusStacks::default(),
             screencopy: ScreencopySessions::default(),
@@ -272,6 +288,7 @@ impl Workspace {
 
         self.floating_layer.refresh();
         self.tiling_layer.refresh();
+        self.scrolling_layer.refresh();
 
         self.pending_tokens
             .retain(|token| xdg_activation_state.data_for_token(token).is_some());
@@ -286,6 +303,7 @@ impl Workspace {
 
     pub fn animations_going(&self) -> bool {
         self.tiling_layer.animations_going()
+            || self.scrolling_layer.animations_going()
             || self.floating_layer.animations_going()
             || self
                 .fullscreen
@@ -337,6 +355,7 @@ impl Workspace {
         }
 
         clients.extend(self.tiling_layer.update_animation_state());
+        self.scrolling_layer.update_animation_state();
         self.floating_layer.update_animation_state();
         clients
     }
@@ -347,6 +366,7 @@ impl Workspace {
 
     pub fn set_output(&mut self, output: &Output) {
         self.tiling_layer.set_output(output);
+        self.scrolling_layer.set_output(output);
         self.floating_layer.set_output(output);
         for mapped in self.mapped() {
             for (surface, _) in mapped.windows() {
@@ -373,6 +393,21 @@ impl Workspace {
         self.output = output.clone();
     }
 
+    pub fn set_gap_settings(&mut self, settings: GapSettings) {
+        if self.tiling_layer.gap_settings != settings {
+            self.tiling_layer.gap_settings = settings;
+            self.scrolling_layer.gap_settings = settings;
+            self.floating_layer.gap_settings = settings;
+            self.tiling_layer.recalculate();
+            self.scrolling_layer.recalculate();
+        }
+    }
+
+    /// Thickness of the active window hint on this workspace
+    pub fn active_hint(&self) -> u8 {
+        self.floating_layer.active_hint()
+    }
+
     pub fn preferrs_output(&self, output: &Output) -> bool {
         self.output_stack.contains(&output.name())
     }
@@ -392,7 +427,8 @@ impl Workspace {
         }
 
         let mut was_floating = self.floating_layer.unmap(&mapped).is_some();
-        let mut was_tiling = self.tiling_layer.unmap(&mapped);
+        let mut was_tiling =
+            self.tiling_layer.unmap(&mapped) || self.scrolling_layer.unmap(&mapped);
         if was_floating || was_tiling {
             assert!(was_floating != was_tiling);
         }
@@ -440,6 +476,7 @@ impl Workspace {
         self.floating_layer
             .mapped()
             .chain(self.tiling_layer.mapped().map(|(w, _)| w))
+            .chain(self.scrolling_layer.mapped().map(|(w, _)| w))
             .chain(self.minimized_windows.iter().map(|w| &w.window))
             .find(|e| e.windows().any(|(w, _)| &w == surface))
     }
@@ -448,6 +485,7 @@ impl Workspace {
         let location = location.to_local(&self.output);
         self.floating_layer
             .element_under(location)
+            .or_else(|| self.scrolling_layer.element_under(location))
             .or_else(|| self.tiling_layer.element_under(location))
     }
 
@@ -459,6 +497,7 @@ impl Workspace {
         let location = location.to_local(&self.output);
         self.floating_layer
             .surface_under(location)
+            .or_else(|| self.scrolling_layer.surface_under(location))
             .or_else(|| self.tiling_layer.surface_under(location, overview))
             .map(|(m, p)| (m, p.to_global(&self.output)))
     }
@@ -466,11 +505,13 @@ impl Workspace {
     pub fn element_geometry(&self, elem: &CosmicMapped) -> Option<Rectangle<i32, Local>> {
         self.floating_layer
             .element_geometry(elem)
+            .or_else(|| self.scrolling_layer.element_geometry(elem))
             .or_else(|| self.tiling_layer.element_geometry(elem))
     }
 
     pub fn recalculate(&mut self) {
         self.tiling_layer.recalculate();
+        self.scrolling_layer.recalculate();
         self.floating_layer.recalculate();
     }
 
@@ -494,8 +535,8 @@ impl Workspace {
                         elem.set_geometry(state.original_geometry.to_global(&self.output));
                         elem.configure();
                         self.tiling_layer.recalculate();
-                        self.tiling_layer
-                            .element_geometry(&elem)
+                        self.scrolling_layer.recalculate();
+                        self.element_geometry(&elem)
                             .map(|geo| geo.size.as_logical())
                     }
                     ManagedLayer::Sticky => unreachable!(),
@@ -558,6 +599,18 @@ impl Workspace {
                 output_geo: self.output.geometry(),
                 fullscreen,
             })
+        } else if self.scrolling_layer.mapped().any(|(m, _)| m == elem) {
+            let was_maximized = self.floating_layer.unmap(&elem).is_some();
+            self.scrolling_layer.unmap(elem);
+            Some(MinimizedWindow {
+                window: elem.clone(),
+                previous_state: MinimizedState::Tiling {
+                    tiling_state: None,
+                    was_maximized,
+                },
+                output_geo: self.output.geometry(),
+                fullscreen,
+            })
         } else {
             self.floating_layer
                 .unmap_minimize(elem, to)
@@ -599,15 +652,22 @@ impl Workspace {
             } => {
                 if self.tiling_enabled {
                     let focus_stack = self.focus_stack.get(seat);
-                    self.tiling_layer.remap_minimized(
-                        window.window.clone(),
-                        from,
-                        tiling_state,
-                        Some(focus_stack.iter()),
-                    );
+                    if self.scrolling_enabled {
+                        self.scrolling_layer.map(
+                            window.window.clone(),
+                            Some(focus_stack.iter()),
+                            None,
+                        );
+                    } else {
+                        self.tiling_layer.remap_minimized(
+                            window.window.clone(),
+                            from,
+                            tiling_state,
+                            Some(focus_stack.iter()),
+                        );
+                    }
                     if was_maximized {
-                        let previous_geometry =
-                            self.tiling_layer.element_geometry(&window.window).unwrap();
+                        let previous_geometry = self.element_geometry(&window.window).unwrap();
                         self.floating_layer
                             .map_maximized(window.window, previous_geometry, true);
                     }
@@ -735,6 +795,7 @@ impl Workspace {
             self.floating_layer.refresh();
             self.tiling_layer.recalculate();
             self.tiling_layer.refresh();
+            self.scrolling_layer.recalculate();
 
             let signal = if let Some(surface) = window.wl_surface() {
                 let signal = Arc::new(AtomicBool::new(false));
@@ -807,11 +868,11 @@ impl Workspace {
             }
         }
 
-        if !self.floating_layer.resize(focused, direction, edge, amount) {
-            self.tiling_layer.resize(focused, direction, edge, amount)
-        } else {
-            true
-        }
+        self.floating_layer.resize(focused, direction, edge, amount)
+            || self
+                .scrolling_layer
+                .resize(focused, direction, edge, amount)
+            || self.tiling_layer.resize(focused, direction, edge, amount)
     }
 
     pub fn toggle_tiling(
@@ -853,6 +914,7 @@ impl Workspace {
             for window in self
                 .tiling_layer
                 .mapped()
+                .chain(self.scrolling_layer.mapped())
                 .map(|(m, _)| m.clone())
                 .collect::<Vec<_>>()
                 .into_iter()
@@ -869,11 +931,14 @@ impl Workspace {
                         original_geometry,
                     ));
                 }
-                self.tiling_layer.unmap(&window);
+                if !self.tiling_layer.unmap(&window) {
+                    self.scrolling_layer.unmap(&window);
+                }
                 self.floating_layer.map(window, None);
             }
             workspace_state.set_workspace_tiling_state(&self.handle, TilingState::FloatingOnly);
             self.tiling_enabled = false;
+            self.scrolling_enabled = false;
         }
         for (window, original_layer, original_geometry) in maximized_windows {
             let mut state = window.maximized_state.lock().unwrap();
@@ -888,19 +953,97 @@ impl Workspace {
         }
     }
 
+    pub fn toggle_scrolling(
+        &mut self,
+        seat: &Seat<State>,
+        workspace_state: &mut WorkspaceUpdateGuard<'_, State>,
+    ) {
+        self.set_scrolling(!self.scrolling_enabled, seat, workspace_state)
+    }
+
+    /// Moves all tiled windows between the `tiling_layer` and the `scrolling_layer`.
+    ///
+    /// Enabling scrolling also enables tiling, if the workspace was floating before.
+    pub fn set_scrolling(
+        &mut self,
+        scrolling: bool,
+        seat: &Seat<State>,
+        workspace_state: &mut WorkspaceUpdateGuard<'_, State>,
+    ) {
+        if scrolling && !self.tiling_enabled {
+            self.set_tiling(true, seat, workspace_state);
+        }
+        if scrolling == self.scrolling_enabled {
+            return;
+        }
+
+        for window in self
+            .tiling_layer
+            .mapped()
+            .chain(self.scrolling_layer.mapped())
+            .map(|(m, _)| m.clone())
+            .filter(|m| m.is_maximized(false))
+            .collect::<Vec<_>>()
+            .into_iter()
+        {
+            self.unmaximize_request(&window);
+        }
+
+        let mut windows = if scrolling {
+            self.tiling_layer
+                .mapped()
+                .map(|(m, geo)| (m.clone(), geo))
+                .collect::<Vec<_>>()
+        } else {
+            self.scrolling_layer
+                .mapped()
+                .map(|(m, geo)| (m.clone(), geo))
+                .collect::<Vec<_>>()
+        };
+        // keep the windows in their visual order
+        windows.sort_by_key(|(_, geo)| (geo.loc.x, geo.loc.y));
+
+        for (window, _) in windows {
+            if scrolling {
+                self.tiling_layer.unmap(&window);
+                self.scrolling_layer
+                    .map(window, Option::<std::iter::Empty<_>>::None, None);
+            } else {
+                self.scrolling_layer.unmap(&window);
+                self.tiling_layer
+                    .map(window, Option::<std::iter::Empty<_>>::None, None);
+            }
+        }
+        self.scrolling_enabled = scrolling;
+    }
+
+    /// Maps a window into the layer currently used for tiled windows
+    pub fn map_tiled(
+        &mut self,
+        window: CosmicMapped,
+        seat: Option<&Seat<State>>,
+        direction: Option<Direction>,
+    ) {
+        let focus_stack = seat.map(|seat| self.focus_stack.get(seat));
+        if self.scrolling_enabled {
+            self.scrolling_layer
+                .map(window, focus_stack.as_ref().map(|x| x.iter()), direction);
+        } else {
+            self.tiling_layer
+                .map(window, focus_stack.as_ref().map(|x| x.iter()), direction);
+        }
+    }
+
     pub fn toggle_floating_window(&mut self, seat: &Seat<State>, window: &CosmicMapped) {
         if self.tiling_enabled {
             if window.is_maximized(false) {
                 self.unmaximize_request(window);
             }
-            if self.tiling_layer.mapped().any(|(m, _)| m == window) {
-                self.tiling_layer.unmap(window);
+            if self.tiling_layer.unmap(window) || self.scrolling_layer.unmap(window) {
                 self.floating_layer.map(window.clone(), None);
             } else if self.floating_layer.mapped().any(|w| w == window) {
-                let focus_stack = self.focus_stack.get(seat);
                 self.floating_layer.unmap(&window);
-                self.tiling_layer
-                    .map(window.clone(), Some(focus_stack.iter()), None)
+                self.map_tiled(window.clone(), Some(seat), None);
             }
         }
     }
@@ -916,6 +1059,7 @@ impl Workspace {
         self.floating_layer
             .mapped()
             .chain(self.tiling_layer.mapped().map(|(w, _)| w))
+            .chain(self.scrolling_layer.mapped().map(|(w, _)| w))
     }
 
     pub fn outputs(&self) -> impl Iterator<Item = &Output> {
@@ -925,10 +1069,16 @@ impl Workspace {
     pub fn is_empty(&self) -> bool {
         self.floating_layer.mapped().next().is_none()
             && self.tiling_layer.mapped().next().is_none()
+            && self.scrolling_layer.mapped().next().is_none()
             && self.minimized_windows.is_empty()
             && self.pending_tokens.is_empty()
     }
 
+    /// Returns `true`, if the workspace should not be removed, once it is empty
+    pub fn keep_if_empty(&self) -> bool {
+        self.pinned || self.restoring
+    }
+
     pub fn is_fullscreen(&self, mapped: &CosmicMapped) -> bool {
         self.fullscreen
             .as_ref()
@@ -954,6 +1104,7 @@ impl Workspace {
     pub fn is_tiled(&self, mapped: &CosmicMapped) -> bool {
         !self.is_fullscreen(mapped)
             && (self.tiling_layer.mapped().any(|(m, _)| m == mapped)
+                || self.scrolling_layer.mapped().any(|(m, _)| m == mapped)
                 || self.minimized_windows.iter().any(|m| {
                     &m.window == mapped && matches!(m.previous_state, MinimizedState::Tiling { .. })
                 }))
@@ -1004,6 +1155,7 @@ impl Workspace {
         overview: (OverviewMode, Option<(SwapIndicator, Option<&Tree<Data>>)>),
         resize_indicator: Option<(ResizeMode, ResizeIndicator)>,
         indicator_thickness: u8,
+        inactive_alpha: f32,
         theme: &CosmicTheme,
     ) -> Result<SplitRenderElements<WorkspaceRenderElement<R>>, OutputNotMapped>
     where
@@ -1028,13 +1180,10 @@ impl Workspace {
             let element_geo = Rectangle::from_loc_and_size(
                 self.element_for_surface(&fullscreen.surface)
                     .and_then(|elem| {
-                        self.floating_layer
-                            .element_geometry(elem)
-                            .or_else(|| self.tiling_layer.element_geometry(elem))
-                            .map(|mut geo| {
-                                geo.loc -= elem.geometry().loc.as_local();
-                                geo
-                            })
+                        self.element_geometry(elem).map(|mut geo| {
+                            geo.loc -= elem.geometry().loc.as_local();
+                            geo
+                        })
                     })
                     .unwrap_or(bbox)
                     .loc,
@@ -1155,6 +1304,21 @@ impl Workspace {
                     resize_indicator.clone(),
                     indicator_thickness,
                     alpha,
+                    inactive_alpha,
+                    theme,
+                ),
+                WorkspaceRenderElement::from,
+            );
+
+            // scrolling surfaces
+            elements.extend_map(
+                self.scrolling_layer.render::<R>(
+                    renderer,
+                    focused.as_ref(),
+                    resize_indicator.clone(),
+                    indicator_thickness,
+                    alpha,
+                    inactive_alpha,
                     theme,
                 ),
                 WorkspaceRenderElement::from,
@@ -1182,6 +1346,7 @@ impl Workspace {
                     overview,
                     resize_indicator,
                     indicator_thickness,
+                    inactive_alpha,
                     theme,
                 )?,
                 WorkspaceRenderElement::from,
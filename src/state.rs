@@ -9,12 +9,19 @@ use crate::{
     },
     config::{Config, OutputConfig, OutputState},
     input::gestures::GestureState,
-    shell::{grabs::SeatMoveGrabState, CosmicSurface, SeatExt, Shell},
+    shell::{
+        gamma::{GammaRamp, NightLight, FALLBACK_GAMMA_SIZE},
+        grabs::SeatMoveGrabState,
+        idle::IdlePolicy,
+        CosmicSurface, SeatExt, Shell,
+    },
     utils::prelude::OutputExt,
     wayland::protocols::{
         drm::WlDrmState,
+        gamma_control::GammaControlState,
         image_source::ImageSourceState,
         output_configuration::OutputConfigurationState,
+        output_power::OutputPowerState,
         screencopy::ScreencopyState,
         toplevel_info::ToplevelInfoState,
         toplevel_management::{ManagementCapabilities, ToplevelManagementState},
@@ -66,6 +73,7 @@ use smithay::{
     wayland::{
         alpha_modifier::AlphaModifierState,
         compositor::{CompositorClientState, CompositorState, SurfaceData},
+        cursor_shape::CursorShapeManagerState,
         dmabuf::{DmabufFeedback, DmabufGlobal, DmabufState},
         foreign_toplevel_list::ForeignToplevelListState,
         fractional_scale::{with_fractional_scale, FractionalScaleManagerState},
@@ -106,7 +114,7 @@ use time::UtcOffset;
 
 use std::{
     cell::RefCell,
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     ffi::OsString,
     process::Child,
     sync::{atomic::AtomicBool, Arc, Mutex, Once, RwLock},
@@ -186,6 +194,14 @@ pub struct Common {
     pub should_stop: bool,
     pub local_offset: time::UtcOffset,
     pub gesture_state: Option<GestureState>,
+    /// Whether the tablet mode switch is active
+    pub tablet_mode: bool,
+    /// Whether the lid is closed
+    pub lid_closed: bool,
+    /// Builtin output disabled by closing the lid and its previous state
+    pub lid_disabled_output: Option<(Output, OutputState)>,
+    /// Answers to shortcut inhibitor requests by app_id, if the policy is `AskOnce`
+    pub shortcuts_inhibit_decisions: HashMap<String, bool>,
 
     pub kiosk_child: Option<Child>,
     pub theme: cosmic::Theme,
@@ -209,6 +225,10 @@ pub struct Common {
     pub idle_notifier_state: IdleNotifierState<State>,
     pub idle_inhibit_manager_state: IdleInhibitManagerState,
     pub idle_inhibiting_surfaces: HashSet<WlSurface>,
+    pub idle_policy: IdlePolicy,
+    pub output_power_state: OutputPowerState,
+    pub gamma_control_state: GammaControlState,
+    pub night_light: NightLight,
     pub shm_state: ShmState,
     pub wl_drm_state: WlDrmState<Option<DrmNode>>,
     pub viewporter_state: ViewporterState,
@@ -356,10 +376,37 @@ impl BackendData {
         }
 
         loop_handle.insert_idle(|state| state.common.update_xwayland_scale());
+        loop_handle.insert_idle(|state| state.refresh_gamma());
 
         Ok(())
     }
 
+    pub fn set_output_power(&mut self, output: &Output, on: bool) {
+        match self {
+            // Nested backends cannot turn off the outputs of the host.
+            BackendData::Winit(_) | BackendData::X11(_) => {}
+            BackendData::Kms(ref mut state) => state.set_output_power(output, on),
+            _ => unreachable!("No backend was initialized"),
+        }
+    }
+
+    pub fn gamma_size(&self, output: &Output) -> Option<u32> {
+        match self {
+            // Nested backends apply the ramp while rendering.
+            BackendData::Winit(_) | BackendData::X11(_) => Some(FALLBACK_GAMMA_SIZE),
+            BackendData::Kms(ref state) => state.gamma_size(output),
+            _ => unreachable!("No backend was initialized"),
+        }
+    }
+
+    pub fn set_gamma(&mut self, output: &Output, ramp: Option<&GammaRamp>) {
+        match self {
+            BackendData::Winit(_) | BackendData::X11(_) => {}
+            BackendData::Kms(ref mut state) => state.set_gamma(output, ramp),
+            _ => unreachable!("No backend was initialized"),
+        }
+    }
+
     pub fn schedule_render(&mut self, output: &Output) {
         match self {
             BackendData::Winit(_) => {} // We cannot do this on the winit backend.
@@ -517,6 +564,7 @@ impl State {
         PointerConstraintsState::new::<Self>(&dh);
         PointerGesturesState::new::<Self>(&dh);
         TabletManagerState::new::<Self>(&dh);
+        CursorShapeManagerState::new::<Self>(&dh);
         SecurityContextState::new::<Self, _>(&dh, client_has_no_security_context);
         InputMethodManagerState::new::<Self, _>(&dh, client_is_privileged);
         TextInputManagerState::new::<Self>(&dh);
@@ -527,6 +575,8 @@ impl State {
         let idle_notifier_state = IdleNotifierState::<Self>::new(&dh, handle.clone());
         let idle_inhibit_manager_state = IdleInhibitManagerState::new::<State>(&dh);
         let idle_inhibiting_surfaces = HashSet::new();
+        let output_power_state = OutputPowerState::new::<Self, _>(dh, client_is_privileged);
+        let gamma_control_state = GammaControlState::new::<Self, _>(dh, client_is_privileged);
 
         let data_control_state = std::env::var("COSMIC_DATA_CONTROL_ENABLED")
             .is_ok_and(|value| value == "1")
@@ -584,6 +634,10 @@ impl State {
                 startup_done: Arc::new(AtomicBool::new(false)),
                 should_stop: false,
                 gesture_state: None,
+                tablet_mode: false,
+                lid_closed: false,
+                lid_disabled_output: None,
+                shortcuts_inhibit_decisions: HashMap::new(),
 
                 kiosk_child: None,
                 theme: cosmic::theme::system_preference(),
@@ -596,6 +650,10 @@ impl State {
                 idle_notifier_state,
                 idle_inhibit_manager_state,
                 idle_inhibiting_surfaces,
+                idle_policy: IdlePolicy::default(),
+                output_power_state,
+                gamma_control_state,
+                night_light: NightLight::default(),
                 image_source_state,
                 screencopy_state,
                 shm_state,
@@ -643,6 +701,11 @@ impl State {
 }
 
 impl Common {
+    /// Whether touch targets should be enlarged, because the device is in tablet mode
+    pub fn touch_friendly(&self) -> bool {
+        self.tablet_mode && self.config.cosmic_conf.switches.tablet_mode_touch_friendly
+    }
+
     pub fn update_primary_output(
         &self,
         output: &Output,
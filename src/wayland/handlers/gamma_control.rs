@@ -0,0 +1,26 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use smithay::output::Output;
+
+use crate::{
+    state::State,
+    wayland::protocols::gamma_control::{
+        delegate_gamma_control, GammaControlHandler, GammaControlState,
+    },
+};
+
+impl GammaControlHandler for State {
+    fn gamma_control_state(&mut self) -> &mut GammaControlState {
+        &mut self.common.gamma_control_state
+    }
+
+    fn gamma_size(&mut self, output: &Output) -> Option<u32> {
+        self.backend.gamma_size(output)
+    }
+
+    fn gamma_changed(&mut self, output: &Output) {
+        self.refresh_output_gamma(output)
+    }
+}
+
+delegate_gamma_control!(State);
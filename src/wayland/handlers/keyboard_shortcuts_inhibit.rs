@@ -1,12 +1,30 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
-use crate::state::State;
+use crate::{
+    fl,
+    shell::grabs::{Item, MenuGrab},
+    state::State,
+    utils::prelude::*,
+};
+use calloop::LoopHandle;
+use cosmic_comp_config::shortcuts_inhibit::ShortcutsInhibitPolicy;
 use smithay::{
     delegate_keyboard_shortcuts_inhibit,
-    wayland::keyboard_shortcuts_inhibit::{
-        KeyboardShortcutsInhibitHandler, KeyboardShortcutsInhibitState, KeyboardShortcutsInhibitor,
+    input::{
+        pointer::{Focus, GrabStartData as PointerGrabStartData},
+        Seat,
+    },
+    reexports::wayland_server::protocol::wl_surface::WlSurface,
+    utils::SERIAL_COUNTER,
+    wayland::{
+        keyboard_shortcuts_inhibit::{
+            KeyboardShortcutsInhibitHandler, KeyboardShortcutsInhibitState,
+            KeyboardShortcutsInhibitor,
+        },
+        seat::WaylandFocus,
     },
 };
+use tracing::debug;
 
 impl KeyboardShortcutsInhibitHandler for State {
     fn keyboard_shortcuts_inhibit_state(&mut self) -> &mut KeyboardShortcutsInhibitState {
@@ -14,8 +32,155 @@ impl KeyboardShortcutsInhibitHandler for State {
     }
 
     fn new_inhibitor(&mut self, inhibitor: KeyboardShortcutsInhibitor) {
-        // TODO: Restrict what apps can inhibit shortcuts
-        inhibitor.activate();
+        match self.shortcuts_inhibit_allowed(inhibitor.wl_surface()) {
+            Some(true) => inhibitor.activate(),
+            Some(false) => debug!("Denied keyboard shortcuts inhibitor."),
+            None => self.ask_shortcuts_inhibit(inhibitor.wl_surface()),
+        }
+    }
+}
+
+impl State {
+    /// Inhibitor of the focused window of `seat`, if any
+    pub fn focused_shortcuts_inhibitor(
+        &self,
+        seat: &Seat<State>,
+    ) -> Option<KeyboardShortcutsInhibitor> {
+        let shell = self.common.shell.read().unwrap();
+        let window = shell
+            .active_space(&seat.active_output())
+            .focus_stack
+            .get(seat)
+            .last()?
+            .wl_surface()?;
+        seat.keyboard_shortcuts_inhibitor_for_surface(&window)
+    }
+
+    /// Escape binding of shortcut inhibitors: Deactivates the inhibitor of the focused window
+    /// or activates it again, if the policy allows it.
+    pub fn toggle_shortcuts_inhibitor(&mut self, seat: &Seat<State>) {
+        let Some(inhibitor) = self.focused_shortcuts_inhibitor(seat) else {
+            return;
+        };
+
+        if inhibitor.is_active() {
+            inhibitor.inactivate();
+        } else {
+            match self.shortcuts_inhibit_allowed(inhibitor.wl_surface()) {
+                Some(true) => inhibitor.activate(),
+                Some(false) => {}
+                None => self.ask_shortcuts_inhibit(inhibitor.wl_surface()),
+            }
+        }
+    }
+
+    /// Whether the client of `surface` may inhibit shortcuts, `None` if the user needs to be asked
+    fn shortcuts_inhibit_allowed(&self, surface: &WlSurface) -> Option<bool> {
+        let app_id = self.shortcuts_inhibit_app_id(surface);
+        match &self.common.config.cosmic_conf.shortcuts_inhibit.policy {
+            ShortcutsInhibitPolicy::AllowAll => Some(true),
+            ShortcutsInhibitPolicy::AllowList(app_ids) => {
+                Some(app_id.is_some_and(|app_id| app_ids.contains(&app_id)))
+            }
+            ShortcutsInhibitPolicy::AskOnce => match app_id {
+                Some(app_id) => self
+                    .common
+                    .shortcuts_inhibit_decisions
+                    .get(&app_id)
+                    .copied(),
+                None => Some(false),
+            },
+        }
+    }
+
+    fn shortcuts_inhibit_app_id(&self, surface: &WlSurface) -> Option<String> {
+        let shell = self.common.shell.read().unwrap();
+        let mapped = shell.element_for_surface(surface)?;
+        mapped
+            .windows()
+            .find(|(w, _)| w.wl_surface().as_deref() == Some(surface))
+            .map(|(w, _)| w.app_id())
+    }
+
+    /// Opens a menu on top of the window of `surface`, asking if it may inhibit shortcuts
+    fn ask_shortcuts_inhibit(&mut self, surface: &WlSurface) {
+        let Some(app_id) = self.shortcuts_inhibit_app_id(surface) else {
+            return;
+        };
+
+        let shell = self.common.shell.read().unwrap();
+        let seat = shell.seats.last_active().clone();
+        let pointer = seat.get_pointer().unwrap();
+        if pointer.is_grabbed() {
+            // the user can still answer later by using the escape binding
+            return;
+        }
+        let Some(geometry) = shell
+            .element_for_surface(surface)
+            .and_then(|mapped| shell.global_element_geometry(mapped))
+        else {
+            return;
+        };
+
+        let answer = |allow: bool| {
+            let app_id = app_id.clone();
+            let surface = surface.clone();
+            move |handle: &LoopHandle<'_, State>| {
+                let app_id = app_id.clone();
+                let surface = surface.clone();
+                let _ = handle.insert_idle(move |state| {
+                    state
+                        .common
+                        .shortcuts_inhibit_decisions
+                        .insert(app_id, allow);
+                    if allow {
+                        let seats = state
+                            .common
+                            .shell
+                            .read()
+                            .unwrap()
+                            .seats
+                            .iter()
+                            .cloned()
+                            .collect::<Vec<_>>();
+                        for seat in seats {
+                            if let Some(inhibitor) =
+                                seat.keyboard_shortcuts_inhibitor_for_surface(&surface)
+                            {
+                                inhibitor.activate();
+                            }
+                        }
+                    }
+                });
+            }
+        };
+        let items = vec![
+            Item::new(
+                fl!("shortcuts-inhibit-allow", app = app_id.as_str()),
+                answer(true),
+            ),
+            Item::new(
+                fl!("shortcuts-inhibit-deny", app = app_id.as_str()),
+                answer(false),
+            ),
+        ];
+
+        let start_data = PointerGrabStartData {
+            focus: None,
+            button: 0x110, // BTN_LEFT
+            location: pointer.current_location(),
+        };
+        let grab = MenuGrab::new(
+            start_data,
+            &seat,
+            items.into_iter(),
+            geometry.loc + geometry.size.downscale(2).to_point(),
+            self.common.event_loop_handle.clone(),
+            self.common.theme.clone(),
+        );
+        std::mem::drop(shell);
+
+        pointer.set_grab(self, grab, SERIAL_COUNTER.next_serial(), Focus::Keep);
     }
 }
 
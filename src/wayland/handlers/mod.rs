@@ -3,6 +3,7 @@
 pub mod alpha_modifier;
 pub mod buffer;
 pub mod compositor;
+pub mod cursor_shape;
 pub mod data_control;
 pub mod data_device;
 pub mod decoration;
@@ -11,6 +12,7 @@ pub mod drm;
 pub mod drm_lease;
 pub mod foreign_toplevel_list;
 pub mod fractional_scale;
+pub mod gamma_control;
 pub mod idle_inhibit;
 pub mod idle_notify;
 pub mod image_source;
@@ -19,6 +21,7 @@ pub mod keyboard_shortcuts_inhibit;
 pub mod layer_shell;
 pub mod output;
 pub mod output_configuration;
+pub mod output_power;
 pub mod pointer_constraints;
 pub mod pointer_gestures;
 pub mod presentation;
@@ -0,0 +1,27 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use smithay::output::Output;
+
+use crate::{
+    shell::idle::OutputPower,
+    state::State,
+    wayland::protocols::output_power::{
+        delegate_output_power, OutputPowerHandler, OutputPowerState,
+    },
+};
+
+impl OutputPowerHandler for State {
+    fn output_power_state(&mut self) -> &mut OutputPowerState {
+        &mut self.common.output_power_state
+    }
+
+    fn get_dpms(&mut self, output: &Output) -> bool {
+        OutputPower::get(output).is_on()
+    }
+
+    fn set_dpms(&mut self, output: &Output, on: bool) {
+        self.set_output_power(output, on)
+    }
+}
+
+delegate_output_power!(State);
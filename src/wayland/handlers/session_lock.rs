@@ -1,16 +1,22 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
-use crate::{shell::SessionLock, state::State, utils::prelude::*};
+use crate::{
+    shell::{element::lock_fallback::lock_fallback, SessionLock},
+    state::State,
+    utils::prelude::*,
+};
 use smithay::{
     delegate_session_lock,
+    desktop::space::SpaceElement,
     output::Output,
     reexports::wayland_server::{protocol::wl_output::WlOutput, Resource},
-    utils::Size,
+    utils::{IsAlive, Size},
     wayland::session_lock::{
         LockSurface, SessionLockHandler, SessionLockManagerState, SessionLocker,
     },
 };
 use std::collections::HashMap;
+use tracing::{info, warn};
 
 impl SessionLockHandler for State {
     fn lock_state(&mut self) -> &mut SessionLockManagerState {
@@ -20,23 +26,26 @@ impl SessionLockHandler for State {
     fn lock(&mut self, locker: SessionLocker) {
         let mut shell = self.common.shell.write().unwrap();
 
-        // Reject lock if sesion lock exists and is still valid
+        // Reject lock if sesion lock exists and is still valid,
+        // otherwise the new client takes over the existing lock.
         if let Some(session_lock) = shell.session_lock.as_ref() {
-            if self
-                .common
-                .display_handle
-                .get_client(session_lock.ext_session_lock.id())
-                .is_ok()
-            {
+            if session_lock.ext_session_lock.alive() {
                 return;
             }
+            info!("Lock client taking over the existing session lock.");
         }
 
         let ext_session_lock = locker.ext_session_lock().clone();
         locker.lock();
+        let fallback = shell
+            .session_lock
+            .take()
+            .map(|session_lock| session_lock.fallback)
+            .unwrap_or_default();
         shell.session_lock = Some(SessionLock {
             ext_session_lock,
             surfaces: HashMap::new(),
+            fallback,
         });
 
         for output in shell.outputs() {
@@ -70,4 +79,46 @@ impl SessionLockHandler for State {
     }
 }
 
+impl State {
+    /// Shows a fallback screen on every output, while the lock client is gone.
+    ///
+    /// The session stays locked until another client takes over the lock and unlocks it.
+    pub fn refresh_session_lock(&mut self) {
+        let mut shell = self.common.shell.write().unwrap();
+        let outputs = shell.outputs().cloned().collect::<Vec<_>>();
+        let Some(session_lock) = shell.session_lock.as_mut() else {
+            return;
+        };
+        if session_lock.ext_session_lock.alive() {
+            return;
+        }
+
+        session_lock.surfaces.clear();
+        session_lock
+            .fallback
+            .retain(|output, _| outputs.contains(output));
+        for output in outputs {
+            let size = output.geometry().as_logical().size;
+            match session_lock.fallback.get(&output) {
+                Some(fallback) if fallback.bbox().size == size => continue,
+                Some(fallback) => fallback.resize(size),
+                None => {
+                    if session_lock.fallback.is_empty() {
+                        warn!("Lock client died, showing fallback lock screen.");
+                    }
+                    session_lock.fallback.insert(
+                        output.clone(),
+                        lock_fallback(
+                            size,
+                            self.common.event_loop_handle.clone(),
+                            self.common.theme.clone(),
+                        ),
+                    );
+                }
+            }
+            self.backend.schedule_render(&output);
+        }
+    }
+}
+
 delegate_session_lock!(State);
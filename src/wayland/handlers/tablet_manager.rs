@@ -1,14 +1,25 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
-use crate::state::State;
+use crate::{backend::render::cursor::TabletToolCursors, state::State};
 use smithay::{
     backend::input::TabletToolDescriptor, delegate_tablet_manager,
     input::pointer::CursorImageStatus, wayland::tablet_manager::TabletSeatHandler,
 };
 
 impl TabletSeatHandler for State {
-    fn tablet_tool_image(&mut self, _tool: &TabletToolDescriptor, _image: CursorImageStatus) {
-        // TODO display cursor for each tablet tool
+    fn tablet_tool_image(&mut self, tool: &TabletToolDescriptor, image: CursorImageStatus) {
+        for seat in self.common.shell.read().unwrap().seats.iter() {
+            let mut tools = seat
+                .user_data()
+                .get::<TabletToolCursors>()
+                .unwrap()
+                .lock()
+                .unwrap();
+            if let Some(cursor) = tools.get_mut(tool) {
+                cursor.image = image;
+                return;
+            }
+        }
     }
 }
 
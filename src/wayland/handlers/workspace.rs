@@ -28,6 +28,7 @@ impl WorkspaceHandler for State {
     }
 
     fn commit_requests(&mut self, _dh: &DisplayHandle, requests: Vec<Request>) {
+        let mut persist = false;
         for request in requests.into_iter() {
             match request {
                 Request::Activate(handle) => {
@@ -64,9 +65,45 @@ impl WorkspaceHandler for State {
                         );
                     }
                 }
-                _ => {}
+                Request::Deactivate(handle) => {
+                    let mut shell = self.common.shell.write().unwrap();
+                    shell.deactivate_workspace(&handle, &mut self.common.workspace_state.update());
+                }
+                Request::Create { in_group, name } => {
+                    let mut shell = self.common.shell.write().unwrap();
+                    // the protocol doesn't carry a position for new workspaces
+                    shell.create_workspace(
+                        &in_group,
+                        name,
+                        None,
+                        &mut self.common.workspace_state.update(),
+                    );
+                    persist = true;
+                }
+                Request::Rename { workspace, name } => {
+                    let mut shell = self.common.shell.write().unwrap();
+                    shell.rename_workspace(
+                        &workspace,
+                        name,
+                        &mut self.common.workspace_state.update(),
+                    );
+                    persist = true;
+                }
+                Request::Remove(handle) => {
+                    let mut shell = self.common.shell.write().unwrap();
+                    shell.remove_workspace(
+                        &handle,
+                        &mut self.common.workspace_state.update(),
+                        &self.common.xdg_activation_state,
+                    );
+                    persist = true;
+                }
             }
         }
+
+        if persist {
+            self.common.persist_workspaces();
+        }
     }
 }
 
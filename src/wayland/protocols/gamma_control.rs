@@ -0,0 +1,267 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{fs::File, io::Read, os::fd::AsRawFd};
+
+use smithay::{
+    output::Output,
+    reexports::{
+        wayland_protocols_wlr::gamma_control::v1::server::{
+            zwlr_gamma_control_manager_v1::{self, ZwlrGammaControlManagerV1},
+            zwlr_gamma_control_v1::{self, ZwlrGammaControlV1},
+        },
+        wayland_server::{
+            backend::ClientId, Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New,
+            Resource,
+        },
+    },
+};
+use tracing::warn;
+
+#[derive(Debug)]
+pub struct GammaControlState {
+    /// Gamma controls by output, every output may only be controlled by one client at a time
+    instances: Vec<GammaControl>,
+}
+
+#[derive(Debug)]
+struct GammaControl {
+    instance: ZwlrGammaControlV1,
+    output: Output,
+    size: u32,
+    ramp: Option<Vec<u16>>,
+}
+
+pub trait GammaControlHandler {
+    fn gamma_control_state(&mut self) -> &mut GammaControlState;
+
+    /// Number of entries per color channel of the gamma ramp of `output`, if it has one
+    fn gamma_size(&mut self, output: &Output) -> Option<u32>;
+    /// The gamma ramp set by a client for `output` changed or was reset
+    fn gamma_changed(&mut self, output: &Output);
+}
+
+pub struct GammaControlManagerGlobalData {
+    filter: Box<dyn for<'a> Fn(&'a Client) -> bool + Send + Sync>,
+}
+
+impl GammaControlState {
+    pub fn new<D, F>(dh: &DisplayHandle, client_filter: F) -> GammaControlState
+    where
+        D: GlobalDispatch<ZwlrGammaControlManagerV1, GammaControlManagerGlobalData>
+            + Dispatch<ZwlrGammaControlManagerV1, ()>
+            + Dispatch<ZwlrGammaControlV1, ()>
+            + GammaControlHandler
+            + 'static,
+        F: for<'a> Fn(&'a Client) -> bool + Send + Sync + 'static,
+    {
+        dh.create_global::<D, ZwlrGammaControlManagerV1, _>(
+            1,
+            GammaControlManagerGlobalData {
+                filter: Box::new(client_filter),
+            },
+        );
+        GammaControlState {
+            instances: Vec::new(),
+        }
+    }
+
+    /// Gamma ramp set by a client for `output`, the red, green and blue channels one after another
+    pub fn gamma(&self, output: &Output) -> Option<&[u16]> {
+        self.instances
+            .iter()
+            .find(|control| &control.output == output)
+            .and_then(|control| control.ramp.as_deref())
+    }
+
+    /// Invalidates the controls of a removed output
+    pub fn output_removed(&mut self, output: &Output) {
+        self.instances.retain(|control| {
+            if &control.output == output {
+                control.instance.failed();
+                false
+            } else {
+                true
+            }
+        });
+    }
+}
+
+impl<D> GlobalDispatch<ZwlrGammaControlManagerV1, GammaControlManagerGlobalData, D>
+    for GammaControlState
+where
+    D: GlobalDispatch<ZwlrGammaControlManagerV1, GammaControlManagerGlobalData>
+        + Dispatch<ZwlrGammaControlManagerV1, ()>
+        + Dispatch<ZwlrGammaControlV1, ()>
+        + GammaControlHandler
+        + 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _handle: &DisplayHandle,
+        _client: &Client,
+        resource: New<ZwlrGammaControlManagerV1>,
+        _global_data: &GammaControlManagerGlobalData,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        data_init.init(resource, ());
+    }
+
+    fn can_view(client: Client, global_data: &GammaControlManagerGlobalData) -> bool {
+        (global_data.filter)(&client)
+    }
+}
+
+impl<D> Dispatch<ZwlrGammaControlManagerV1, (), D> for GammaControlState
+where
+    D: GlobalDispatch<ZwlrGammaControlManagerV1, GammaControlManagerGlobalData>
+        + Dispatch<ZwlrGammaControlManagerV1, ()>
+        + Dispatch<ZwlrGammaControlV1, ()>
+        + GammaControlHandler
+        + 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        _resource: &ZwlrGammaControlManagerV1,
+        request: zwlr_gamma_control_manager_v1::Request,
+        _data: &(),
+        _dh: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zwlr_gamma_control_manager_v1::Request::GetGammaControl { id, output } => {
+                let instance = data_init.init(id, ());
+                let Some(output) = Output::from_resource(&output) else {
+                    instance.failed();
+                    return;
+                };
+                let Some(size) = state.gamma_size(&output) else {
+                    instance.failed();
+                    return;
+                };
+
+                let gamma_state = state.gamma_control_state();
+                if gamma_state
+                    .instances
+                    .iter()
+                    .any(|control| control.output == output)
+                {
+                    // already controlled by another client
+                    instance.failed();
+                    return;
+                }
+                instance.gamma_size(size);
+                gamma_state.instances.push(GammaControl {
+                    instance,
+                    output,
+                    size,
+                    ramp: None,
+                });
+            }
+            zwlr_gamma_control_manager_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<ZwlrGammaControlV1, (), D> for GammaControlState
+where
+    D: GlobalDispatch<ZwlrGammaControlManagerV1, GammaControlManagerGlobalData>
+        + Dispatch<ZwlrGammaControlManagerV1, ()>
+        + Dispatch<ZwlrGammaControlV1, ()>
+        + GammaControlHandler
+        + 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        resource: &ZwlrGammaControlV1,
+        request: zwlr_gamma_control_v1::Request,
+        _data: &(),
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zwlr_gamma_control_v1::Request::SetGamma { fd } => {
+                // failed controls are inert
+                let gamma_state = state.gamma_control_state();
+                let Some(pos) = gamma_state
+                    .instances
+                    .iter()
+                    .position(|control| &control.instance == resource)
+                else {
+                    return;
+                };
+
+                // never wait for the client, like wlroots the whole ramp has to be readable at once
+                unsafe {
+                    let flags = libc::fcntl(fd.as_raw_fd(), libc::F_GETFL);
+                    if flags != -1 {
+                        libc::fcntl(fd.as_raw_fd(), libc::F_SETFL, flags | libc::O_NONBLOCK);
+                    }
+                }
+
+                let mut ramp = vec![0u16; gamma_state.instances[pos].size as usize * 3];
+                let buf = bytemuck::cast_slice_mut::<u16, u8>(&mut ramp);
+                match File::from(fd).read(buf) {
+                    Ok(len) if len == buf.len() => {}
+                    Ok(_) => {
+                        resource.post_error(
+                            zwlr_gamma_control_v1::Error::InvalidGamma,
+                            "Gamma ramp has the wrong size",
+                        );
+                        return;
+                    }
+                    Err(err) => {
+                        warn!(?err, "Failed to read gamma ramp.");
+                        let control = gamma_state.instances.remove(pos);
+                        control.instance.failed();
+                        if control.ramp.is_some() {
+                            state.gamma_changed(&control.output);
+                        }
+                        return;
+                    }
+                }
+
+                let control = &mut gamma_state.instances[pos];
+                control.ramp = Some(ramp);
+                let output = control.output.clone();
+                state.gamma_changed(&output);
+            }
+            zwlr_gamma_control_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(state: &mut D, _client: ClientId, resource: &ZwlrGammaControlV1, _data: &()) {
+        let gamma_state = state.gamma_control_state();
+        let Some(pos) = gamma_state
+            .instances
+            .iter()
+            .position(|control| &control.instance == resource)
+        else {
+            return;
+        };
+
+        // restore the original gamma ramp
+        let control = gamma_state.instances.remove(pos);
+        if control.ramp.is_some() {
+            state.gamma_changed(&control.output);
+        }
+    }
+}
+
+macro_rules! delegate_gamma_control {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        smithay::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols_wlr::gamma_control::v1::server::zwlr_gamma_control_manager_v1::ZwlrGammaControlManagerV1: $crate::wayland::protocols::gamma_control::GammaControlManagerGlobalData
+        ] => $crate::wayland::protocols::gamma_control::GammaControlState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols_wlr::gamma_control::v1::server::zwlr_gamma_control_manager_v1::ZwlrGammaControlManagerV1: ()
+        ] => $crate::wayland::protocols::gamma_control::GammaControlState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols_wlr::gamma_control::v1::server::zwlr_gamma_control_v1::ZwlrGammaControlV1: ()
+        ] => $crate::wayland::protocols::gamma_control::GammaControlState);
+    };
+}
+pub(crate) use delegate_gamma_control;
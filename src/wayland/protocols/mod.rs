@@ -1,8 +1,10 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
 pub mod drm;
+pub mod gamma_control;
 pub mod image_source;
 pub mod output_configuration;
+pub mod output_power;
 pub mod screencopy;
 pub mod toplevel_info;
 pub mod toplevel_management;
@@ -0,0 +1,215 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use smithay::{
+    output::Output,
+    reexports::{
+        wayland_protocols_wlr::output_power_management::v1::server::{
+            zwlr_output_power_manager_v1::{self, ZwlrOutputPowerManagerV1},
+            zwlr_output_power_v1::{self, Mode, ZwlrOutputPowerV1},
+        },
+        wayland_server::{
+            backend::ClientId, Client, DataInit, Dispatch, DisplayHandle, GlobalDispatch, New,
+            Resource, WEnum,
+        },
+    },
+};
+
+#[derive(Debug)]
+pub struct OutputPowerState {
+    /// Power controls by output, every output may only be controlled by one client at a time
+    instances: Vec<(ZwlrOutputPowerV1, Output)>,
+}
+
+pub trait OutputPowerHandler {
+    fn output_power_state(&mut self) -> &mut OutputPowerState;
+
+    fn get_dpms(&mut self, output: &Output) -> bool;
+    fn set_dpms(&mut self, output: &Output, on: bool);
+}
+
+pub struct OutputPowerManagerGlobalData {
+    filter: Box<dyn for<'a> Fn(&'a Client) -> bool + Send + Sync>,
+}
+
+impl OutputPowerState {
+    pub fn new<D, F>(dh: &DisplayHandle, client_filter: F) -> OutputPowerState
+    where
+        D: GlobalDispatch<ZwlrOutputPowerManagerV1, OutputPowerManagerGlobalData>
+            + Dispatch<ZwlrOutputPowerManagerV1, ()>
+            + Dispatch<ZwlrOutputPowerV1, ()>
+            + OutputPowerHandler
+            + 'static,
+        F: for<'a> Fn(&'a Client) -> bool + Send + Sync + 'static,
+    {
+        dh.create_global::<D, ZwlrOutputPowerManagerV1, _>(
+            1,
+            OutputPowerManagerGlobalData {
+                filter: Box::new(client_filter),
+            },
+        );
+        OutputPowerState {
+            instances: Vec::new(),
+        }
+    }
+
+    /// Notifies the client controlling `output` about a power change
+    pub fn output_power_changed(&self, output: &Output, on: bool) {
+        for (instance, _) in self.instances.iter().filter(|(_, o)| o == output) {
+            instance.mode(mode(on));
+        }
+    }
+
+    /// Invalidates the controls of a removed output
+    pub fn output_removed(&mut self, output: &Output) {
+        self.instances.retain(|(instance, o)| {
+            if o == output {
+                instance.failed();
+                false
+            } else {
+                true
+            }
+        });
+    }
+}
+
+fn mode(on: bool) -> Mode {
+    if on {
+        Mode::On
+    } else {
+        Mode::Off
+    }
+}
+
+impl<D> GlobalDispatch<ZwlrOutputPowerManagerV1, OutputPowerManagerGlobalData, D>
+    for OutputPowerState
+where
+    D: GlobalDispatch<ZwlrOutputPowerManagerV1, OutputPowerManagerGlobalData>
+        + Dispatch<ZwlrOutputPowerManagerV1, ()>
+        + Dispatch<ZwlrOutputPowerV1, ()>
+        + OutputPowerHandler
+        + 'static,
+{
+    fn bind(
+        _state: &mut D,
+        _handle: &DisplayHandle,
+        _client: &Client,
+        resource: New<ZwlrOutputPowerManagerV1>,
+        _global_data: &OutputPowerManagerGlobalData,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        data_init.init(resource, ());
+    }
+
+    fn can_view(client: Client, global_data: &OutputPowerManagerGlobalData) -> bool {
+        (global_data.filter)(&client)
+    }
+}
+
+impl<D> Dispatch<ZwlrOutputPowerManagerV1, (), D> for OutputPowerState
+where
+    D: GlobalDispatch<ZwlrOutputPowerManagerV1, OutputPowerManagerGlobalData>
+        + Dispatch<ZwlrOutputPowerManagerV1, ()>
+        + Dispatch<ZwlrOutputPowerV1, ()>
+        + OutputPowerHandler
+        + 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        _resource: &ZwlrOutputPowerManagerV1,
+        request: zwlr_output_power_manager_v1::Request,
+        _data: &(),
+        _dh: &DisplayHandle,
+        data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zwlr_output_power_manager_v1::Request::GetOutputPower { id, output } => {
+                let instance = data_init.init(id, ());
+                let Some(output) = Output::from_resource(&output) else {
+                    instance.failed();
+                    return;
+                };
+
+                let power_state = state.output_power_state();
+                if power_state.instances.iter().any(|(_, o)| o == &output) {
+                    // already controlled by another client
+                    instance.failed();
+                    return;
+                }
+                power_state
+                    .instances
+                    .push((instance.clone(), output.clone()));
+                instance.mode(mode(state.get_dpms(&output)));
+            }
+            zwlr_output_power_manager_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<D> Dispatch<ZwlrOutputPowerV1, (), D> for OutputPowerState
+where
+    D: GlobalDispatch<ZwlrOutputPowerManagerV1, OutputPowerManagerGlobalData>
+        + Dispatch<ZwlrOutputPowerManagerV1, ()>
+        + Dispatch<ZwlrOutputPowerV1, ()>
+        + OutputPowerHandler
+        + 'static,
+{
+    fn request(
+        state: &mut D,
+        _client: &Client,
+        resource: &ZwlrOutputPowerV1,
+        request: zwlr_output_power_v1::Request,
+        _data: &(),
+        _dh: &DisplayHandle,
+        _data_init: &mut DataInit<'_, D>,
+    ) {
+        match request {
+            zwlr_output_power_v1::Request::SetMode { mode } => {
+                // failed controls are inert
+                let Some(output) = state
+                    .output_power_state()
+                    .instances
+                    .iter()
+                    .find(|(instance, _)| instance == resource)
+                    .map(|(_, output)| output.clone())
+                else {
+                    return;
+                };
+
+                match mode {
+                    WEnum::Value(Mode::On) => state.set_dpms(&output, true),
+                    WEnum::Value(Mode::Off) => state.set_dpms(&output, false),
+                    _ => resource.post_error(
+                        zwlr_output_power_v1::Error::InvalidMode,
+                        "Invalid power mode",
+                    ),
+                }
+            }
+            zwlr_output_power_v1::Request::Destroy => {}
+            _ => unreachable!(),
+        }
+    }
+
+    fn destroyed(state: &mut D, _client: ClientId, resource: &ZwlrOutputPowerV1, _data: &()) {
+        state
+            .output_power_state()
+            .instances
+            .retain(|(instance, _)| instance != resource);
+    }
+}
+
+macro_rules! delegate_output_power {
+    ($(@<$( $lt:tt $( : $clt:tt $(+ $dlt:tt )* )? ),+>)? $ty: ty) => {
+        smithay::reexports::wayland_server::delegate_global_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols_wlr::output_power_management::v1::server::zwlr_output_power_manager_v1::ZwlrOutputPowerManagerV1: $crate::wayland::protocols::output_power::OutputPowerManagerGlobalData
+        ] => $crate::wayland::protocols::output_power::OutputPowerState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols_wlr::output_power_management::v1::server::zwlr_output_power_manager_v1::ZwlrOutputPowerManagerV1: ()
+        ] => $crate::wayland::protocols::output_power::OutputPowerState);
+        smithay::reexports::wayland_server::delegate_dispatch!($(@< $( $lt $( : $clt $(+ $dlt )* )? ),+ >)? $ty: [
+            smithay::reexports::wayland_protocols_wlr::output_power_management::v1::server::zwlr_output_power_v1::ZwlrOutputPowerV1: ()
+        ] => $crate::wayland::protocols::output_power::OutputPowerState);
+    };
+}
+pub(crate) use delegate_output_power;